@@ -0,0 +1,108 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use iced::{Font, Pixels, Point, Rectangle, Renderer, Size, mouse};
+use juicy_lemon::cache::CandleCache;
+use juicy_lemon::candle::Candle;
+use juicy_lemon::graph::chart::{CandleChart, ChartCaches};
+use juicy_lemon::theme::DARK;
+use rust_decimal::Decimal;
+use std::collections::VecDeque;
+
+const CANDLES: u64 = 10_000;
+const SYMBOL: &str = "BTCUSDT";
+const TIMEFRAME: &str = "1m";
+
+/// Contiguous 1m bars whose prices wander between 98 and 108.
+fn candles() -> VecDeque<Candle> {
+    (0..CANDLES)
+        .map(|i| {
+            let open = Decimal::from(100 + i % 7);
+            let close = open + Decimal::from(i % 3) - Decimal::ONE;
+
+            Candle {
+                open_time: i * 60_000,
+                close_time: (i + 1) * 60_000 - 1,
+                open,
+                high: open.max(close) + Decimal::ONE,
+                low: open.min(close) - Decimal::ONE,
+                close,
+                volume: Decimal::from(i),
+            }
+        })
+        .collect()
+}
+
+fn draw(renderer: &Renderer, cache: &CandleCache, caches: &ChartCaches, cursor: mouse::Cursor) {
+    let bounds = Rectangle::new(Point::ORIGIN, Size::new(1600.0, 900.0));
+
+    CandleChart {
+        candles: cache.get(SYMBOL, TIMEFRAME).unwrap(),
+        plot: cache.plot(SYMBOL, TIMEFRAME),
+        visible: CANDLES as usize,
+        decimals: 2,
+        timeframe: TIMEFRAME,
+        profiles: None,
+        paper: None,
+        backtest: None,
+        caches,
+        palette: DARK,
+    }
+    .draw(renderer, bounds, cursor);
+}
+
+/// Frame build time of 10k candles, per layer that changed since the last frame.
+fn frame_build(c: &mut Criterion) {
+    let renderer =
+        Renderer::Secondary(iced_tiny_skia::Renderer::new(Font::default(), Pixels(16.0)));
+    let mut cache = CandleCache::new(usize::MAX);
+    cache.insert(SYMBOL, TIMEFRAME, candles());
+    let mut group = c.benchmark_group("chart_frame_build");
+
+    group.bench_function("all_layers", |b| {
+        b.iter(|| {
+            draw(
+                &renderer,
+                &cache,
+                &ChartCaches::default(),
+                mouse::Cursor::Unavailable,
+            )
+        })
+    });
+
+    let caches = ChartCaches::default();
+    draw(&renderer, &cache, &caches, mouse::Cursor::Unavailable);
+    group.bench_function("unchanged", |b| {
+        b.iter(|| draw(&renderer, &cache, &caches, mouse::Cursor::Unavailable))
+    });
+
+    let mut x = 0.0;
+    group.bench_function("crosshair_moved", |b| {
+        b.iter(|| {
+            x = (x + 1.0) % 1000.0;
+            let cursor = mouse::Cursor::Available(Point::new(100.0 + x, 300.0));
+            draw(&renderer, &cache, &caches, cursor)
+        })
+    });
+
+    let mut last = cache
+        .get(SYMBOL, TIMEFRAME)
+        .unwrap()
+        .back()
+        .unwrap()
+        .clone();
+    let mut tick = 0;
+    group.bench_function("forming_candle_tick", |b| {
+        b.iter(|| {
+            tick += 1;
+            last.close = last.low + Decimal::from(tick % 5);
+            cache
+                .merge(SYMBOL, TIMEFRAME, &[last.clone()], CANDLES as usize)
+                .unwrap();
+            draw(&renderer, &cache, &caches, mouse::Cursor::Unavailable)
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, frame_build);
+criterion_main!(benches);
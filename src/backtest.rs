@@ -0,0 +1,588 @@
+use crate::candle::Candle;
+use crate::dataset::Indicator;
+use crate::paper::{OrderKind, OrderSide, PaperAccount, PaperEvent};
+use crate::symbols::Symbol;
+use rust_decimal::prelude::*;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+
+/// Balances of the simulated account, never shown.
+const CASH: &str = "CASH";
+const ASSET: &str = "ASSET";
+
+const MILLIS_PER_YEAR: f64 = 365.25 * 24.0 * 60.0 * 60.0 * 1000.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Signal {
+    /// Buy with all the cash when flat.
+    Enter,
+    /// Sell everything when long.
+    Exit,
+    Hold,
+}
+
+/// Decides on every closed candle, the backtester fills at the next candle's open so a
+/// strategy never trades on a price it could not have seen.
+pub trait Strategy {
+    fn name(&self) -> String;
+
+    /// `candles` ends with the candle that just closed.
+    fn signal(&mut self, candles: &[Candle]) -> Signal;
+
+    /// The series the signals come from, one value per candle, for candle exports.
+    fn indicators(&self, candles: &[Candle]) -> Vec<Indicator>;
+}
+
+/// `value` at every candle, seeing only the candles up to it.
+fn series(
+    name: String,
+    candles: &[Candle],
+    value: impl Fn(&[Candle]) -> Option<Decimal>,
+) -> Indicator {
+    Indicator {
+        name,
+        values: (1..=candles.len())
+            .map(|end| value(&candles[..end]))
+            .collect(),
+    }
+}
+
+fn average_close(candles: &[Candle], period: usize) -> Option<Decimal> {
+    let window = candles.get(candles.len().checked_sub(period)?..)?;
+    Some(window.iter().map(|c| c.close).sum::<Decimal>() / Decimal::from(period))
+}
+
+/// Long while the fast moving average of the closes is above the slow one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmaCross {
+    pub fast: usize,
+    pub slow: usize,
+}
+
+impl Strategy for SmaCross {
+    fn name(&self) -> String {
+        format!("sma:{},{}", self.fast, self.slow)
+    }
+
+    fn signal(&mut self, candles: &[Candle]) -> Signal {
+        let previous = &candles[..candles.len().saturating_sub(1)];
+        let (Some(fast), Some(slow), Some(previous_fast), Some(previous_slow)) = (
+            average_close(candles, self.fast),
+            average_close(candles, self.slow),
+            average_close(previous, self.fast),
+            average_close(previous, self.slow),
+        ) else {
+            return Signal::Hold;
+        };
+
+        if previous_fast <= previous_slow && fast > slow {
+            Signal::Enter
+        } else if previous_fast >= previous_slow && fast < slow {
+            Signal::Exit
+        } else {
+            Signal::Hold
+        }
+    }
+
+    fn indicators(&self, candles: &[Candle]) -> Vec<Indicator> {
+        [self.fast, self.slow]
+            .into_iter()
+            .map(|period| {
+                series(format!("sma_{}", period), candles, |window| {
+                    average_close(window, period)
+                })
+            })
+            .collect()
+    }
+}
+
+/// Buys when the RSI of the closes falls below `oversold`, sells above `overbought`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RsiReversion {
+    pub period: usize,
+    pub oversold: Decimal,
+    pub overbought: Decimal,
+}
+
+/// Plain averages of the gains and losses over the last `period` closes.
+fn rsi(candles: &[Candle], period: usize) -> Option<Decimal> {
+    let window = candles.get(candles.len().checked_sub(period + 1)?..)?;
+    let (gains, losses) = window
+        .windows(2)
+        .map(|pair| pair[1].close - pair[0].close)
+        .fold((Decimal::ZERO, Decimal::ZERO), |(gains, losses), change| {
+            if change.is_sign_positive() {
+                (gains + change, losses)
+            } else {
+                (gains, losses - change)
+            }
+        });
+
+    if losses.is_zero() {
+        return Some(Decimal::ONE_HUNDRED);
+    }
+
+    Some(Decimal::ONE_HUNDRED - Decimal::ONE_HUNDRED / (Decimal::ONE + gains / losses))
+}
+
+impl Strategy for RsiReversion {
+    fn name(&self) -> String {
+        format!("rsi:{},{},{}", self.period, self.oversold, self.overbought)
+    }
+
+    fn signal(&mut self, candles: &[Candle]) -> Signal {
+        match rsi(candles, self.period) {
+            Some(rsi) if rsi < self.oversold => Signal::Enter,
+            Some(rsi) if rsi > self.overbought => Signal::Exit,
+            _ => Signal::Hold,
+        }
+    }
+
+    fn indicators(&self, candles: &[Candle]) -> Vec<Indicator> {
+        vec![series(format!("rsi_{}", self.period), candles, |window| {
+            rsi(window, self.period)
+        })]
+    }
+}
+
+/// `sma:FAST,SLOW` or `rsi:PERIOD,OVERSOLD,OVERBOUGHT`, e.g. `sma:10,30`.
+pub fn parse_strategy(spec: &str) -> Result<Box<dyn Strategy>, String> {
+    let (name, params) = spec.trim().split_once(':').unwrap_or((spec.trim(), ""));
+    let params = params.split(',').map(str::trim).collect::<Vec<_>>();
+    let invalid = |expected: &str| format!("Invalid strategy {:?}, expected {}", spec, expected);
+
+    match name {
+        "sma" => {
+            let expected = "sma:FAST,SLOW with 0 < FAST < SLOW";
+            let [fast, slow] = params.as_slice() else {
+                return Err(invalid(expected));
+            };
+            let (Ok(fast), Ok(slow)) = (fast.parse::<usize>(), slow.parse::<usize>()) else {
+                return Err(invalid(expected));
+            };
+            if fast == 0 || fast >= slow {
+                return Err(invalid(expected));
+            }
+
+            Ok(Box::new(SmaCross { fast, slow }))
+        }
+        "rsi" => {
+            let expected = "rsi:PERIOD,OVERSOLD,OVERBOUGHT with 0 < OVERSOLD < OVERBOUGHT < 100";
+            let [period, oversold, overbought] = params.as_slice() else {
+                return Err(invalid(expected));
+            };
+            let (Ok(period), Ok(oversold), Ok(overbought)) = (
+                period.parse::<usize>(),
+                oversold.parse::<Decimal>(),
+                overbought.parse::<Decimal>(),
+            ) else {
+                return Err(invalid(expected));
+            };
+            if period == 0
+                || oversold <= Decimal::ZERO
+                || oversold >= overbought
+                || overbought >= Decimal::ONE_HUNDRED
+            {
+                return Err(invalid(expected));
+            }
+
+            Ok(Box::new(RsiReversion {
+                period,
+                oversold,
+                overbought,
+            }))
+        }
+        _ => Err(format!(
+            "Unknown strategy {:?}, expected sma:FAST,SLOW or rsi:PERIOD,OVERSOLD,OVERBOUGHT",
+            spec
+        )),
+    }
+}
+
+/// A round trip from a buy to the sell that closed it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BacktestTrade {
+    pub entry_time: u64,
+    pub entry_price: Decimal,
+    pub exit_time: u64,
+    pub exit_price: Decimal,
+    pub quantity: Decimal,
+    /// Both fills' fees, included in `pnl`.
+    pub fees: Decimal,
+    pub pnl: Decimal,
+    /// Percent of what the entry cost.
+    pub return_pct: Decimal,
+}
+
+#[derive(Debug, Clone)]
+pub struct BacktestReport {
+    pub symbol: String,
+    pub strategy: String,
+    pub initial_cash: Decimal,
+    /// Close time and account value after every candle, oldest first.
+    pub equity: Vec<(u64, Decimal)>,
+    pub trades: Vec<BacktestTrade>,
+    /// The simulated account, its fills mark the trades on the chart.
+    pub account: PaperAccount,
+    /// Candle length, annualizes the Sharpe ratio.
+    pub interval: u64,
+}
+
+impl BacktestReport {
+    pub fn final_equity(&self) -> Decimal {
+        self.equity
+            .last()
+            .map(|(_, value)| *value)
+            .unwrap_or(self.initial_cash)
+    }
+
+    /// Percent gained or lost over the whole run.
+    pub fn total_return(&self) -> Decimal {
+        (self.final_equity() / self.initial_cash - Decimal::ONE) * Decimal::ONE_HUNDRED
+    }
+
+    /// Largest fall from a peak of the equity curve, in percent.
+    pub fn max_drawdown(&self) -> Decimal {
+        let mut peak = self.initial_cash;
+        let mut drawdown = Decimal::ZERO;
+
+        for (_, value) in &self.equity {
+            peak = peak.max(*value);
+            if peak > Decimal::ZERO {
+                drawdown = drawdown.max((peak - value) / peak * Decimal::ONE_HUNDRED);
+            }
+        }
+
+        drawdown
+    }
+
+    /// Percent of trades that made money, `None` without trades.
+    pub fn win_rate(&self) -> Option<Decimal> {
+        if self.trades.is_empty() {
+            return None;
+        }
+
+        let wins = self.trades.iter().filter(|t| t.pnl > Decimal::ZERO).count();
+        Some(Decimal::from(wins * 100) / Decimal::from(self.trades.len()))
+    }
+
+    /// Annualized mean over standard deviation of the per-candle returns, without a
+    /// risk-free rate. `None` when the equity never moved.
+    pub fn sharpe(&self) -> Option<f64> {
+        let returns = self
+            .equity
+            .windows(2)
+            .filter_map(|pair| {
+                let (previous, current) = (pair[0].1.to_f64()?, pair[1].1.to_f64()?);
+                (previous > 0.0).then(|| current / previous - 1.0)
+            })
+            .collect::<Vec<_>>();
+        if returns.len() < 2 || self.interval == 0 {
+            return None;
+        }
+
+        let count = returns.len() as f64;
+        let mean = returns.iter().sum::<f64>() / count;
+        let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (count - 1.0);
+        let deviation = variance.sqrt();
+
+        (deviation > 0.0)
+            .then(|| mean / deviation * (MILLIS_PER_YEAR / self.interval as f64).sqrt())
+    }
+
+    /// The figures on one line each, for the CLI and the window.
+    pub fn summary(&self) -> String {
+        let mut summary = String::new();
+        let _ = writeln!(summary, "Strategy {} on {}", self.strategy, self.symbol);
+        let _ = writeln!(
+            summary,
+            "Equity {} -> {} ({}%)",
+            self.initial_cash.round_dp(2),
+            self.final_equity().round_dp(2),
+            self.total_return().round_dp(2)
+        );
+        let _ = writeln!(summary, "Max drawdown {}%", self.max_drawdown().round_dp(2));
+        let _ = writeln!(
+            summary,
+            "Trades {}, win rate {}",
+            self.trades.len(),
+            self.win_rate()
+                .map(|rate| format!("{}%", rate.round_dp(2)))
+                .unwrap_or_else(|| "-".to_string())
+        );
+        let _ = writeln!(
+            summary,
+            "Sharpe {}",
+            self.sharpe()
+                .map(|sharpe| format!("{:.2}", sharpe))
+                .unwrap_or_else(|| "-".to_string())
+        );
+
+        summary
+    }
+}
+
+/// Runs strategies long only over a candle series with the paper account's fills, fees
+/// and slippage.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Backtester {
+    pub initial_cash: Decimal,
+    pub fee_rate: Decimal,
+    pub slippage: Decimal,
+}
+
+impl Backtester {
+    /// A position still open after the last candle is sold at its close.
+    pub fn run(
+        &self,
+        symbol: &str,
+        candles: &[Candle],
+        strategy: &mut dyn Strategy,
+    ) -> Result<BacktestReport, String> {
+        if self.initial_cash <= Decimal::ZERO {
+            return Err("The starting cash must be above zero".to_string());
+        }
+        let (Some(first), Some(last)) = (candles.first(), candles.last()) else {
+            return Err(format!("No candles to backtest {} on", symbol));
+        };
+
+        let market = Symbol {
+            symbol: symbol.to_string(),
+            price: None,
+            decimals: 8,
+            timeframe: String::new(),
+            base_asset: ASSET.to_string(),
+            quote_asset: CASH.to_string(),
+            status: String::new(),
+            permissions: Vec::new(),
+        };
+        let mut account = PaperAccount::new(
+            BTreeMap::from([(CASH.to_string(), self.initial_cash)]),
+            self.fee_rate,
+            self.slippage,
+        );
+        let mut equity = Vec::with_capacity(candles.len());
+        let mut pending = Signal::Hold;
+
+        for (index, candle) in candles.iter().enumerate() {
+            self.fill(
+                &mut account,
+                &market,
+                pending,
+                candle.open,
+                candle.open_time,
+            )?;
+            pending = Signal::Hold;
+
+            equity.push((candle.close_time, value(&account, candle.close)));
+
+            if index + 1 < candles.len() {
+                pending = strategy.signal(&candles[..=index]);
+            }
+        }
+
+        self.fill(
+            &mut account,
+            &market,
+            Signal::Exit,
+            last.close,
+            last.close_time,
+        )?;
+        if let Some(point) = equity.last_mut() {
+            point.1 = value(&account, last.close);
+        }
+
+        let interval = match candles {
+            [first, second, ..] => second.open_time - first.open_time,
+            _ => last.close_time + 1 - first.open_time,
+        };
+
+        Ok(BacktestReport {
+            symbol: symbol.to_string(),
+            strategy: strategy.name(),
+            initial_cash: self.initial_cash,
+            equity,
+            trades: round_trips(&account),
+            account,
+            interval,
+        })
+    }
+
+    /// Enters with all the cash when flat, exits everything when long.
+    fn fill(
+        &self,
+        account: &mut PaperAccount,
+        market: &Symbol,
+        signal: Signal,
+        price: Decimal,
+        time: u64,
+    ) -> Result<(), String> {
+        let quantity = match signal {
+            Signal::Enter if account.balance(ASSET).is_zero() => {
+                let worst = price * (Decimal::ONE + self.slippage) * (Decimal::ONE + self.fee_rate);
+                (account.balance(CASH) / worst).round_dp_with_strategy(6, RoundingStrategy::ToZero)
+            }
+            Signal::Exit => account.balance(ASSET),
+            _ => Decimal::ZERO,
+        };
+        if quantity.is_zero() {
+            return Ok(());
+        }
+
+        let side = match signal {
+            Signal::Enter => OrderSide::Buy,
+            _ => OrderSide::Sell,
+        };
+        let events = account.place(market, side, OrderKind::Market, quantity, Some(price), time)?;
+
+        match events.first() {
+            Some(PaperEvent::Rejected(_, reason)) => Err(reason.clone()),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn value(account: &PaperAccount, price: Decimal) -> Decimal {
+    account.balance(CASH) + account.balance(ASSET) * price
+}
+
+/// Pairs every buy with the sell after it, the backtester never holds two entries.
+fn round_trips(account: &PaperAccount) -> Vec<BacktestTrade> {
+    account
+        .fills
+        .chunks_exact(2)
+        .map(|pair| {
+            let (entry, exit) = (&pair[0], &pair[1]);
+            let cost = entry.price * entry.quantity + entry.fee;
+            let pnl = exit.price * exit.quantity - exit.fee - cost;
+
+            BacktestTrade {
+                entry_time: entry.time,
+                entry_price: entry.price,
+                exit_time: exit.time,
+                exit_price: exit.price,
+                quantity: entry.quantity,
+                fees: entry.fee + exit.fee,
+                pnl,
+                return_pct: pnl / cost * Decimal::ONE_HUNDRED,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candles(closes: &[i64]) -> Vec<Candle> {
+        closes
+            .iter()
+            .enumerate()
+            .map(|(i, close)| {
+                let close = Decimal::from(*close);
+                Candle {
+                    open: close,
+                    high: close,
+                    low: close,
+                    close,
+                    volume: Decimal::ONE,
+                    open_time: i as u64 * 60_000,
+                    close_time: i as u64 * 60_000 + 59_999,
+                }
+            })
+            .collect()
+    }
+
+    fn backtester(fee_rate: Decimal) -> Backtester {
+        Backtester {
+            initial_cash: dec!(1000),
+            fee_rate,
+            slippage: Decimal::ZERO,
+        }
+    }
+
+    #[test]
+    fn trades_fill_at_the_next_open() {
+        let candles = candles(&[10, 10, 10, 12, 14, 16, 12, 8, 8, 9, 10, 11]);
+        let mut strategy = SmaCross { fast: 1, slow: 2 };
+        let report = backtester(Decimal::ZERO)
+            .run("BTCUSDT", &candles, &mut strategy)
+            .unwrap();
+
+        // Crossed up on the close of 12, down on the close of 12 after 16.
+        assert_eq!(report.trades.len(), 2);
+        let trade = &report.trades[0];
+        assert_eq!(trade.entry_time, candles[4].open_time);
+        assert_eq!(trade.entry_price, dec!(14));
+        assert_eq!(trade.exit_time, candles[7].open_time);
+        assert_eq!(trade.exit_price, dec!(8));
+
+        // Bought again at 10 after 9 crossed, then sold at the last close.
+        assert_eq!(report.trades[1].entry_price, dec!(10));
+        assert_eq!(report.trades[1].exit_time, candles[11].close_time);
+        assert_eq!(report.trades[1].exit_price, dec!(11));
+        assert_eq!(report.win_rate(), Some(dec!(50)));
+        assert_eq!(report.account.balance(ASSET), Decimal::ZERO);
+        assert_eq!(report.final_equity(), report.account.balance(CASH));
+        assert!(report.max_drawdown() > dec!(42));
+    }
+
+    #[test]
+    fn fees_come_out_of_the_trade() {
+        let candles = candles(&[10, 10, 11, 12, 13]);
+        let mut strategy = SmaCross { fast: 1, slow: 2 };
+        let report = backtester(dec!(0.01))
+            .run("BTCUSDT", &candles, &mut strategy)
+            .unwrap();
+
+        let trade = &report.trades[0];
+        assert_eq!(trade.entry_price, dec!(12));
+        assert_eq!(trade.exit_price, dec!(13));
+        assert!(trade.fees > Decimal::ZERO);
+        assert_eq!(
+            trade.pnl,
+            (dec!(13) - dec!(12)) * trade.quantity - trade.fees
+        );
+        assert_eq!(report.final_equity(), dec!(1000) + trade.pnl);
+    }
+
+    #[test]
+    fn strategies_parse_from_specs() {
+        assert_eq!(parse_strategy("sma:10,30").unwrap().name(), "sma:10,30");
+        assert_eq!(
+            parse_strategy(" rsi:14,30,70 ").unwrap().name(),
+            "rsi:14,30,70"
+        );
+        assert!(parse_strategy("sma:30,10").is_err());
+        assert!(parse_strategy("rsi:14,70,30").is_err());
+        assert!(parse_strategy("macd:12,26,9").is_err());
+
+        let rising = candles(&[1, 2, 3, 4, 5]);
+        assert_eq!(rsi(&rising, 4), Some(Decimal::ONE_HUNDRED));
+        assert_eq!(rsi(&rising, 5), None);
+    }
+
+    #[test]
+    fn indicators_line_up_with_the_candles() {
+        let closes = candles(&[1, 2, 3, 4, 5]);
+
+        let sma = parse_strategy("sma:2,3").unwrap().indicators(&closes);
+        assert_eq!(sma[0].name, "sma_2");
+        assert_eq!(
+            sma[0].values,
+            [
+                None,
+                Some(dec!(1.5)),
+                Some(dec!(2.5)),
+                Some(dec!(3.5)),
+                Some(dec!(4.5))
+            ]
+        );
+        assert_eq!(sma[1].name, "sma_3");
+        assert_eq!(sma[1].values[..3], [None, None, Some(dec!(2))]);
+
+        let rsi = parse_strategy("rsi:4,30,70").unwrap().indicators(&closes);
+        assert_eq!(rsi[0].name, "rsi_4");
+        assert_eq!(rsi[0].values, [None, None, None, None, Some(dec!(100))]);
+    }
+}
@@ -0,0 +1,855 @@
+use chrono::{NaiveDate, Utc};
+use juicy_lemon::backtest::{BacktestReport, Backtester, parse_strategy};
+use juicy_lemon::candle::{Candle, get_candles_since, timeframe_millis};
+use juicy_lemon::config::{
+    self, Config, MAX_HISTORY_CANDLES, check_export_scale, check_export_side,
+};
+use juicy_lemon::dataset::{CandleTable, Timestamps, parse_candles};
+use juicy_lemon::symbols::{Symbol, fetch_symbol_prices, get_symbols};
+use rust_decimal::Decimal;
+use std::fs;
+use std::path::Path;
+use std::process::ExitCode;
+
+const USAGE: &str = "\
+Usage: juicy-lemon-cli <command> [options]
+
+Commands:
+  quotes <SYMBOL>...                 Print the last price of each symbol
+  klines <SYMBOL> [options]          Dump candles for a range
+      --timeframe <TF>               1m, 5m, 15m, 1h, 4h or 1d (default 1h)
+      --from <DATE|MILLIS>           Range start, YYYY-MM-DD or epoch millis (default one day ago)
+      --to <DATE|MILLIS>             Range end, exclusive (default now)
+      --format <FORMAT>              csv, json, jsonl or parquet (default csv)
+      --time <millis|utc|local>      Timestamps in csv, jsonl and parquet (default millis)
+      --output <FILE>                Write to FILE instead of stdout, required for parquet
+  chart <SYMBOL> --output <FILE> [options]
+                                     Render a candle chart to a .png or .svg file
+      --timeframe <TF>               1m, 5m, 15m, 1h, 4h or 1d (default 1h)
+      --candles <N>                  Latest candles to draw (default history.candles)
+      --width <PX>                   Image width (default export.width)
+      --height <PX>                  Image height (default export.height)
+      --scale <N>                    PNG pixels per point (default export.scale)
+      --theme <NAME>                 Palette to draw with (default ui.theme)
+      --volume-profile               Overlay the visible range and session profiles
+  backtest [SYMBOL] [options]        Run a strategy over candles and report its results
+      --csv <FILE>                   Read candles from a CSV file instead of the exchange
+      --timeframe <TF>               1m, 5m, 15m, 1h, 4h or 1d (default 1h)
+      --from <DATE|MILLIS>           Range start (default 1000 candles before --to)
+      --to <DATE|MILLIS>             Range end, exclusive (default now)
+      --strategy <SPEC>              sma:FAST,SLOW or rsi:PERIOD,OVERSOLD,OVERBOUGHT
+                                     (default backtest.strategy)
+      --cash <AMOUNT>                Starting cash (default backtest.initial_cash)
+      --fee <FRACTION>               Fee per fill (default backtest.fee_rate)
+      --slippage <FRACTION>          Slippage per fill (default backtest.slippage)
+      --trades                       List every trade after the summary
+      --chart <FILE>                 Render the candles with the trades to a .png or .svg
+  instruments [options]              List instruments
+      --quote <ASSET>                Only instruments quoted in ASSET
+      --search <TEXT>                Only symbols containing TEXT
+      --all                          Include instruments that are not trading
+
+Options for every command:
+  --config <FILE>                    Read FILE instead of juicy-lemon.toml
+  --set <SECTION.KEY=VALUE>          Override one config setting, e.g. api.testnet=true
+";
+
+const KLINE_PAGE_SIZE: u32 = 1000;
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    Quotes(Vec<String>),
+    Klines(KlineArgs),
+    Chart(ChartArgs),
+    Backtest(BacktestArgs),
+    Instruments(InstrumentArgs),
+}
+
+#[derive(Debug, PartialEq)]
+enum Format {
+    Csv,
+    Json,
+    JsonLines,
+    Parquet,
+}
+
+#[derive(Debug, PartialEq)]
+struct KlineArgs {
+    symbol: String,
+    timeframe: String,
+    from: Option<u64>,
+    to: Option<u64>,
+    format: Format,
+    timestamps: Timestamps,
+    output: Option<String>,
+}
+
+/// Unset options fall back to the config when the command runs.
+#[derive(Debug, PartialEq)]
+struct ChartArgs {
+    symbol: String,
+    timeframe: String,
+    output: String,
+    candles: Option<usize>,
+    width: Option<u32>,
+    height: Option<u32>,
+    scale: Option<f32>,
+    theme: Option<String>,
+    volume_profile: bool,
+}
+
+/// Candles come from `csv` when given, else from the exchange. Unset options fall back
+/// to the config when the command runs.
+#[derive(Debug, PartialEq)]
+struct BacktestArgs {
+    symbol: String,
+    csv: Option<String>,
+    timeframe: String,
+    from: Option<u64>,
+    to: Option<u64>,
+    strategy: Option<String>,
+    cash: Option<Decimal>,
+    fee: Option<Decimal>,
+    slippage: Option<Decimal>,
+    trades: bool,
+    chart: Option<String>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct InstrumentArgs {
+    quote: Option<String>,
+    search: Option<String>,
+    all: bool,
+}
+
+/// Epoch millis as is, or a UTC date meaning its midnight.
+fn parse_time(value: &str) -> Result<u64, String> {
+    if let Ok(millis) = value.parse::<u64>() {
+        return Ok(millis);
+    }
+
+    NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|time| time.and_utc().timestamp_millis() as u64)
+        .ok_or_else(|| {
+            format!(
+                "Invalid time {}, expected YYYY-MM-DD or epoch millis",
+                value
+            )
+        })
+}
+
+fn parse_args(args: &[String]) -> Result<Command, String> {
+    let (command, rest) = args.split_first().ok_or("Missing command")?;
+    let mut positional = Vec::new();
+    let mut options = Vec::new();
+    let mut iter = rest.iter();
+
+    while let Some(arg) = iter.next() {
+        match arg.strip_prefix("--") {
+            Some(flag @ ("all" | "volume-profile" | "trades")) => {
+                options.push((flag.to_string(), String::new()))
+            }
+            Some(name) => {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| format!("Missing value for --{}", name))?;
+                options.push((name.to_string(), value.clone()));
+            }
+            None => positional.push(arg.to_uppercase()),
+        }
+    }
+
+    match command.as_str() {
+        "quotes" => {
+            if positional.is_empty() {
+                return Err("quotes needs at least one symbol".to_string());
+            }
+            if let Some((name, _)) = options.first() {
+                return Err(format!("Unknown option --{}", name));
+            }
+
+            Ok(Command::Quotes(positional))
+        }
+        "klines" => {
+            let [symbol] = positional.as_slice() else {
+                return Err("klines needs exactly one symbol".to_string());
+            };
+
+            let mut args = KlineArgs {
+                symbol: symbol.clone(),
+                timeframe: "1h".to_string(),
+                from: None,
+                to: None,
+                format: Format::Csv,
+                timestamps: Timestamps::Millis,
+                output: None,
+            };
+
+            for (name, value) in options {
+                match name.as_str() {
+                    "timeframe" => {
+                        timeframe_millis(&value)
+                            .ok_or_else(|| format!("Unsupported timeframe {}", value))?;
+                        args.timeframe = value;
+                    }
+                    "from" => args.from = Some(parse_time(&value)?),
+                    "to" => args.to = Some(parse_time(&value)?),
+                    "format" => {
+                        args.format = match value.as_str() {
+                            "csv" => Format::Csv,
+                            "json" => Format::Json,
+                            "jsonl" => Format::JsonLines,
+                            "parquet" => Format::Parquet,
+                            _ => return Err(format!("Unsupported format {}", value)),
+                        }
+                    }
+                    "time" => args.timestamps = Timestamps::parse(&value)?,
+                    "output" => args.output = Some(value),
+                    _ => return Err(format!("Unknown option --{}", name)),
+                }
+            }
+
+            if args.format == Format::Parquet && args.output.is_none() {
+                return Err("parquet needs --output <FILE>".to_string());
+            }
+
+            Ok(Command::Klines(args))
+        }
+        "chart" => {
+            let [symbol] = positional.as_slice() else {
+                return Err("chart needs exactly one symbol".to_string());
+            };
+
+            let mut args = ChartArgs {
+                symbol: symbol.clone(),
+                timeframe: "1h".to_string(),
+                output: String::new(),
+                candles: None,
+                width: None,
+                height: None,
+                scale: None,
+                theme: None,
+                volume_profile: false,
+            };
+
+            let number = |name: &str, value: &str| {
+                value.parse::<u32>().ok().filter(|n| *n > 0).ok_or_else(|| {
+                    format!("Invalid --{} {}, expected a positive number", name, value)
+                })
+            };
+
+            for (name, value) in options {
+                match name.as_str() {
+                    "timeframe" => {
+                        timeframe_millis(&value)
+                            .ok_or_else(|| format!("Unsupported timeframe {}", value))?;
+                        args.timeframe = value;
+                    }
+                    "output" => args.output = value,
+                    "candles" => {
+                        let candles = number(&name, &value)? as usize;
+                        if candles > MAX_HISTORY_CANDLES {
+                            return Err(format!("--candles is at most {}", MAX_HISTORY_CANDLES));
+                        }
+                        args.candles = Some(candles);
+                    }
+                    "width" | "height" => {
+                        let side = number(&name, &value)?;
+                        check_export_side(&format!("--{}", name), side)?;
+
+                        if name == "width" {
+                            args.width = Some(side);
+                        } else {
+                            args.height = Some(side);
+                        }
+                    }
+                    "scale" => {
+                        let scale = value
+                            .parse::<f32>()
+                            .map_err(|_| format!("Invalid --scale {}", value))?;
+                        check_export_scale("--scale", scale)?;
+                        args.scale = Some(scale);
+                    }
+                    "theme" => args.theme = Some(value),
+                    "volume-profile" => args.volume_profile = true,
+                    _ => return Err(format!("Unknown option --{}", name)),
+                }
+            }
+
+            if args.output.is_empty() {
+                return Err("chart needs --output <FILE>".to_string());
+            }
+
+            Ok(Command::Chart(args))
+        }
+        "backtest" => {
+            let mut args = BacktestArgs {
+                symbol: String::new(),
+                csv: None,
+                timeframe: "1h".to_string(),
+                from: None,
+                to: None,
+                strategy: None,
+                cash: None,
+                fee: None,
+                slippage: None,
+                trades: false,
+                chart: None,
+            };
+
+            let decimal = |name: &str, value: &str| {
+                value
+                    .parse::<Decimal>()
+                    .ok()
+                    .filter(|n| !n.is_sign_negative())
+                    .ok_or_else(|| format!("Invalid --{} {}, expected a number", name, value))
+            };
+
+            for (name, value) in options {
+                match name.as_str() {
+                    "csv" => args.csv = Some(value),
+                    "timeframe" => {
+                        timeframe_millis(&value)
+                            .ok_or_else(|| format!("Unsupported timeframe {}", value))?;
+                        args.timeframe = value;
+                    }
+                    "from" => args.from = Some(parse_time(&value)?),
+                    "to" => args.to = Some(parse_time(&value)?),
+                    "strategy" => {
+                        parse_strategy(&value)?;
+                        args.strategy = Some(value);
+                    }
+                    "cash" => args.cash = Some(decimal(&name, &value)?),
+                    "fee" => args.fee = Some(decimal(&name, &value)?),
+                    "slippage" => args.slippage = Some(decimal(&name, &value)?),
+                    "trades" => args.trades = true,
+                    "chart" => args.chart = Some(value),
+                    _ => return Err(format!("Unknown option --{}", name)),
+                }
+            }
+
+            // A CSV file names the series after itself unless a symbol is given.
+            args.symbol = match (positional.as_slice(), &args.csv) {
+                ([symbol], _) => symbol.clone(),
+                ([], Some(csv)) => Path::new(csv)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy().to_uppercase())
+                    .unwrap_or_default(),
+                _ => return Err("backtest needs one symbol or --csv <FILE>".to_string()),
+            };
+
+            Ok(Command::Backtest(args))
+        }
+        "instruments" => {
+            if !positional.is_empty() {
+                return Err("instruments takes no symbols".to_string());
+            }
+
+            let mut args = InstrumentArgs::default();
+
+            for (name, value) in options {
+                match name.as_str() {
+                    "quote" => args.quote = Some(value.to_uppercase()),
+                    "search" => args.search = Some(value.to_uppercase()),
+                    "all" => args.all = true,
+                    _ => return Err(format!("Unknown option --{}", name)),
+                }
+            }
+
+            Ok(Command::Instruments(args))
+        }
+        _ => Err(format!("Unknown command {}", command)),
+    }
+}
+
+async fn quotes(symbols: Vec<String>) -> Result<String, String> {
+    let prices = fetch_symbol_prices(symbols).await?;
+
+    Ok(prices
+        .iter()
+        .map(|p| format!("{}\t{}\n", p.symbol, p.price))
+        .collect())
+}
+
+async fn decimals(symbol: &str) -> Result<u32, String> {
+    get_symbols()
+        .await?
+        .into_iter()
+        .find(|s| s.symbol == symbol)
+        .map(|s| s.decimals)
+        .ok_or_else(|| format!("Unknown symbol {}", symbol))
+}
+
+/// Pages through the range oldest first, `to` is exclusive.
+async fn klines(args: &KlineArgs) -> Result<Vec<Candle>, String> {
+    let interval = timeframe_millis(&args.timeframe).unwrap_or(60_000);
+    let now = Utc::now().timestamp_millis() as u64;
+    let to = args.to.unwrap_or(now);
+    let mut start_time = args.from.unwrap_or(to.saturating_sub(24 * 60 * 60 * 1000));
+
+    let decimals = decimals(&args.symbol).await?;
+    let mut candles = Vec::new();
+
+    while start_time < to {
+        let page = get_candles_since(
+            &args.symbol,
+            &args.timeframe,
+            decimals,
+            start_time,
+            KLINE_PAGE_SIZE,
+        )
+        .await?;
+        let full = page.len() == KLINE_PAGE_SIZE as usize;
+
+        let Some(last) = page.last() else {
+            break;
+        };
+        start_time = last.open_time + interval;
+
+        candles.extend(page.into_iter().filter(|c| c.open_time < to));
+
+        if !full {
+            break;
+        }
+    }
+
+    Ok(candles)
+}
+
+/// Draws the chart the window would show for the same symbol, timeframe and theme.
+#[cfg(feature = "gui")]
+async fn chart(args: &ChartArgs) -> Result<(), String> {
+    use juicy_lemon::candle::get_candles_with_limit;
+    use juicy_lemon::graph::chart::{CandleChart, ChartCaches};
+    use juicy_lemon::graph::export::export_chart;
+    use juicy_lemon::profile::get_volume_profiles;
+    use juicy_lemon::theme::load_palettes;
+    use std::collections::VecDeque;
+
+    let config = config::get();
+    let theme = args.theme.as_ref().unwrap_or(&config.ui.theme);
+    let palettes = load_palettes(&config.ui.palettes_dir)?;
+    let palette = palettes
+        .iter()
+        .find(|(name, _)| name == theme)
+        .map(|(_, palette)| *palette)
+        .ok_or_else(|| {
+            let names = palettes
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>();
+            format!(
+                "Unknown theme {}, expected one of {}",
+                theme,
+                names.join(", ")
+            )
+        })?;
+
+    let decimals = decimals(&args.symbol).await?;
+    let candles = get_candles_with_limit(
+        &args.symbol,
+        &args.timeframe,
+        decimals,
+        args.candles.unwrap_or(config.history.candles) as u32,
+    )
+    .await?;
+    let Some(first) = candles.first() else {
+        return Err(format!("No {} candles for {}", args.timeframe, args.symbol));
+    };
+
+    let profiles = if args.volume_profile {
+        Some(
+            get_volume_profiles(
+                args.symbol.clone(),
+                args.timeframe.clone(),
+                decimals,
+                first.open_time,
+            )
+            .await?,
+        )
+    } else {
+        None
+    };
+
+    let candles = VecDeque::from(candles);
+    let caches = ChartCaches::default();
+    let chart = CandleChart {
+        candles: &candles,
+        plot: None,
+        visible: candles.len(),
+        decimals,
+        timeframe: &args.timeframe,
+        profiles: profiles.as_ref(),
+        paper: None,
+        backtest: None,
+        caches: &caches,
+        palette,
+    };
+
+    export_chart(
+        &chart,
+        Path::new(&args.output),
+        args.width.unwrap_or(config.export.width),
+        args.height.unwrap_or(config.export.height),
+        args.scale.unwrap_or(config.export.scale),
+    )?;
+    eprintln!(
+        "Wrote {} {} chart to {}",
+        args.symbol, args.timeframe, args.output
+    );
+
+    Ok(())
+}
+
+#[cfg(not(feature = "gui"))]
+async fn chart(_args: &ChartArgs) -> Result<(), String> {
+    Err("chart needs the chart renderers, build with the gui feature".to_string())
+}
+
+/// The candles of a backtest, `from` defaults to as many candles as a chart holds.
+async fn backtest_candles(args: &BacktestArgs) -> Result<Vec<Candle>, String> {
+    if let Some(path) = &args.csv {
+        let csv =
+            fs::read_to_string(path).map_err(|err| format!("Cannot read {}: {}", path, err))?;
+
+        return Ok(parse_candles(&csv)?
+            .into_iter()
+            .filter(|c| args.from.is_none_or(|from| c.open_time >= from))
+            .filter(|c| args.to.is_none_or(|to| c.open_time < to))
+            .collect());
+    }
+
+    let interval = timeframe_millis(&args.timeframe).unwrap_or(60_000);
+    let to = args.to.unwrap_or(Utc::now().timestamp_millis() as u64);
+    let from = args
+        .from
+        .unwrap_or(to.saturating_sub(interval * MAX_HISTORY_CANDLES as u64));
+
+    klines(&KlineArgs {
+        symbol: args.symbol.clone(),
+        timeframe: args.timeframe.clone(),
+        from: Some(from),
+        to: Some(to),
+        format: Format::Csv,
+        timestamps: Timestamps::Millis,
+        output: None,
+    })
+    .await
+}
+
+async fn backtest(args: &BacktestArgs) -> Result<(), String> {
+    let config = &config::get().backtest;
+    let candles = backtest_candles(args).await?;
+    let backtester = Backtester {
+        initial_cash: args.cash.unwrap_or(config.initial_cash),
+        fee_rate: args.fee.unwrap_or(config.fee_rate),
+        slippage: args.slippage.unwrap_or(config.slippage),
+    };
+    let mut strategy = parse_strategy(args.strategy.as_ref().unwrap_or(&config.strategy))?;
+    let report = backtester.run(&args.symbol, &candles, &mut *strategy)?;
+
+    print!("{}", report.summary());
+
+    if args.trades {
+        println!("entry_time\tentry_price\texit_time\texit_price\tquantity\tfees\tpnl\treturn_pct");
+        for trade in &report.trades {
+            println!(
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                trade.entry_time,
+                trade.entry_price,
+                trade.exit_time,
+                trade.exit_price,
+                trade.quantity,
+                trade.fees.round_dp(8),
+                trade.pnl.round_dp(8),
+                trade.return_pct.round_dp(4)
+            );
+        }
+    }
+
+    if let Some(path) = &args.chart {
+        backtest_chart(args, &candles, &report, path)?;
+    }
+
+    Ok(())
+}
+
+/// Draws every candle of the run with its fills, in the configured theme and size.
+#[cfg(feature = "gui")]
+fn backtest_chart(
+    args: &BacktestArgs,
+    candles: &[Candle],
+    report: &BacktestReport,
+    path: &str,
+) -> Result<(), String> {
+    use juicy_lemon::candle::TIMEFRAMES;
+    use juicy_lemon::graph::chart::{CandleChart, ChartCaches};
+    use juicy_lemon::graph::export::export_chart;
+    use juicy_lemon::graph::paper::PaperMarks;
+    use juicy_lemon::theme::load_palettes;
+    use std::collections::VecDeque;
+
+    let config = config::get();
+    let palette = load_palettes(&config.ui.palettes_dir)?
+        .into_iter()
+        .find(|(name, _)| name == &config.ui.theme)
+        .map(|(_, palette)| palette)
+        .ok_or_else(|| format!("Unknown theme {}", config.ui.theme))?;
+
+    let candles = VecDeque::from(candles.to_vec());
+    let decimals = candles.iter().map(|c| c.close.scale()).max().unwrap_or(2);
+    let caches = ChartCaches::default();
+    let chart = CandleChart {
+        candles: &candles,
+        plot: None,
+        visible: candles.len(),
+        decimals,
+        timeframe: TIMEFRAMES
+            .into_iter()
+            .find(|tf| timeframe_millis(tf) == Some(report.interval))
+            .unwrap_or(&args.timeframe),
+        profiles: None,
+        paper: None,
+        backtest: Some((
+            1,
+            PaperMarks {
+                account: &report.account,
+                symbol: &report.symbol,
+            },
+        )),
+        caches: &caches,
+        palette,
+    };
+
+    export_chart(
+        &chart,
+        Path::new(path),
+        config.export.width,
+        config.export.height,
+        config.export.scale,
+    )?;
+    eprintln!("Wrote {} backtest chart to {}", args.symbol, path);
+
+    Ok(())
+}
+
+#[cfg(not(feature = "gui"))]
+fn backtest_chart(
+    _args: &BacktestArgs,
+    _candles: &[Candle],
+    _report: &BacktestReport,
+    _path: &str,
+) -> Result<(), String> {
+    Err("--chart needs the chart renderers, build with the gui feature".to_string())
+}
+
+fn instruments(symbols: Vec<Symbol>, args: &InstrumentArgs) -> String {
+    symbols
+        .into_iter()
+        .filter(|s| args.all || s.is_trading())
+        .filter(|s| args.quote.as_ref().is_none_or(|q| &s.quote_asset == q))
+        .filter(|s| {
+            args.search
+                .as_ref()
+                .is_none_or(|text| s.symbol.contains(text.as_str()))
+        })
+        .map(|s| {
+            format!(
+                "{}\t{}\t{}\t{}\t{}\n",
+                s.symbol, s.base_asset, s.quote_asset, s.decimals, s.status
+            )
+        })
+        .collect()
+}
+
+async fn run(command: Command) -> Result<(), String> {
+    match command {
+        Command::Quotes(symbols) => print!("{}", quotes(symbols).await?),
+        Command::Klines(args) => {
+            let candles = klines(&args).await?;
+            let table = CandleTable::new(&candles, args.timestamps);
+            let content = match args.format {
+                Format::Csv => table.to_csv().into_bytes(),
+                Format::Json => serde_json::to_vec_pretty(&candles)
+                    .map_err(|err| format!("Failed to encode candles: {}", err))?,
+                Format::JsonLines => table.to_json_lines().into_bytes(),
+                Format::Parquet => table.to_parquet()?,
+            };
+
+            match &args.output {
+                Some(path) => {
+                    fs::write(path, content)
+                        .map_err(|err| format!("Failed to write {}: {}", path, err))?;
+                    eprintln!("Wrote {} candles to {}", candles.len(), path);
+                }
+                None => print!("{}", String::from_utf8_lossy(&content)),
+            }
+        }
+        Command::Chart(args) => chart(&args).await?,
+        Command::Backtest(args) => backtest(&args).await?,
+        Command::Instruments(args) => print!("{}", instruments(get_symbols().await?, &args)),
+    }
+
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    if args.is_empty() || args.iter().any(|a| a == "-h" || a == "--help") {
+        print!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    match Config::from_args(&mut args) {
+        Ok(config) => config::init(config),
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::from(2);
+        }
+    }
+
+    let command = match parse_args(&args) {
+        Ok(command) => command,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(err) => {
+            eprintln!("Failed to start the runtime: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    match runtime.block_on(run(command)) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parses_klines_range() {
+        let command = parse_args(&args(
+            "klines btcusdt --timeframe 15m --from 2024-01-01 --to 1704153600000 --format jsonl --time utc",
+        ));
+
+        assert_eq!(
+            command,
+            Ok(Command::Klines(KlineArgs {
+                symbol: "BTCUSDT".to_string(),
+                timeframe: "15m".to_string(),
+                from: Some(1_704_067_200_000),
+                to: Some(1_704_153_600_000),
+                format: Format::JsonLines,
+                timestamps: Timestamps::Utc,
+                output: None,
+            }))
+        );
+    }
+
+    #[test]
+    fn parses_chart_options() {
+        let command = parse_args(&args(
+            "chart ethusdt --output eth.png --candles 200 --volume-profile --scale 2",
+        ));
+
+        assert_eq!(
+            command,
+            Ok(Command::Chart(ChartArgs {
+                symbol: "ETHUSDT".to_string(),
+                timeframe: "1h".to_string(),
+                output: "eth.png".to_string(),
+                candles: Some(200),
+                width: None,
+                height: None,
+                scale: Some(2.0),
+                theme: None,
+                volume_profile: true,
+            }))
+        );
+
+        assert!(parse_args(&args("chart BTCUSDT")).is_err());
+        assert!(parse_args(&args("chart BTCUSDT --output a.png --candles 5000")).is_err());
+        assert!(parse_args(&args("chart BTCUSDT --output a.png --width 0")).is_err());
+        assert_eq!(
+            parse_args(&args("chart BTCUSDT --output a.png --height 10000")),
+            Err("--height must be between 1 and 8192, got 10000".to_string())
+        );
+        assert_eq!(
+            parse_args(&args("chart BTCUSDT --output a.png --scale 8")),
+            Err("--scale must be between 0.25 and 4, got 8".to_string())
+        );
+    }
+
+    #[test]
+    fn parses_backtest_sources() {
+        assert_eq!(
+            parse_args(&args(
+                "backtest --csv data/btcusd.csv --strategy rsi:14,30,70 --fee 0 --trades"
+            )),
+            Ok(Command::Backtest(BacktestArgs {
+                symbol: "BTCUSD".to_string(),
+                csv: Some("data/btcusd.csv".to_string()),
+                timeframe: "1h".to_string(),
+                from: None,
+                to: None,
+                strategy: Some("rsi:14,30,70".to_string()),
+                cash: None,
+                fee: Some(Decimal::ZERO),
+                slippage: None,
+                trades: true,
+                chart: None,
+            }))
+        );
+
+        let Ok(Command::Backtest(parsed)) = parse_args(&args(
+            "backtest ethusdt --timeframe 4h --from 2024-01-01 --chart eth.svg",
+        )) else {
+            panic!("expected a backtest");
+        };
+        assert_eq!(parsed.symbol, "ETHUSDT");
+        assert_eq!(parsed.timeframe, "4h");
+        assert_eq!(parsed.from, Some(1_704_067_200_000));
+        assert_eq!(parsed.chart.as_deref(), Some("eth.svg"));
+
+        assert!(parse_args(&args("backtest")).is_err());
+        assert!(parse_args(&args("backtest BTCUSDT --strategy sma:30,10")).is_err());
+        assert!(parse_args(&args("backtest BTCUSDT --cash -5")).is_err());
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert!(parse_args(&args("quotes")).is_err());
+        assert!(parse_args(&args("klines BTCUSDT ETHUSDT")).is_err());
+        assert!(parse_args(&args("klines BTCUSDT --timeframe 3m")).is_err());
+        assert!(parse_args(&args("klines BTCUSDT --from yesterday")).is_err());
+        assert!(parse_args(&args("klines BTCUSDT --format parquet")).is_err());
+        assert!(parse_args(&args("klines BTCUSDT --time est")).is_err());
+        assert!(parse_args(&args("instruments --quote")).is_err());
+        assert_eq!(
+            parse_args(&args("instruments --all --quote usdt")),
+            Ok(Command::Instruments(InstrumentArgs {
+                quote: Some("USDT".to_string()),
+                search: None,
+                all: true,
+            }))
+        );
+    }
+}
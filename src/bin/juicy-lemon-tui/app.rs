@@ -0,0 +1,405 @@
+use crate::terminal::Key;
+use juicy_lemon::cache::CandleCache;
+use juicy_lemon::candle::{Candle, TIMEFRAMES, get_candles_with_limit};
+use juicy_lemon::config;
+use juicy_lemon::store::{Appended, CandleStore, get_candles_with_store, store_candles};
+use juicy_lemon::symbols::{
+    Symbol, SymbolWithPrice, fetch_symbol_prices, get_symbols, read_cached_symbols,
+    write_cached_symbols,
+};
+use juicy_lemon::theme::Palette;
+use juicy_lemon::utils::get_current_select_state;
+use juicy_lemon::watchlist::Watchlist;
+use juicy_lemon::watchlist::io::validate;
+use juicy_lemon::watchlist::tick::PriceTick;
+use std::collections::HashMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Instant;
+
+pub const SUGGESTIONS: usize = 5;
+
+/// Bars fetched on every refresh, enough to close the previous one and update the open one.
+const REFRESH_CANDLES: u32 = 2;
+
+/// The iced app's messages that make sense without a mouse, plus raw key presses.
+#[derive(Debug, Clone)]
+pub enum Message {
+    SymbolsFetched(Vec<Symbol>),
+    FetchError(String),
+    RefetchData,
+    AddSymbol(String),
+    SymbolRemove(String),
+    SelectSymbol(String),
+    ChangeTimeframe(String),
+    PricesUpdated(Vec<SymbolWithPrice>),
+    CandlesFetched(Vec<Candle>, String, String),
+    CandlesLoaded(Vec<Candle>, String, String),
+    CandlesStored(Result<Appended, String>),
+    KeyPressed(Key),
+    /// The terminal got a new size, the next frame is drawn to fit it.
+    Resized,
+}
+
+/// Futures whose results come back as messages, what `iced::Task` is to the GUI.
+pub type Task = Vec<Pin<Box<dyn Future<Output = Message> + Send>>>;
+
+fn perform(future: impl Future<Output = Message> + Send + 'static) -> Task {
+    vec![Box::pin(future)]
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Mode {
+    Normal,
+    Adding(String),
+}
+
+pub struct State {
+    pub instruments: Vec<Symbol>,
+    pub watchlist: Watchlist,
+    pub selected: usize,
+    pub displayed_symbol: Option<Symbol>,
+    pub candles: CandleCache,
+    pub candle_store: CandleStore,
+    pub price_ticks: HashMap<String, PriceTick>,
+    pub mode: Mode,
+    pub status: String,
+    pub quit: bool,
+    pub palette: Palette,
+    initial_symbols: Vec<String>,
+}
+
+impl State {
+    pub fn new(
+        name: String,
+        timeframe: String,
+        initial_symbols: Vec<String>,
+        palette: Palette,
+    ) -> Self {
+        Self {
+            instruments: Vec::new(),
+            watchlist: Watchlist::new(name, timeframe),
+            selected: 0,
+            displayed_symbol: None,
+            candles: CandleCache::from_config(),
+            candle_store: CandleStore::from_config(),
+            price_ticks: HashMap::new(),
+            mode: Mode::Normal,
+            status: "Loading instruments...".to_string(),
+            quit: false,
+            palette,
+            initial_symbols,
+        }
+    }
+
+    pub fn displayed_candles(&self) -> Option<&std::collections::VecDeque<Candle>> {
+        let symbol = self.displayed_symbol.as_ref()?;
+        self.candles.get(&symbol.symbol, &self.watchlist.timeframe)
+    }
+
+    pub fn suggestions(&self) -> Vec<String> {
+        let Mode::Adding(input) = &self.mode else {
+            return Vec::new();
+        };
+
+        let mut suggestions =
+            get_current_select_state(&self.instruments, input, &self.watchlist.symbols, None);
+        suggestions.truncate(SUGGESTIONS);
+        suggestions
+    }
+
+    fn select(&mut self, symbol: &str) {
+        self.selected = self
+            .watchlist
+            .symbols
+            .iter()
+            .position(|s| s.symbol == symbol)
+            .unwrap_or(0);
+    }
+
+    fn selected_symbol(&self) -> Option<String> {
+        self.watchlist
+            .symbols
+            .get(self.selected)
+            .map(|s| s.symbol.clone())
+    }
+}
+
+pub fn init() -> Task {
+    perform(async {
+        if let Some(symbols) = read_cached_symbols() {
+            return Message::SymbolsFetched(symbols);
+        }
+
+        match get_symbols().await {
+            Ok(symbols) => Message::SymbolsFetched(symbols),
+            Err(err) => Message::FetchError(err),
+        }
+    })
+}
+
+fn fetch_prices(symbols: Vec<String>) -> Task {
+    if symbols.is_empty() {
+        return Task::new();
+    }
+
+    perform(async move {
+        match fetch_symbol_prices(symbols).await {
+            Ok(prices) => Message::PricesUpdated(prices),
+            Err(err) => Message::FetchError(err),
+        }
+    })
+}
+
+fn load_candles(state: &State, symbol: &Symbol) -> Task {
+    let store = state.candle_store.clone();
+    let symbol_name = symbol.symbol.clone();
+    let timeframe = state.watchlist.timeframe.clone();
+    let decimals = symbol.decimals;
+
+    perform(async move {
+        match get_candles_with_store(store, symbol_name.clone(), timeframe.clone(), decimals).await
+        {
+            Ok(candles) => Message::CandlesLoaded(candles, symbol_name, timeframe),
+            Err(err) => Message::FetchError(err),
+        }
+    })
+}
+
+fn store(state: &State, candles: Vec<Candle>, symbol: String, timeframe: String) -> Task {
+    let store = state.candle_store.clone();
+
+    perform(async move {
+        Message::CandlesStored(store_candles(store, symbol, timeframe, candles).await)
+    })
+}
+
+fn on_key(state: &mut State, key: Key) -> Task {
+    if key == Key::CtrlC {
+        state.quit = true;
+        return Task::new();
+    }
+
+    match &mut state.mode {
+        Mode::Adding(input) => {
+            match key {
+                Key::Char(c) => input.push(c.to_ascii_uppercase()),
+                Key::Backspace => {
+                    input.pop();
+                }
+                Key::Esc => state.mode = Mode::Normal,
+                Key::Enter => {
+                    let typed = input.clone();
+                    let symbol = state.suggestions().into_iter().next().unwrap_or(typed);
+                    state.mode = Mode::Normal;
+
+                    return update(state, Message::AddSymbol(symbol));
+                }
+                _ => {}
+            }
+
+            Task::new()
+        }
+        Mode::Normal => {
+            let count = state.watchlist.symbols.len();
+            let timeframe = TIMEFRAMES
+                .iter()
+                .position(|t| *t == state.watchlist.timeframe)
+                .unwrap_or(0);
+
+            let message = match key {
+                Key::Char('q') => {
+                    state.quit = true;
+                    return Task::new();
+                }
+                Key::Char('a') => {
+                    state.mode = Mode::Adding(String::new());
+                    return Task::new();
+                }
+                Key::Char('d') | Key::Delete => state.selected_symbol().map(Message::SymbolRemove),
+                Key::Char('k') | Key::Up if count > 0 => {
+                    state.selected = (state.selected + count - 1) % count;
+                    state.selected_symbol().map(Message::SelectSymbol)
+                }
+                Key::Char('j') | Key::Down if count > 0 => {
+                    state.selected = (state.selected + 1) % count;
+                    state.selected_symbol().map(Message::SelectSymbol)
+                }
+                Key::Char('t') | Key::Right => Some(Message::ChangeTimeframe(
+                    TIMEFRAMES[(timeframe + 1) % TIMEFRAMES.len()].to_string(),
+                )),
+                Key::Char('T') | Key::Left => Some(Message::ChangeTimeframe(
+                    TIMEFRAMES[(timeframe + TIMEFRAMES.len() - 1) % TIMEFRAMES.len()].to_string(),
+                )),
+                _ => None,
+            };
+
+            message
+                .map(|message| update(state, message))
+                .unwrap_or_default()
+        }
+    }
+}
+
+pub fn update(state: &mut State, message: Message) -> Task {
+    match message {
+        Message::SymbolsFetched(instruments) => {
+            write_cached_symbols(&instruments);
+            state.instruments = instruments;
+
+            let (symbols, issues) = validate(&state.initial_symbols, &state.instruments);
+            state.status = issues
+                .iter()
+                .map(|issue| issue.to_string())
+                .collect::<Vec<_>>()
+                .join(", ");
+            state.watchlist.symbols = symbols;
+
+            match state.selected_symbol() {
+                Some(symbol) => update(state, Message::SelectSymbol(symbol)),
+                None => Task::new(),
+            }
+        }
+        Message::FetchError(err) => {
+            state.status = err;
+            Task::new()
+        }
+        Message::RefetchData => {
+            let mut tasks = fetch_prices(state.watchlist.symbol_names());
+
+            if let Some(symbol) = state.displayed_symbol.clone() {
+                let timeframe = state.watchlist.timeframe.clone();
+
+                tasks.extend(perform(async move {
+                    match get_candles_with_limit(
+                        &symbol.symbol,
+                        &timeframe,
+                        symbol.decimals,
+                        REFRESH_CANDLES,
+                    )
+                    .await
+                    {
+                        Ok(candles) => Message::CandlesFetched(candles, symbol.symbol, timeframe),
+                        Err(err) => Message::FetchError(err),
+                    }
+                }));
+            }
+
+            tasks
+        }
+        Message::AddSymbol(symbol) => {
+            if let Err(err) = state.watchlist.add(&state.instruments, &symbol) {
+                state.status = err;
+                return Task::new();
+            }
+            state.status.clear();
+
+            let mut tasks = fetch_prices(vec![symbol.clone()]);
+
+            // The list is sorted, so the highlight follows the symbol it was on.
+            let shown = state
+                .displayed_symbol
+                .as_ref()
+                .map_or(symbol.clone(), |s| s.symbol.clone());
+            state.select(&shown);
+
+            if state.displayed_symbol.is_none() {
+                tasks.extend(update(state, Message::SelectSymbol(symbol)));
+            }
+
+            tasks
+        }
+        Message::SymbolRemove(symbol) => {
+            let next = state.watchlist.remove(&symbol).map(|s| s.symbol.clone());
+            state.price_ticks.remove(&symbol);
+            state.candles.remove_symbol(&symbol);
+
+            let shown = match &state.displayed_symbol {
+                Some(s) if s.symbol != symbol => Some(s.symbol.clone()),
+                _ => next.clone(),
+            };
+            if let Some(shown) = shown {
+                state.select(&shown);
+            }
+
+            if state
+                .displayed_symbol
+                .as_ref()
+                .is_some_and(|s| s.symbol == symbol)
+            {
+                state.displayed_symbol = None;
+
+                if let Some(next) = next {
+                    return update(state, Message::SelectSymbol(next));
+                }
+            }
+
+            Task::new()
+        }
+        Message::SelectSymbol(symbol) => {
+            let Some(instrument) = state.watchlist.symbols.iter().find(|s| s.symbol == symbol)
+            else {
+                return Task::new();
+            };
+
+            if state
+                .displayed_symbol
+                .as_ref()
+                .is_some_and(|s| s.symbol == symbol)
+            {
+                return Task::new();
+            }
+
+            let instrument = instrument.clone();
+            let task = load_candles(state, &instrument);
+            state.displayed_symbol = Some(instrument);
+
+            task
+        }
+        Message::ChangeTimeframe(timeframe) => {
+            state.watchlist.timeframe = timeframe;
+
+            match state.displayed_symbol.clone() {
+                Some(symbol) => load_candles(state, &symbol),
+                None => Task::new(),
+            }
+        }
+        Message::PricesUpdated(prices) => {
+            let ticks = state.watchlist.apply_prices(&prices, Instant::now());
+            state.price_ticks.extend(ticks);
+
+            Task::new()
+        }
+        Message::CandlesFetched(candles, symbol, timeframe) => {
+            let max_len = config::get().history.candles;
+            let gap = state.candles.merge(&symbol, &timeframe, &candles, max_len);
+            let mut tasks = store(state, candles, symbol.clone(), timeframe);
+
+            if let (Ok(Some(_)), Some(displayed)) = (gap, state.displayed_symbol.clone())
+                && displayed.symbol == symbol
+            {
+                tasks.extend(load_candles(state, &displayed));
+            }
+
+            tasks
+        }
+        Message::CandlesLoaded(candles, symbol, timeframe) => {
+            let tasks = store(state, candles.clone(), symbol.clone(), timeframe.clone());
+            state.candles.insert(&symbol, &timeframe, candles.into());
+            state
+                .candles
+                .evict(Some((symbol.as_str(), timeframe.as_str())));
+
+            tasks
+        }
+        Message::CandlesStored(result) => {
+            if let Err(err) = result {
+                state.status = err;
+            }
+
+            Task::new()
+        }
+        Message::KeyPressed(key) => on_key(state, key),
+        Message::Resized => Task::new(),
+    }
+}
@@ -0,0 +1,212 @@
+mod app;
+mod render;
+mod terminal;
+
+use app::{Message, State, Task, update};
+use juicy_lemon::config::{self, Config};
+use juicy_lemon::theme::{Palette, load_palettes};
+use juicy_lemon::watchlist::io::{WatchlistFormat, parse};
+use std::io::Read;
+use std::process::ExitCode;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::Duration;
+use terminal::{Terminal, parse_keys};
+use tokio::runtime::Runtime;
+
+/// How often the terminal size is checked for a resize.
+const RESIZE_POLL: Duration = Duration::from_millis(250);
+
+const USAGE: &str = "\
+Usage: juicy-lemon-tui [SYMBOL...] [--watchlist FILE] [--timeframe TF]
+                       [--config FILE] [--set section.key=value]...
+
+Shows a watchlist with live prices and a candlestick chart of the selected symbol.
+FILE is a watchlist exported from the app as .json, .csv or .txt. Without symbols or
+a watchlist, defaults.symbols from the config is shown.
+";
+
+fn parse_args(args: &[String]) -> Result<(String, String, Vec<String>), String> {
+    let defaults = &config::get().defaults;
+    let mut name = defaults.watchlist.clone();
+    let mut timeframe = defaults.timeframe.clone();
+    let mut symbols = Vec::new();
+    let mut iter = args.iter();
+
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--watchlist" => {
+                let path = iter.next().ok_or("Missing value for --watchlist")?;
+                let format = WatchlistFormat::from_path(path)
+                    .ok_or("Use a .json, .csv or .txt watchlist file")?;
+                let content = std::fs::read_to_string(path)
+                    .map_err(|err| format!("Cannot read {}: {}", path, err))?;
+                let file = parse(format, &content)?;
+
+                name = file.name.unwrap_or(name);
+                timeframe = file.timeframe.unwrap_or(timeframe);
+                symbols.extend(file.symbols);
+            }
+            "--timeframe" => {
+                timeframe = iter.next().ok_or("Missing value for --timeframe")?.clone();
+            }
+            _ if arg.starts_with("--") => return Err(format!("Unknown option {}", arg)),
+            _ => symbols.push(arg.to_uppercase()),
+        }
+    }
+
+    if symbols.is_empty() {
+        symbols = defaults.symbols.clone();
+    }
+
+    Ok((name, timeframe, symbols))
+}
+
+/// The palette `ui.theme` names, built in or from the palettes directory.
+fn palette() -> Result<Palette, String> {
+    let ui = &config::get().ui;
+    let palettes = load_palettes(&ui.palettes_dir)?;
+
+    palettes
+        .iter()
+        .find(|(name, _)| *name == ui.theme)
+        .map(|(_, palette)| *palette)
+        .ok_or_else(|| {
+            let names = palettes
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>();
+            format!(
+                "Unknown theme {}, expected one of {}",
+                ui.theme,
+                names.join(", ")
+            )
+        })
+}
+
+fn spawn(runtime: &Runtime, sender: &Sender<Message>, task: Task) {
+    for future in task {
+        let sender = sender.clone();
+        runtime.spawn(async move {
+            let _ = sender.send(future.await);
+        });
+    }
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    if args.iter().any(|a| a == "-h" || a == "--help") {
+        print!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    match Config::from_args(&mut args) {
+        Ok(config) => config::init(config),
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::from(2);
+        }
+    }
+
+    let (name, timeframe, symbols) = match parse_args(&args) {
+        Ok(parsed) => parsed,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let palette = match palette() {
+        Ok(palette) => palette,
+        Err(err) => {
+            eprintln!("{}", err);
+            return ExitCode::from(2);
+        }
+    };
+
+    let runtime = match Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(err) => {
+            eprintln!("Failed to start the runtime: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let terminal = match Terminal::enter() {
+        Ok(terminal) => terminal,
+        Err(err) => {
+            eprintln!("Cannot use this terminal: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let (sender, receiver) = mpsc::channel();
+
+    let keys = sender.clone();
+    thread::spawn(move || {
+        let mut buffer = [0u8; 64];
+        let mut stdin = std::io::stdin();
+
+        while let Ok(read) = stdin.read(&mut buffer) {
+            if read == 0 {
+                break;
+            }
+
+            for key in parse_keys(&buffer[..read]) {
+                if keys.send(Message::KeyPressed(key)).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    let ticks = sender.clone();
+    let interval = Duration::from_secs(config::get().refresh.terminal_secs);
+    thread::spawn(move || {
+        while ticks.send(Message::RefetchData).is_ok() {
+            thread::sleep(interval);
+        }
+    });
+
+    let resizes = sender.clone();
+    thread::spawn(move || {
+        let mut size = terminal::size();
+
+        loop {
+            thread::sleep(RESIZE_POLL);
+
+            let current = terminal::size();
+            if current != size {
+                size = current;
+                if resizes.send(Message::Resized).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+
+    let mut state = State::new(name, timeframe, symbols, palette);
+    spawn(&runtime, &sender, app::init());
+    let (mut rows, mut cols) = terminal::size();
+
+    while !state.quit {
+        terminal.draw(&render::render(&state, rows, cols));
+
+        let Ok(message) = receiver.recv() else {
+            break;
+        };
+
+        if let Message::Resized = message {
+            (rows, cols) = terminal::size();
+        }
+
+        let task = update(&mut state, message);
+        spawn(&runtime, &sender, task);
+    }
+
+    drop(terminal);
+    runtime.shutdown_background();
+
+    ExitCode::SUCCESS
+}
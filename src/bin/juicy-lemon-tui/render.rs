@@ -0,0 +1,303 @@
+use crate::app::{Mode, State};
+use juicy_lemon::candle::{Candle, TIMEFRAMES};
+use juicy_lemon::theme::{Palette, Rgb};
+use juicy_lemon::watchlist::tick::TickDirection;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::*;
+use std::fmt::Write;
+
+const LIST_WIDTH: usize = 30;
+const AXIS_WIDTH: usize = 12;
+const LABEL_EVERY: usize = 4;
+
+/// Screen cells on the palette's background, turned into one escape-coded string per frame.
+pub struct Grid {
+    cols: usize,
+    palette: Palette,
+    cells: Vec<Vec<(char, Rgb)>>,
+}
+
+impl Grid {
+    pub fn new(rows: usize, cols: usize, palette: &Palette) -> Self {
+        Self {
+            cols,
+            palette: *palette,
+            cells: vec![vec![(' ', palette.text); cols]; rows],
+        }
+    }
+
+    pub fn put(&mut self, row: usize, col: usize, text: &str, color: Rgb) {
+        let Some(line) = self.cells.get_mut(row) else {
+            return;
+        };
+
+        for (i, c) in text.chars().enumerate() {
+            if let Some(cell) = line.get_mut(col + i) {
+                *cell = (c, color);
+            }
+        }
+    }
+
+    pub fn to_ansi(&self) -> String {
+        let Rgb(r, g, b) = self.palette.background;
+        let mut frame = format!("\x1b[H\x1b[48;2;{};{};{}m", r, g, b);
+        let mut current = None;
+
+        for (i, line) in self.cells.iter().enumerate() {
+            let _ = write!(frame, "\x1b[{};1H", i + 1);
+
+            for &(c, color) in line.iter().take(self.cols) {
+                if current != Some(color) {
+                    let _ = write!(frame, "\x1b[38;2;{};{};{}m", color.0, color.1, color.2);
+                    current = Some(color);
+                }
+                frame.push(c);
+            }
+        }
+
+        frame.push_str("\x1b[0m");
+        frame
+    }
+
+    #[cfg(test)]
+    fn row(&self, row: usize) -> String {
+        self.cells[row].iter().map(|(c, _)| *c).collect()
+    }
+}
+
+fn candle_color(palette: &Palette, candle: &Candle) -> Rgb {
+    if candle.is_bullish() {
+        palette.bull
+    } else {
+        palette.bear
+    }
+}
+
+/// One column per candle, each row split in half so bodies move in half-cell steps.
+pub fn render_candles(
+    grid: &mut Grid,
+    candles: &[Candle],
+    top: usize,
+    left: usize,
+    height: usize,
+    width: usize,
+    decimals: u32,
+) {
+    let palette = grid.palette;
+    let candles = &candles[candles.len().saturating_sub(width)..];
+
+    let (Some(low), Some(high)) = (
+        candles.iter().map(|c| c.low).min(),
+        candles.iter().map(|c| c.high).max(),
+    ) else {
+        return;
+    };
+
+    if height == 0 {
+        return;
+    }
+
+    let low = low.to_f64().unwrap_or_default();
+    let high = high.to_f64().unwrap_or_default();
+    let step = ((high - low) / height as f64).max(f64::EPSILON);
+
+    for (x, candle) in candles.iter().enumerate() {
+        let open = candle.open.to_f64().unwrap_or_default();
+        let close = candle.close.to_f64().unwrap_or_default();
+        let body_top = open.max(close);
+        let body_bottom = open.min(close);
+        let wick_top = candle.high.to_f64().unwrap_or_default();
+        let wick_bottom = candle.low.to_f64().unwrap_or_default();
+
+        for y in 0..height {
+            let band_top = high - y as f64 * step;
+            let band_mid = band_top - step / 2.0;
+            let band_bottom = band_top - step;
+
+            let upper = body_top > band_mid && body_bottom < band_top;
+            let lower = body_top > band_bottom && body_bottom < band_mid;
+
+            let c = match (upper, lower) {
+                (true, true) => '█',
+                (true, false) => '▀',
+                (false, true) => '▄',
+                _ if wick_top > band_bottom && wick_bottom < band_top => '│',
+                _ => continue,
+            };
+
+            grid.put(
+                top + y,
+                left + x,
+                &c.to_string(),
+                candle_color(&palette, candle),
+            );
+        }
+    }
+
+    for y in (0..height).step_by(LABEL_EVERY) {
+        let price = Decimal::from_f64(high - (y as f64 + 0.5) * step)
+            .unwrap_or_default()
+            .round_dp(decimals);
+
+        grid.put(
+            top + y,
+            left + width + 1,
+            &format!("─ {}", price),
+            palette.muted,
+        );
+    }
+}
+
+fn render_watchlist(grid: &mut Grid, state: &State, top: usize, height: usize) {
+    let palette = &state.palette;
+
+    for (i, symbol) in state.watchlist.symbols.iter().take(height).enumerate() {
+        let selected = i == state.selected;
+        let marker = if selected { ">" } else { " " };
+        let price = symbol.price.map(|p| p.to_string()).unwrap_or_default();
+
+        grid.put(
+            top + i,
+            0,
+            &format!("{} {:<12}{:>14}", marker, symbol.symbol, price),
+            if selected {
+                palette.primary
+            } else {
+                palette.text
+            },
+        );
+
+        if let Some(tick) = state.price_ticks.get(&symbol.symbol) {
+            let (arrow, color) = match tick.direction {
+                TickDirection::Up => ("▲", palette.bull),
+                TickDirection::Down => ("▼", palette.bear),
+            };
+            grid.put(top + i, LIST_WIDTH - 2, arrow, color);
+        }
+    }
+
+    if state.watchlist.symbols.is_empty() {
+        grid.put(top, 2, "Press a to add a symbol", palette.muted);
+    }
+}
+
+fn render_header(grid: &mut Grid, state: &State) {
+    let palette = &state.palette;
+    let title = match &state.displayed_symbol {
+        Some(symbol) => format!("Juicy Lemon · {} · {}", state.watchlist.name, symbol.symbol),
+        None => format!("Juicy Lemon · {}", state.watchlist.name),
+    };
+    grid.put(0, 0, &title, palette.text);
+
+    let mut col = grid
+        .cols
+        .saturating_sub(TIMEFRAMES.iter().map(|t| t.len() + 3).sum());
+
+    for timeframe in TIMEFRAMES {
+        let active = timeframe == state.watchlist.timeframe;
+        let label = if active {
+            format!("[{}]", timeframe)
+        } else {
+            format!(" {} ", timeframe)
+        };
+
+        let color = if active {
+            palette.primary
+        } else {
+            palette.muted
+        };
+        grid.put(0, col, &label, color);
+        col += label.len();
+    }
+}
+
+fn render_footer(grid: &mut Grid, state: &State, rows: usize) {
+    let palette = &state.palette;
+    grid.put(rows - 2, 0, &state.status, palette.bear);
+
+    match &state.mode {
+        Mode::Normal => grid.put(
+            rows - 1,
+            0,
+            "a add  d remove  ↑↓/jk select  ←→/tT timeframe  q quit",
+            palette.muted,
+        ),
+        Mode::Adding(input) => {
+            let prompt = format!("Add symbol: {}_", input);
+            grid.put(rows - 1, 0, &prompt, palette.primary);
+            grid.put(
+                rows - 1,
+                prompt.chars().count() + 2,
+                &state.suggestions().join("  "),
+                palette.muted,
+            );
+        }
+    }
+}
+
+pub fn render(state: &State, rows: usize, cols: usize) -> String {
+    let mut grid = Grid::new(rows, cols, &state.palette);
+
+    if rows < 6 || cols < LIST_WIDTH + AXIS_WIDTH + 10 {
+        grid.put(0, 0, "Terminal too small", state.palette.bear);
+        return grid.to_ansi();
+    }
+
+    let body_top = 2;
+    let body_height = rows - 4;
+
+    render_header(&mut grid, state);
+    render_watchlist(&mut grid, state, body_top, body_height);
+
+    if let (Some(symbol), Some(candles)) = (&state.displayed_symbol, state.displayed_candles()) {
+        let candles = candles.iter().cloned().collect::<Vec<_>>();
+
+        render_candles(
+            &mut grid,
+            &candles,
+            body_top,
+            LIST_WIDTH + 2,
+            body_height,
+            cols - LIST_WIDTH - 2 - AXIS_WIDTH,
+            symbol.decimals,
+        );
+    }
+
+    render_footer(&mut grid, state, rows);
+    grid.to_ansi()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use juicy_lemon::theme::LIGHT;
+    use rust_decimal::dec;
+
+    #[test]
+    fn bodies_and_wicks_use_block_characters() {
+        let candle = |open, high, low, close| Candle {
+            open,
+            high,
+            low,
+            close,
+            volume: Decimal::ZERO,
+            open_time: 0,
+            close_time: 0,
+        };
+        let candles = [
+            candle(dec!(2), dec!(4), dec!(0), dec!(3)),
+            candle(dec!(3), dec!(3), dec!(1), dec!(1)),
+        ];
+
+        let mut grid = Grid::new(4, 20, &LIGHT);
+        render_candles(&mut grid, &candles, 0, 0, 4, 2, 0);
+
+        let columns = (0..4)
+            .map(|row| grid.row(row).chars().take(2).collect::<String>())
+            .collect::<Vec<_>>();
+
+        assert_eq!(columns, vec!["│ ", "██", "│█", "│ "]);
+        assert_eq!(grid.cells[1][0].1, LIGHT.bull);
+        assert_eq!(grid.cells[1][1].1, LIGHT.bear);
+    }
+}
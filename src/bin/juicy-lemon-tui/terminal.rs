@@ -0,0 +1,129 @@
+use crossterm::terminal::{disable_raw_mode, enable_raw_mode};
+use std::io::{self, Write};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Up,
+    Down,
+    Left,
+    Right,
+    Enter,
+    Backspace,
+    Delete,
+    Esc,
+    CtrlC,
+}
+
+/// The key of a CSI sequence, whose parameter bytes run up to a final byte in `0x40..=0x7e`.
+/// Returns the bytes it takes after `ESC [`, the rest of the read when it is cut off.
+fn csi(bytes: &[u8]) -> (Option<Key>, usize) {
+    let Some(end) = bytes.iter().position(|b| (0x40..=0x7e).contains(b)) else {
+        return (None, bytes.len());
+    };
+
+    let key = match &bytes[..=end] {
+        b"A" => Some(Key::Up),
+        b"B" => Some(Key::Down),
+        b"C" => Some(Key::Right),
+        b"D" => Some(Key::Left),
+        b"3~" => Some(Key::Delete),
+        _ => None,
+    };
+
+    (key, end + 1)
+}
+
+/// Splits one read from a raw mode terminal into keys, escape sequences included.
+/// Sequences for keys the app has no use for, like Home or PageUp, are skipped whole.
+pub fn parse_keys(bytes: &[u8]) -> Vec<Key> {
+    let mut keys = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let (key, len) = match bytes[i..] {
+            [0x1b, b'[', ..] => {
+                let (key, len) = csi(&bytes[i + 2..]);
+                (key, len + 2)
+            }
+            [0x1b, ..] => (Some(Key::Esc), 1),
+            [0x03, ..] => (Some(Key::CtrlC), 1),
+            [b'\r' | b'\n', ..] => (Some(Key::Enter), 1),
+            [0x7f | 0x08, ..] => (Some(Key::Backspace), 1),
+            [byte, ..] if byte.is_ascii() && !byte.is_ascii_control() => {
+                (Some(Key::Char(byte as char)), 1)
+            }
+            _ => (None, 1),
+        };
+
+        keys.extend(key);
+        i += len;
+    }
+
+    keys
+}
+
+/// Rows and columns, 24x80 when the size cannot be read.
+pub fn size() -> (usize, usize) {
+    crossterm::terminal::size()
+        .map(|(cols, rows)| (rows as usize, cols as usize))
+        .unwrap_or((24, 80))
+}
+
+/// Raw mode on the alternate screen, the previous settings come back on drop.
+pub struct Terminal;
+
+impl Terminal {
+    pub fn enter() -> Result<Self, String> {
+        enable_raw_mode().map_err(|err| err.to_string())?;
+
+        print!("\x1b[?1049h\x1b[?25l");
+        let _ = io::stdout().flush();
+
+        Ok(Self)
+    }
+
+    pub fn draw(&self, frame: &str) {
+        let mut stdout = io::stdout().lock();
+        let _ = stdout.write_all(frame.as_bytes());
+        let _ = stdout.flush();
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        print!("\x1b[0m\x1b[?25h\x1b[?1049l");
+        let _ = io::stdout().flush();
+        let _ = disable_raw_mode();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_escape_sequences_and_text() {
+        assert_eq!(
+            parse_keys(b"a\x1b[A\x1b[3~\x7f\r\x1bq\x03"),
+            vec![
+                Key::Char('a'),
+                Key::Up,
+                Key::Delete,
+                Key::Backspace,
+                Key::Enter,
+                Key::Esc,
+                Key::Char('q'),
+                Key::CtrlC,
+            ]
+        );
+    }
+
+    #[test]
+    fn skips_unknown_sequences_whole() {
+        assert_eq!(
+            parse_keys(b"\x1b[5~a\x1b[H\x1b[1;5Ab\x1b[B\x1b[12"),
+            vec![Key::Char('a'), Key::Char('b'), Key::Down]
+        );
+    }
+}
@@ -0,0 +1,325 @@
+use crate::candle::{Candle, CandleGap, MergeError, PlotCandle, candle_gaps, merge_candles};
+use crate::config;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::mem::size_of;
+
+type Timeframe = String;
+
+#[derive(Debug, Default)]
+struct Series {
+    candles: VecDeque<Candle>,
+    /// The same bars as `f64` for the chart, kept in step with `candles`.
+    plot: VecDeque<PlotCandle>,
+    last_used: u64,
+    /// Open times of the gaps already fetched, some ranges never get bars.
+    requested_gaps: HashSet<u64>,
+}
+
+impl Series {
+    fn bytes(&self, symbol: &str, timeframe: &str) -> usize {
+        size_of::<Series>()
+            + self.candles.capacity() * size_of::<Candle>()
+            + self.plot.capacity() * size_of::<PlotCandle>()
+            + symbol.len()
+            + timeframe.len()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SeriesSize {
+    pub symbol: String,
+    pub timeframe: String,
+    pub candles: usize,
+    pub bytes: usize,
+    pub visible: bool,
+}
+
+/// Candles per (symbol, timeframe) series with a byte budget. When a write pushes the
+/// total over it, series that are not on screen go least recently used first. Keyed by
+/// symbol then timeframe, so lookups from `draw` borrow instead of allocating.
+#[derive(Debug)]
+pub struct CandleCache {
+    series: HashMap<String, HashMap<Timeframe, Series>>,
+    clock: u64,
+    pub max_bytes: usize,
+}
+
+impl CandleCache {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            series: HashMap::new(),
+            clock: 0,
+            max_bytes,
+        }
+    }
+
+    pub fn from_config() -> Self {
+        Self::new(config::get().cache.memory_kb * 1024)
+    }
+
+    pub fn get(&self, symbol: &str, timeframe: &str) -> Option<&VecDeque<Candle>> {
+        self.series
+            .get(symbol)?
+            .get(timeframe)
+            .map(|series| &series.candles)
+    }
+
+    /// The bars of `get` as `f64`, index for index.
+    pub fn plot(&self, symbol: &str, timeframe: &str) -> Option<&VecDeque<PlotCandle>> {
+        self.series
+            .get(symbol)?
+            .get(timeframe)
+            .map(|series| &series.plot)
+    }
+
+    /// Creates the series when missing and marks it as the most recently used.
+    fn touch(&mut self, symbol: &str, timeframe: &str) -> &mut Series {
+        self.clock += 1;
+
+        let series = self
+            .series
+            .entry(symbol.to_string())
+            .or_default()
+            .entry(timeframe.to_string())
+            .or_default();
+        series.last_used = self.clock;
+
+        series
+    }
+
+    /// Replaces the series, its gaps can be fetched again.
+    pub fn insert(&mut self, symbol: &str, timeframe: &str, candles: VecDeque<Candle>) {
+        let series = self.touch(symbol, timeframe);
+        series.plot = candles.iter().map(PlotCandle::from).collect();
+        series.candles = candles;
+        series.requested_gaps.clear();
+    }
+
+    /// Merges fetched bars into the series and returns the oldest gap left that was not
+    /// asked for yet, marking it as asked. A range the exchange has no bars for, like an
+    /// outage, keeps showing up as a gap on every merge but is only fetched once.
+    pub fn merge(
+        &mut self,
+        symbol: &str,
+        timeframe: &str,
+        candles: &[Candle],
+        max_len: usize,
+    ) -> Result<Option<CandleGap>, MergeError> {
+        let series = self.touch(symbol, timeframe);
+        merge_candles(&mut series.candles, candles, timeframe, max_len)?;
+
+        // The same steps as the merge, so only the bars that came in are converted.
+        for candle in candles {
+            match series
+                .plot
+                .binary_search_by_key(&candle.open_time, |c| c.open_time)
+            {
+                Ok(index) => series.plot[index] = candle.into(),
+                Err(index) => series.plot.insert(index, candle.into()),
+            }
+        }
+        while series.plot.len() > max_len {
+            series.plot.pop_front();
+        }
+
+        let gap = candle_gaps(&series.candles, timeframe)
+            .find(|gap| !series.requested_gaps.contains(&gap.from));
+        if let Some(gap) = gap {
+            series.requested_gaps.insert(gap.from);
+        }
+
+        Ok(gap)
+    }
+
+    pub fn remove_symbol(&mut self, symbol: &str) {
+        self.series.remove(symbol);
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (&String, &Timeframe, &Series)> {
+        self.series.iter().flat_map(|(symbol, timeframes)| {
+            timeframes
+                .iter()
+                .map(move |(timeframe, series)| (symbol, timeframe, series))
+        })
+    }
+
+    pub fn total_bytes(&self) -> usize {
+        self.iter()
+            .map(|(symbol, timeframe, series)| series.bytes(symbol, timeframe))
+            .sum()
+    }
+
+    /// Drops least recently used series other than `visible` until the budget holds, and
+    /// returns the (symbol, timeframe) of each one dropped.
+    pub fn evict(&mut self, visible: Option<(&str, &str)>) -> Vec<(String, Timeframe)> {
+        let mut total = self.total_bytes();
+        let mut evicted = Vec::new();
+
+        if total <= self.max_bytes {
+            return evicted;
+        }
+
+        let mut candidates = self
+            .iter()
+            .filter(|(symbol, timeframe, _)| visible != Some((symbol.as_str(), timeframe.as_str())))
+            .map(|(symbol, timeframe, series)| {
+                (
+                    (symbol.clone(), timeframe.clone()),
+                    series.last_used,
+                    series.bytes(symbol, timeframe),
+                )
+            })
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|(_, last_used, _)| *last_used);
+
+        for ((symbol, timeframe), _, bytes) in candidates {
+            if total <= self.max_bytes {
+                break;
+            }
+
+            if let Some(timeframes) = self.series.get_mut(&symbol) {
+                timeframes.remove(&timeframe);
+                if timeframes.is_empty() {
+                    self.series.remove(&symbol);
+                }
+            }
+            total -= bytes;
+            evicted.push((symbol, timeframe));
+        }
+
+        evicted
+    }
+
+    /// Largest series first, for the cache debug view.
+    pub fn sizes(&self, visible: Option<(&str, &str)>) -> Vec<SeriesSize> {
+        let mut sizes = self
+            .iter()
+            .map(|(symbol, timeframe, series)| SeriesSize {
+                symbol: symbol.clone(),
+                timeframe: timeframe.clone(),
+                candles: series.candles.len(),
+                bytes: series.bytes(symbol, timeframe),
+                visible: visible == Some((symbol.as_str(), timeframe.as_str())),
+            })
+            .collect::<Vec<_>>();
+
+        sizes.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(a.symbol.cmp(&b.symbol)));
+        sizes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::candles;
+    use rust_decimal::Decimal;
+
+    const MINUTE: u64 = 60_000;
+
+    #[test]
+    fn an_empty_gap_fetch_is_not_repeated() {
+        let mut cache = CandleCache::new(usize::MAX);
+        cache.insert("BTCUSDT", "1m", candles(0..3, MINUTE).into());
+
+        let gap = cache
+            .merge("BTCUSDT", "1m", &candles(6..8, MINUTE), 100)
+            .unwrap();
+        assert_eq!(
+            gap,
+            Some(CandleGap {
+                from: 3 * MINUTE,
+                count: 3
+            })
+        );
+
+        // The exchange had nothing for the gap, and the next refresh only moves on.
+        assert_eq!(cache.merge("BTCUSDT", "1m", &[], 100), Ok(None));
+        assert_eq!(
+            cache.merge("BTCUSDT", "1m", &candles(7..9, MINUTE), 100),
+            Ok(None)
+        );
+
+        // A newer gap is still fetched, and a reload may try the old one again.
+        let gap = cache
+            .merge("BTCUSDT", "1m", &candles(10..11, MINUTE), 100)
+            .unwrap();
+        assert_eq!(gap.map(|g| g.from), Some(9 * MINUTE));
+
+        let series = cache.get("BTCUSDT", "1m").unwrap().clone();
+        cache.insert("BTCUSDT", "1m", series);
+        let gap = cache.merge("BTCUSDT", "1m", &[], 100).unwrap();
+        assert_eq!(gap.map(|g| g.from), Some(3 * MINUTE));
+    }
+
+    #[test]
+    fn keeps_the_plot_bars_in_step_with_the_candles() {
+        let mut cache = CandleCache::new(usize::MAX);
+        cache.insert("BTCUSDT", "1m", candles(2..6, MINUTE).into());
+
+        // Older bars go in front, a revised bar is replaced and the oldest fall off.
+        let mut revised = candles(5..8, MINUTE);
+        revised[0].close += Decimal::ONE;
+        cache
+            .merge("BTCUSDT", "1m", &candles(0..2, MINUTE), 100)
+            .unwrap();
+        cache.merge("BTCUSDT", "1m", &revised, 6).unwrap();
+
+        let expected = cache
+            .get("BTCUSDT", "1m")
+            .unwrap()
+            .iter()
+            .map(PlotCandle::from)
+            .collect::<VecDeque<_>>();
+        assert_eq!(expected.len(), 6);
+        assert_eq!(expected[0].open_time, 2 * MINUTE);
+        assert_eq!(cache.plot("BTCUSDT", "1m"), Some(&expected));
+    }
+
+    #[test]
+    fn counts_the_bytes_of_each_series() {
+        let mut cache = CandleCache::new(usize::MAX);
+        cache.insert("BTCUSDT", "1m", candles(0..10, MINUTE).into());
+        cache.insert("ETHUSDT", "15m", candles(0..4, MINUTE).into());
+
+        let bar = size_of::<Candle>() + size_of::<PlotCandle>();
+        let btc = size_of::<Series>() + 10 * bar + "BTCUSDT1m".len();
+        let eth = size_of::<Series>() + 4 * bar + "ETHUSDT15m".len();
+        assert_eq!(cache.total_bytes(), btc + eth);
+
+        let sizes = cache.sizes(Some(("ETHUSDT", "15m")));
+        assert_eq!(
+            sizes
+                .iter()
+                .map(|s| (s.symbol.as_str(), s.candles, s.bytes, s.visible))
+                .collect::<Vec<_>>(),
+            [("BTCUSDT", 10, btc, false), ("ETHUSDT", 4, eth, true)]
+        );
+
+        cache.remove_symbol("BTCUSDT");
+        assert_eq!(cache.total_bytes(), eth);
+    }
+
+    #[test]
+    fn evicts_least_recently_used_but_never_the_visible_series() {
+        let mut cache = CandleCache::new(usize::MAX);
+        for symbol in ["A", "B", "C"] {
+            cache.insert(symbol, "1h", candles(0..10, MINUTE).into());
+        }
+        let one = cache.total_bytes() / 3;
+
+        // Writing to A makes B the least recently used.
+        cache.insert("A", "1h", candles(0..10, MINUTE).into());
+        cache.max_bytes = one * 2;
+        assert_eq!(cache.evict(None), [("B".to_string(), "1h".to_string())]);
+        assert!(cache.get("B", "1h").is_none());
+
+        // Nothing fits, but the series on screen stays.
+        cache.max_bytes = 0;
+        assert_eq!(
+            cache.evict(Some(("C", "1h"))),
+            [("A".to_string(), "1h".to_string())]
+        );
+        assert_eq!(cache.get("C", "1h").map(|c| c.len()), Some(10));
+        assert!(cache.evict(Some(("C", "1h"))).is_empty());
+    }
+}
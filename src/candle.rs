@@ -0,0 +1,344 @@
+use crate::config;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Candle {
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
+    #[serde(default)]
+    pub volume: Decimal,
+    pub open_time: u64,
+    pub close_time: u64,
+}
+
+impl Candle {
+    pub fn is_bullish(&self) -> bool {
+        self.close > self.open
+    }
+}
+
+/// OHLC converted to `f64` once when bars arrive, so tessellating thousands of bars never
+/// runs `Decimal` division. `Decimal` stays the source of truth for storage and labels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PlotCandle {
+    pub open_time: u64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub bullish: bool,
+}
+
+impl From<&Candle> for PlotCandle {
+    fn from(candle: &Candle) -> Self {
+        Self {
+            open_time: candle.open_time,
+            open: candle.open.to_f64().unwrap_or_default(),
+            high: candle.high.to_f64().unwrap_or_default(),
+            low: candle.low.to_f64().unwrap_or_default(),
+            close: candle.close.to_f64().unwrap_or_default(),
+            bullish: candle.is_bullish(),
+        }
+    }
+}
+
+/// Intervals offered by the chart, shortest first.
+pub const TIMEFRAMES: [&str; 5] = ["1m", "5m", "1h", "4h", "1d"];
+
+/// Length of one bar, `None` for intervals the app does not offer.
+pub fn timeframe_millis(timeframe: &str) -> Option<u64> {
+    let minute = 60_000;
+
+    match timeframe {
+        "1m" => Some(minute),
+        "5m" => Some(5 * minute),
+        "15m" => Some(15 * minute),
+        "1h" => Some(60 * minute),
+        "4h" => Some(240 * minute),
+        "1d" => Some(1440 * minute),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MergeError {
+    /// The bars span a different interval than the series they were fetched for.
+    TimeframeMismatch,
+}
+
+/// Missing bars starting at `from`, `count` bars long.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CandleGap {
+    pub from: u64,
+    pub count: u32,
+}
+
+/// Every run of missing bars in `series`, oldest first. None for unknown timeframes.
+pub fn candle_gaps<'a>(
+    series: &'a VecDeque<Candle>,
+    timeframe: &str,
+) -> impl Iterator<Item = CandleGap> + 'a {
+    let interval = timeframe_millis(timeframe);
+
+    series
+        .iter()
+        .zip(series.iter().skip(1))
+        .filter_map(move |(a, b)| {
+            let interval = interval?;
+            (b.open_time - a.open_time > interval).then(|| CandleGap {
+                from: a.open_time + interval,
+                count: ((b.open_time - a.open_time) / interval - 1) as u32,
+            })
+        })
+}
+
+/// Reconciles `new` into `series` by `open_time`: revised bars replace the old ones,
+/// missing bars are inserted in order and the oldest fall off past `max_len`.
+/// Reports the first gap still left in the series so it can be fetched.
+pub fn merge_candles(
+    series: &mut VecDeque<Candle>,
+    new: &[Candle],
+    timeframe: &str,
+    max_len: usize,
+) -> Result<Option<CandleGap>, MergeError> {
+    let interval = timeframe_millis(timeframe);
+
+    if let Some(interval) = interval
+        && new
+            .iter()
+            .any(|c| c.close_time.saturating_sub(c.open_time) + 1 != interval)
+    {
+        return Err(MergeError::TimeframeMismatch);
+    }
+
+    for candle in new {
+        match series.binary_search_by_key(&candle.open_time, |c| c.open_time) {
+            Ok(index) => series[index] = candle.clone(),
+            Err(index) => series.insert(index, candle.clone()),
+        }
+    }
+
+    while series.len() > max_len {
+        series.pop_front();
+    }
+
+    Ok(candle_gaps(series, timeframe).next())
+}
+
+pub async fn get_candles(
+    symbol: &str,
+    timeframe: &str,
+    decimals: u32,
+) -> Result<Vec<Candle>, String> {
+    get_candles_with_limit(
+        symbol,
+        timeframe,
+        decimals,
+        config::get().history.candles as u32,
+    )
+    .await
+}
+
+pub async fn get_candles_with_limit(
+    symbol: &str,
+    timeframe: &str,
+    decimals: u32,
+    limit: u32,
+) -> Result<Vec<Candle>, String> {
+    let url = format!(
+        "{}/api/v3/klines?symbol={symbol}&limit={limit}&interval={timeframe}",
+        config::get().api.rest()
+    );
+
+    fetch_klines(&url, decimals).await
+}
+
+/// Candles opening at or after `start_time`, used when a range has to be covered exactly.
+pub async fn get_candles_since(
+    symbol: &str,
+    timeframe: &str,
+    decimals: u32,
+    start_time: u64,
+    limit: u32,
+) -> Result<Vec<Candle>, String> {
+    let url = format!(
+        "{}/api/v3/klines?symbol={symbol}&limit={limit}&interval={timeframe}&startTime={start_time}",
+        config::get().api.rest()
+    );
+
+    fetch_klines(&url, decimals).await
+}
+
+async fn fetch_klines(url: &str, decimals: u32) -> Result<Vec<Candle>, String> {
+    match reqwest::get(url).await {
+        Ok(response) => {
+            match response.json::<Vec<Vec<Value>>>().await {
+                Ok(raw_klines) => {
+                    let candles = raw_klines
+                        .into_iter()
+                        .filter_map(|entry| {
+                            if entry.len() < 12 {
+                                return None;
+                            }
+                            
+                            let open_str = entry[1].as_str()?;
+                            let high_str = entry[2].as_str()?;
+                            let low_str = entry[3].as_str()?;
+                            let close_str = entry[4].as_str()?;
+
+                            let mut open = open_str.parse::<Decimal>().ok()?;
+                            let mut high = high_str.parse::<Decimal>().ok()?;
+                            let mut low = low_str.parse::<Decimal>().ok()?;
+                            let mut close = close_str.parse::<Decimal>().ok()?;
+                            let volume = entry[5].as_str()?.parse::<Decimal>().ok()?;
+
+                            open.rescale(decimals);
+                            high.rescale(decimals);
+                            low.rescale(decimals);
+                            close.rescale(decimals);
+
+                            Some(Candle {
+                                open_time: entry[0].as_u64()?,
+                                close_time: entry[6].as_u64()?,
+                                open,
+                                high,
+                                low,
+                                close,
+                                volume,
+                            })
+                        })
+                        .collect::<Vec<Candle>>();
+
+                    Ok(candles)
+                }
+                Err(err) => Err(format!("Failed to parse candle JSON: {}", err)),
+            }
+        }
+        Err(err) => Err(format!("Failed to fetch candles: {}", err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::dec;
+
+    const MINUTE: u64 = 60_000;
+
+    fn candle(index: u64, close: Decimal) -> Candle {
+        Candle {
+            open: dec!(100),
+            high: close.max(dec!(100)),
+            low: close.min(dec!(100)),
+            close,
+            volume: dec!(1),
+            open_time: index * MINUTE,
+            close_time: (index + 1) * MINUTE - 1,
+        }
+    }
+
+    fn series(range: std::ops::Range<u64>) -> VecDeque<Candle> {
+        range.map(|i| candle(i, dec!(100))).collect()
+    }
+
+    fn open_times(series: &VecDeque<Candle>) -> Vec<u64> {
+        series.iter().map(|c| c.open_time / MINUTE).collect()
+    }
+
+    #[test]
+    fn replaces_the_open_bar_in_place() {
+        let mut old = series(0..5);
+
+        let gap = merge_candles(&mut old, &[candle(4, dec!(105))], "1m", 5).unwrap();
+
+        assert_eq!(gap, None);
+        assert_eq!(open_times(&old), vec![0, 1, 2, 3, 4]);
+        assert_eq!(old.back().unwrap().close, dec!(105));
+    }
+
+    #[test]
+    fn appends_every_bar_that_closed_between_fetches() {
+        let mut old = series(0..5);
+        let new = (3..8).map(|i| candle(i, dec!(101))).collect::<Vec<_>>();
+
+        let gap = merge_candles(&mut old, &new, "1m", 5).unwrap();
+
+        assert_eq!(gap, None);
+        assert_eq!(open_times(&old), vec![3, 4, 5, 6, 7]);
+        assert!(old.iter().all(|c| c.close == dec!(101)));
+    }
+
+    #[test]
+    fn replaces_revised_bars_in_the_middle() {
+        let mut old = series(0..5);
+
+        merge_candles(&mut old, &[candle(2, dec!(99))], "1m", 5).unwrap();
+
+        assert_eq!(open_times(&old), vec![0, 1, 2, 3, 4]);
+        assert_eq!(old[2].close, dec!(99));
+    }
+
+    #[test]
+    fn reports_a_gap_and_fills_it_later() {
+        let mut old = series(0..3);
+        let new = (6..8).map(|i| candle(i, dec!(100))).collect::<Vec<_>>();
+
+        let gap = merge_candles(&mut old, &new, "1m", 10).unwrap();
+
+        assert_eq!(
+            gap,
+            Some(CandleGap {
+                from: 3 * MINUTE,
+                count: 3
+            })
+        );
+
+        let missing = (3..6).map(|i| candle(i, dec!(100))).collect::<Vec<_>>();
+        let gap = merge_candles(&mut old, &missing, "1m", 10).unwrap();
+
+        assert_eq!(gap, None);
+        assert_eq!(open_times(&old), (0..8).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn a_jump_past_the_window_leaves_no_gap() {
+        let mut old = series(0..5);
+        let new = (100..105).map(|i| candle(i, dec!(100))).collect::<Vec<_>>();
+
+        let gap = merge_candles(&mut old, &new, "1m", 5).unwrap();
+
+        assert_eq!(gap, None);
+        assert_eq!(open_times(&old), vec![100, 101, 102, 103, 104]);
+    }
+
+    #[test]
+    fn rejects_bars_of_another_timeframe() {
+        let mut old = series(0..5);
+        let hourly = Candle {
+            open_time: 0,
+            close_time: 60 * MINUTE - 1,
+            ..candle(0, dec!(100))
+        };
+
+        let result = merge_candles(&mut old, &[hourly], "1m", 5);
+
+        assert_eq!(result, Err(MergeError::TimeframeMismatch));
+        assert_eq!(open_times(&old), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn empty_series_takes_the_new_bars() {
+        let mut old = VecDeque::new();
+        let new = (0..3).map(|i| candle(i, dec!(100))).collect::<Vec<_>>();
+
+        merge_candles(&mut old, &new, "1m", 5).unwrap();
+
+        assert_eq!(open_times(&old), vec![0, 1, 2]);
+    }
+}
@@ -1,2 +1,33 @@
-pub const WHITE_COLOR: [f32; 3] = [0.976, 0.980, 0.984];
-pub const GRAY_COLOR: [f32; 3] = [0.2, 0.2, 0.2];
\ No newline at end of file
+use iced::Color;
+use crate::candle::PlotCandle;
+use crate::theme::{Palette, Rgb};
+use crate::trades::AggTrade;
+use crate::watchlist::tick::{PriceTick, TickDirection};
+
+pub fn color(rgb: Rgb) -> Color {
+    Color::from_rgb8(rgb.0, rgb.1, rgb.2)
+}
+
+pub fn candle_color(palette: &Palette, candle: &PlotCandle) -> Color {
+    if candle.bullish {
+        color(palette.bull)
+    } else {
+        color(palette.bear)
+    }
+}
+
+/// A buyer resting on the book means the taker sold.
+pub fn trade_color(palette: &Palette, trade: &AggTrade) -> Color {
+    if trade.buyer_maker {
+        color(palette.bear)
+    } else {
+        color(palette.bull)
+    }
+}
+
+pub fn tick_color(palette: &Palette, tick: &PriceTick) -> Color {
+    match tick.direction {
+        TickDirection::Up => color(palette.bull),
+        TickDirection::Down => color(palette.bear),
+    }
+}
@@ -0,0 +1,802 @@
+use crate::backtest::parse_strategy;
+use crate::candle::TIMEFRAMES;
+use crate::dataset::Timestamps;
+use crate::keys::{Action, Chord, Keymap};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use toml::{Table, Value};
+
+/// Read from the working directory when no other file is given.
+pub const CONFIG_FILE: &str = "juicy-lemon.toml";
+
+/// Points at a config file other than `juicy-lemon.toml`.
+pub const CONFIG_ENV: &str = "JUICY_CONFIG";
+
+/// `JUICY_<SECTION>_<KEY>` overrides one setting, e.g. `JUICY_API_TESTNET=true`.
+pub const ENV_PREFIX: &str = "JUICY_";
+
+pub const SECTIONS: [&str; 11] = [
+    "api",
+    "refresh",
+    "history",
+    "defaults",
+    "cache",
+    "ui",
+    "keys",
+    "export",
+    "paper",
+    "portfolio",
+    "backtest",
+];
+
+pub const TESTNET_REST_URL: &str = "https://testnet.binance.vision";
+pub const TESTNET_STREAM_URL: &str = "wss://stream.testnet.binance.vision:9443";
+
+/// Binance rejects kline requests above this limit.
+pub const MAX_HISTORY_CANDLES: usize = 1000;
+
+/// Largest exported image side in pixels.
+pub const MAX_EXPORT_SIDE: u32 = 8192;
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Checks an exported image width or height, `name` is how the caller calls it.
+pub fn check_export_side(name: &str, side: u32) -> Result<(), String> {
+    if !(1..=MAX_EXPORT_SIDE).contains(&side) {
+        return Err(format!(
+            "{} must be between 1 and {}, got {}",
+            name, MAX_EXPORT_SIDE, side
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks an exported PNG scale, `name` is how the caller calls it.
+pub fn check_export_scale(name: &str, scale: f32) -> Result<(), String> {
+    if !(0.25..=4.0).contains(&scale) {
+        return Err(format!(
+            "{} must be between 0.25 and 4, got {}",
+            name, scale
+        ));
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Api {
+    pub rest_url: String,
+    /// Ticker prices come from the website host, which answers batched symbol lists.
+    pub price_url: String,
+    pub stream_url: String,
+    /// Sends every request and stream to the spot testnet instead of the URLs above.
+    pub testnet: bool,
+}
+
+impl Default for Api {
+    fn default() -> Self {
+        Self {
+            rest_url: "https://api.binance.com".to_string(),
+            price_url: "https://www.binance.com".to_string(),
+            stream_url: "wss://stream.binance.com:9443".to_string(),
+            testnet: false,
+        }
+    }
+}
+
+impl Api {
+    pub fn rest(&self) -> &str {
+        if self.testnet {
+            TESTNET_REST_URL
+        } else {
+            self.rest_url.trim_end_matches('/')
+        }
+    }
+
+    pub fn price(&self) -> &str {
+        if self.testnet {
+            TESTNET_REST_URL
+        } else {
+            self.price_url.trim_end_matches('/')
+        }
+    }
+
+    pub fn stream(&self) -> &str {
+        if self.testnet {
+            TESTNET_STREAM_URL
+        } else {
+            self.stream_url.trim_end_matches('/')
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Refresh {
+    pub prices_secs: u64,
+    pub sparklines_secs: u64,
+    pub volume_profile_secs: u64,
+    /// The terminal UI polls prices and the open candle together.
+    pub terminal_secs: u64,
+}
+
+impl Default for Refresh {
+    fn default() -> Self {
+        Self {
+            prices_secs: 1,
+            sparklines_secs: 300,
+            volume_profile_secs: 60,
+            terminal_secs: 2,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct History {
+    /// Candles fetched when a chart opens and kept on screen afterwards.
+    pub candles: usize,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self { candles: 100 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Defaults {
+    pub watchlist: String,
+    pub timeframe: String,
+    /// Filled into the default watchlist once instruments are known.
+    pub symbols: Vec<String>,
+}
+
+impl Default for Defaults {
+    fn default() -> Self {
+        Self {
+            watchlist: "Default".to_string(),
+            timeframe: "1m".to_string(),
+            symbols: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Cache {
+    pub symbols_file: PathBuf,
+    pub candles_dir: PathBuf,
+    pub memory_kb: usize,
+    pub disk_mb: u64,
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Self {
+            symbols_file: PathBuf::from("symbols.json"),
+            candles_dir: PathBuf::from("candles"),
+            memory_kb: 1024,
+            disk_mb: 50,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Ui {
+    /// A built-in palette (dark, light, high-contrast) or a file name in `palettes_dir`.
+    pub theme: String,
+    pub palettes_dir: PathBuf,
+    pub show_tick_arrows: bool,
+    pub show_depth_chart: bool,
+    pub show_volume_profile: bool,
+    pub show_paper_trading: bool,
+    pub show_portfolio: bool,
+    pub show_backtest: bool,
+    pub large_trade_notional: Decimal,
+}
+
+impl Default for Ui {
+    fn default() -> Self {
+        Self {
+            theme: "dark".to_string(),
+            palettes_dir: PathBuf::from("themes"),
+            show_tick_arrows: true,
+            show_depth_chart: false,
+            show_volume_profile: false,
+            show_paper_trading: false,
+            show_portfolio: false,
+            show_backtest: false,
+            large_trade_notional: dec!(50000),
+        }
+    }
+}
+
+/// Shortcuts of the iced app, see `Chord` for how they are written.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Keys {
+    pub timeframe_1m: Chord,
+    pub timeframe_5m: Chord,
+    pub timeframe_1h: Chord,
+    pub timeframe_4h: Chord,
+    pub timeframe_1d: Chord,
+    pub previous_symbol: Chord,
+    pub next_symbol: Chord,
+    pub remove_symbol: Chord,
+    pub focus_search: Chord,
+    pub zoom_in: Chord,
+    pub zoom_out: Chord,
+    pub command_palette: Chord,
+}
+
+impl Default for Keys {
+    fn default() -> Self {
+        Self {
+            timeframe_1m: Chord::key("1"),
+            timeframe_5m: Chord::key("2"),
+            timeframe_1h: Chord::key("3"),
+            timeframe_4h: Chord::key("4"),
+            timeframe_1d: Chord::key("5"),
+            previous_symbol: Chord::key("up"),
+            next_symbol: Chord::key("down"),
+            remove_symbol: Chord::key("delete"),
+            focus_search: Chord::key("/"),
+            zoom_in: Chord::key("+"),
+            zoom_out: Chord::key("-"),
+            command_palette: Chord {
+                ctrl: true,
+                ..Chord::key("k")
+            },
+        }
+    }
+}
+
+impl Keys {
+    /// Every action with its setting name, unbound ones included.
+    pub fn bindings(&self) -> [(&'static str, &Chord, Action); 12] {
+        [
+            ("timeframe_1m", &self.timeframe_1m, Action::Timeframe("1m")),
+            ("timeframe_5m", &self.timeframe_5m, Action::Timeframe("5m")),
+            ("timeframe_1h", &self.timeframe_1h, Action::Timeframe("1h")),
+            ("timeframe_4h", &self.timeframe_4h, Action::Timeframe("4h")),
+            ("timeframe_1d", &self.timeframe_1d, Action::Timeframe("1d")),
+            (
+                "previous_symbol",
+                &self.previous_symbol,
+                Action::PreviousSymbol,
+            ),
+            ("next_symbol", &self.next_symbol, Action::NextSymbol),
+            ("remove_symbol", &self.remove_symbol, Action::RemoveSymbol),
+            ("focus_search", &self.focus_search, Action::FocusSearch),
+            ("zoom_in", &self.zoom_in, Action::ZoomIn),
+            ("zoom_out", &self.zoom_out, Action::ZoomOut),
+            (
+                "command_palette",
+                &self.command_palette,
+                Action::CommandPalette,
+            ),
+        ]
+    }
+
+    pub fn keymap(&self) -> Keymap {
+        Keymap::new(
+            self.bindings()
+                .into_iter()
+                .map(|(_, chord, action)| (chord.clone(), action)),
+        )
+    }
+}
+
+/// Chart images and candle data written by the chart's export buttons and the
+/// `chart` command.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Export {
+    /// Where the window saves charts, created on the first export.
+    pub dir: PathBuf,
+    pub width: u32,
+    pub height: u32,
+    /// PNG pixels per point, text and lines grow with it.
+    pub scale: f32,
+    /// Candle open and close times in CSV, JSON lines and Parquet exports.
+    pub timestamps: Timestamps,
+}
+
+impl Default for Export {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("charts"),
+            width: 1600,
+            height: 900,
+            scale: 1.0,
+            timestamps: Timestamps::Utc,
+        }
+    }
+}
+
+/// The simulated account of the paper trading panel, reset on every start.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Paper {
+    /// Starting balance per asset.
+    pub balances: BTreeMap<String, Decimal>,
+    /// Fraction of each fill's value, 0.001 is 0.1%.
+    pub fee_rate: Decimal,
+    /// Fraction market and stop orders fill worse than the price that triggered them.
+    pub slippage: Decimal,
+}
+
+impl Default for Paper {
+    fn default() -> Self {
+        Self {
+            balances: BTreeMap::from([("USDT".to_string(), dec!(10000))]),
+            fee_rate: dec!(0.001),
+            slippage: dec!(0.0005),
+        }
+    }
+}
+
+/// Holdings of the portfolio panel and how they are valued.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Portfolio {
+    /// Holdings are saved here on every change.
+    pub file: PathBuf,
+    /// The asset costs, values and P&L are counted in.
+    pub valuation: String,
+    /// Total values kept for the value chart, one per price refresh.
+    pub history_points: usize,
+}
+
+impl Default for Portfolio {
+    fn default() -> Self {
+        Self {
+            file: PathBuf::from("portfolio.toml"),
+            valuation: "USDT".to_string(),
+            history_points: 1440,
+        }
+    }
+}
+
+/// Defaults of the backtest panel and the `backtest` command.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Backtest {
+    /// `sma:FAST,SLOW` or `rsi:PERIOD,OVERSOLD,OVERBOUGHT`.
+    pub strategy: String,
+    /// Quote cash the simulated account starts with.
+    pub initial_cash: Decimal,
+    /// Fraction of each fill's value, 0.001 is 0.1%.
+    pub fee_rate: Decimal,
+    /// Fraction fills are worse than the next candle's open.
+    pub slippage: Decimal,
+}
+
+impl Default for Backtest {
+    fn default() -> Self {
+        Self {
+            strategy: "sma:10,30".to_string(),
+            initial_cash: dec!(10000),
+            fee_rate: dec!(0.001),
+            slippage: dec!(0.0005),
+        }
+    }
+}
+
+/// Everything that used to be hard-coded. Every section and key is optional, missing
+/// ones keep their defaults.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub api: Api,
+    pub refresh: Refresh,
+    pub history: History,
+    pub defaults: Defaults,
+    pub cache: Cache,
+    pub ui: Ui,
+    pub keys: Keys,
+    pub export: Export,
+    pub paper: Paper,
+    pub portfolio: Portfolio,
+    pub backtest: Backtest,
+}
+
+/// Makes `config` the one fetchers and binaries read, only the first call counts.
+pub fn init(config: Config) {
+    let _ = CONFIG.set(config);
+}
+
+/// The config loaded at startup, or the defaults when nothing was loaded.
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
+
+/// TOML value as typed, or a plain string. Bare text with commas is a list of strings,
+/// so `JUICY_DEFAULTS_SYMBOLS=BTCUSDT,ETHUSDT` works without brackets and quotes.
+fn parse_value(value: &str) -> Value {
+    if let Ok(mut table) = format!("value = {}", value).parse::<Table>()
+        && let Some(value) = table.remove("value")
+    {
+        return value;
+    }
+
+    if value.contains(',') {
+        return Value::Array(
+            value
+                .split(',')
+                .map(|item| Value::String(item.trim().to_string()))
+                .collect(),
+        );
+    }
+
+    Value::String(value.to_string())
+}
+
+/// `section.key=value` pairs from `JUICY_<SECTION>_<KEY>` variables. Other `JUICY_`
+/// variables, like `JUICY_CONFIG` and `JUICY_DEPTH_REPLAY`, are left alone.
+pub fn env_overrides(vars: impl IntoIterator<Item = (String, String)>) -> Vec<(String, String)> {
+    vars.into_iter()
+        .filter_map(|(name, value)| {
+            let rest = name.strip_prefix(ENV_PREFIX)?.to_lowercase();
+
+            SECTIONS.iter().find_map(|section| {
+                let key = rest.strip_prefix(section)?.strip_prefix('_')?;
+                Some((format!("{}.{}={}", section, key, value), name.clone()))
+            })
+        })
+        .collect()
+}
+
+impl Config {
+    pub fn parse(content: &str) -> Result<Self, String> {
+        toml::from_str(content).map_err(|err| err.to_string())
+    }
+
+    /// Applies a `section.key=value` override, `source` names it in errors.
+    pub fn set(&mut self, setting: &str, source: &str) -> Result<(), String> {
+        let (path, value) = setting
+            .split_once('=')
+            .ok_or_else(|| format!("{}: expected section.key=value", source))?;
+        let (section, key) = path
+            .trim()
+            .split_once('.')
+            .ok_or_else(|| format!("{}: expected section.key=value", source))?;
+
+        let mut table = Table::try_from(&*self).map_err(|err| format!("{}: {}", source, err))?;
+        let section_table = table
+            .get_mut(section)
+            .and_then(Value::as_table_mut)
+            .ok_or_else(|| {
+                format!(
+                    "{}: unknown section {}, expected one of {}",
+                    source,
+                    section,
+                    SECTIONS.join(", ")
+                )
+            })?;
+
+        section_table.insert(key.to_string(), parse_value(value.trim()));
+
+        *self = table
+            .try_into()
+            .map_err(|err: toml::de::Error| format!("{}: {}", source, err.message()))?;
+
+        Ok(())
+    }
+
+    /// Reads `path`, or `JUICY_CONFIG`, or `juicy-lemon.toml` when it exists, then applies
+    /// env and command line overrides in that order and validates the result.
+    pub fn load(path: Option<&Path>, overrides: &[String]) -> Result<Self, String> {
+        let env_path = std::env::var(CONFIG_ENV)
+            .ok()
+            .filter(|p| !p.trim().is_empty())
+            .map(PathBuf::from);
+        let explicit = path.map(Path::to_path_buf).or(env_path);
+
+        let mut config = match &explicit {
+            Some(path) => {
+                let content = fs::read_to_string(path)
+                    .map_err(|err| format!("Cannot read {}: {}", path.display(), err))?;
+                Self::parse(&content).map_err(|err| format!("{}: {}", path.display(), err))?
+            }
+            None => match fs::read_to_string(CONFIG_FILE) {
+                Ok(content) => {
+                    Self::parse(&content).map_err(|err| format!("{}: {}", CONFIG_FILE, err))?
+                }
+                Err(_) => Self::default(),
+            },
+        };
+
+        for (setting, name) in env_overrides(std::env::vars()) {
+            config.set(&setting, &name)?;
+        }
+
+        for setting in overrides {
+            config.set(setting, &format!("--set {}", setting))?;
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Takes `--config FILE` and `--set section.key=value` out of command line `args`
+    /// and loads the config with them, the remaining args are left for the binary.
+    pub fn from_args(args: &mut Vec<String>) -> Result<Self, String> {
+        let mut path = None;
+        let mut overrides = Vec::new();
+        let mut rest = Vec::new();
+        let mut iter = args.drain(..);
+
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--config" => path = Some(iter.next().ok_or("Missing value for --config")?),
+                "--set" => overrides.push(iter.next().ok_or("Missing value for --set")?),
+                _ => rest.push(arg),
+            }
+        }
+
+        drop(iter);
+        *args = rest;
+
+        Self::load(path.as_deref().map(Path::new), &overrides)
+    }
+
+    /// Every problem at once, one per line, so a broken file is fixed in one go.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut problems = Vec::new();
+
+        for (key, url, schemes) in [
+            ("api.rest_url", &self.api.rest_url, ["http://", "https://"]),
+            (
+                "api.price_url",
+                &self.api.price_url,
+                ["http://", "https://"],
+            ),
+            ("api.stream_url", &self.api.stream_url, ["ws://", "wss://"]),
+        ] {
+            if !schemes.iter().any(|scheme| url.starts_with(scheme)) {
+                problems.push(format!(
+                    "{} must start with {} or {}, got {:?}",
+                    key, schemes[0], schemes[1], url
+                ));
+            }
+        }
+
+        for (key, secs) in [
+            ("refresh.prices_secs", self.refresh.prices_secs),
+            ("refresh.sparklines_secs", self.refresh.sparklines_secs),
+            (
+                "refresh.volume_profile_secs",
+                self.refresh.volume_profile_secs,
+            ),
+            ("refresh.terminal_secs", self.refresh.terminal_secs),
+        ] {
+            if secs == 0 {
+                problems.push(format!("{} must be at least 1", key));
+            }
+        }
+
+        if !(1..=MAX_HISTORY_CANDLES).contains(&self.history.candles) {
+            problems.push(format!(
+                "history.candles must be between 1 and {}, got {}",
+                MAX_HISTORY_CANDLES, self.history.candles
+            ));
+        }
+
+        if self.defaults.watchlist.trim().is_empty() {
+            problems.push("defaults.watchlist must not be empty".to_string());
+        }
+
+        if !TIMEFRAMES.contains(&self.defaults.timeframe.as_str()) {
+            problems.push(format!(
+                "defaults.timeframe must be one of {}, got {:?}",
+                TIMEFRAMES.join(", "),
+                self.defaults.timeframe
+            ));
+        }
+
+        for symbol in &self.defaults.symbols {
+            if symbol.is_empty() || !symbol.chars().all(|c| c.is_ascii_alphanumeric()) {
+                problems.push(format!(
+                    "defaults.symbols has {:?}, symbols are letters and digits like BTCUSDT",
+                    symbol
+                ));
+            }
+        }
+
+        if self.ui.theme.trim().is_empty() {
+            problems.push("ui.theme must not be empty".to_string());
+        }
+
+        if self.ui.large_trade_notional.is_sign_negative() {
+            problems.push("ui.large_trade_notional must not be negative".to_string());
+        }
+
+        let bindings = self.keys.bindings();
+        for (i, (key, chord, _)) in bindings.iter().enumerate() {
+            if let Some((other, _, _)) = bindings[..i]
+                .iter()
+                .find(|(_, earlier, _)| chord.is_bound() && earlier == chord)
+            {
+                problems.push(format!(
+                    "keys.{} and keys.{} are both bound to {}",
+                    other, key, chord
+                ));
+            }
+        }
+
+        problems.extend(
+            [
+                check_export_side("export.width", self.export.width),
+                check_export_side("export.height", self.export.height),
+                check_export_scale("export.scale", self.export.scale),
+            ]
+            .into_iter()
+            .filter_map(Result::err),
+        );
+
+        for (asset, balance) in &self.paper.balances {
+            if balance.is_sign_negative() {
+                problems.push(format!("paper.balances.{} must not be negative", asset));
+            }
+        }
+
+        for (key, fraction) in [
+            ("paper.fee_rate", self.paper.fee_rate),
+            ("paper.slippage", self.paper.slippage),
+            ("backtest.fee_rate", self.backtest.fee_rate),
+            ("backtest.slippage", self.backtest.slippage),
+        ] {
+            if fraction.is_sign_negative() || fraction >= Decimal::ONE {
+                problems.push(format!(
+                    "{} must be a fraction from 0 to below 1, got {}",
+                    key, fraction
+                ));
+            }
+        }
+
+        if self.portfolio.valuation.trim().is_empty() {
+            problems.push("portfolio.valuation must not be empty".to_string());
+        }
+
+        if self.portfolio.history_points < 2 {
+            problems.push(format!(
+                "portfolio.history_points must be at least 2, got {}",
+                self.portfolio.history_points
+            ));
+        }
+
+        if let Err(err) = parse_strategy(&self.backtest.strategy) {
+            problems.push(format!("backtest.strategy: {}", err));
+        }
+
+        if self.backtest.initial_cash <= Decimal::ZERO {
+            problems.push(format!(
+                "backtest.initial_cash must be above 0, got {}",
+                self.backtest.initial_cash
+            ));
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(format!(
+                "Invalid configuration:\n  {}",
+                problems.join("\n  ")
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn example_file_matches_defaults() {
+        let example = include_str!("../juicy-lemon.example.toml");
+
+        assert_eq!(Config::parse(example).unwrap(), Config::default());
+    }
+
+    #[test]
+    fn overrides_are_typed_from_their_text() {
+        let env = env_overrides([
+            ("JUICY_API_TESTNET".to_string(), "true".to_string()),
+            (
+                "JUICY_DEFAULTS_SYMBOLS".to_string(),
+                "BTCUSDT,ETHUSDT".to_string(),
+            ),
+            ("JUICY_DEPTH_REPLAY".to_string(), "replay".to_string()),
+        ]);
+        assert_eq!(env.len(), 2);
+
+        let mut config = Config::default();
+        for (setting, name) in &env {
+            config.set(setting, name).unwrap();
+        }
+        config.set("defaults.timeframe=5m", "--set").unwrap();
+        config.set("ui.large_trade_notional=1000", "--set").unwrap();
+        config.set("keys.zoom_in==", "--set").unwrap();
+        config.set("keys.timeframe_1h=6", "--set").unwrap();
+        config.set("export.timestamps=local", "--set").unwrap();
+
+        assert_eq!(config.api.rest(), TESTNET_REST_URL);
+        assert_eq!(config.defaults.symbols, vec!["BTCUSDT", "ETHUSDT"]);
+        assert_eq!(config.defaults.timeframe, "5m");
+        assert_eq!(config.ui.large_trade_notional, dec!(1000));
+        assert_eq!(config.keys.zoom_in, Chord::key("="));
+        assert_eq!(config.keys.timeframe_1h, Chord::key("6"));
+        assert_eq!(config.export.timestamps, Timestamps::Local);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn errors_name_the_setting_and_its_source() {
+        let mut config = Config::default();
+
+        let err = config
+            .set("refresh.price_secs=5", "JUICY_REFRESH_PRICE_SECS")
+            .unwrap_err();
+        assert!(err.starts_with("JUICY_REFRESH_PRICE_SECS: unknown field `price_secs`"));
+
+        let err = config.set("refresh.prices_secs=fast", "--set").unwrap_err();
+        assert!(err.starts_with("--set: invalid type"));
+
+        let err = Config::parse("[history]\ncandles = 5000\n[defaults]\ntimeframe = \"2m\"")
+            .unwrap()
+            .validate()
+            .unwrap_err();
+        assert!(err.contains("history.candles must be between 1 and 1000, got 5000"));
+        assert!(err.contains("defaults.timeframe must be one of 1m, 5m, 1h, 4h, 1d"));
+
+        config.set("export.scale=0", "--set").unwrap();
+        let err = config.validate().unwrap_err();
+        assert!(
+            err.contains("export.scale must be between 0.25 and 4, got 0"),
+            "{}",
+            err
+        );
+        config.set("export.scale=2", "--set").unwrap();
+
+        config.set("paper.fee_rate=1.5", "--set").unwrap();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("paper.fee_rate must be a fraction from 0 to below 1, got 1.5"));
+        config.set("paper.fee_rate=0", "--set").unwrap();
+
+        config.set("portfolio.history_points=1", "--set").unwrap();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("portfolio.history_points must be at least 2, got 1"));
+        config.set("portfolio.history_points=2", "--set").unwrap();
+
+        config
+            .set("backtest.strategy=\"sma:30,10\"", "--set")
+            .unwrap();
+        let err = config.validate().unwrap_err();
+        assert!(
+            err.contains("backtest.strategy: Invalid strategy \"sma:30,10\""),
+            "{}",
+            err
+        );
+        config
+            .set("backtest.strategy=\"rsi:14,30,70\"", "--set")
+            .unwrap();
+
+        let err = config.set("keys.zoom_in=shift+=", "--set").unwrap_err();
+        assert!(err.contains("write the shifted character"), "{}", err);
+
+        config.set("keys.zoom_out=ctrl+k", "--set").unwrap();
+        let err = config.validate().unwrap_err();
+        assert!(err.contains("keys.zoom_out and keys.command_palette are both bound to ctrl+k"));
+    }
+}
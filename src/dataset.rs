@@ -0,0 +1,726 @@
+use crate::candle::Candle;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, SecondsFormat, TimeZone, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::fmt::Write as _;
+use std::path::Path;
+
+const PRICE_COLUMNS: [&str; 5] = ["open", "high", "low", "close", "volume"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataFormat {
+    Csv,
+    JsonLines,
+    Parquet,
+}
+
+impl DataFormat {
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_lowercase());
+
+        match extension.as_deref() {
+            Some("csv") => Ok(DataFormat::Csv),
+            Some("jsonl" | "ndjson") => Ok(DataFormat::JsonLines),
+            Some("parquet") => Ok(DataFormat::Parquet),
+            _ => Err(format!(
+                "Cannot export {}, expected a .csv, .jsonl or .parquet file",
+                path.display()
+            )),
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            DataFormat::Csv => "csv",
+            DataFormat::JsonLines => "jsonl",
+            DataFormat::Parquet => "parquet",
+        }
+    }
+}
+
+/// How open and close times are written.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Timestamps {
+    /// Epoch milliseconds, as Binance sends them.
+    Millis,
+    /// RFC 3339 in UTC, a UTC timestamp column in Parquet.
+    #[default]
+    Utc,
+    /// RFC 3339 with the local offset, a local wall-clock timestamp column in Parquet.
+    Local,
+}
+
+impl Timestamps {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        match text {
+            "millis" => Ok(Timestamps::Millis),
+            "utc" => Ok(Timestamps::Utc),
+            "local" => Ok(Timestamps::Local),
+            _ => Err(format!(
+                "Unsupported timestamps {}, expected millis, utc or local",
+                text
+            )),
+        }
+    }
+
+    fn text(&self, millis: u64) -> String {
+        let millis = millis as i64;
+
+        match self {
+            Timestamps::Millis => millis.to_string(),
+            Timestamps::Utc => Utc
+                .timestamp_millis_opt(millis)
+                .single()
+                .map(|t| t.to_rfc3339_opts(SecondsFormat::Millis, true))
+                .unwrap_or_default(),
+            Timestamps::Local => Local
+                .timestamp_millis_opt(millis)
+                .single()
+                .map(|t| t.to_rfc3339_opts(SecondsFormat::Millis, false))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// A computed series next to the candles, one value per candle and `None` where it has
+/// no value yet, like the first bars of a moving average.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Indicator {
+    pub name: String,
+    pub values: Vec<Option<Decimal>>,
+}
+
+/// Candles as rows for notebooks: times, OHLC, volume, then one column per indicator.
+#[derive(Debug, Clone)]
+pub struct CandleTable<'a> {
+    pub candles: Vec<&'a Candle>,
+    pub indicators: Vec<Indicator>,
+    pub timestamps: Timestamps,
+}
+
+fn prices(candle: &Candle) -> [Decimal; 5] {
+    [
+        candle.open,
+        candle.high,
+        candle.low,
+        candle.close,
+        candle.volume,
+    ]
+}
+
+fn csv_cell(text: &str) -> String {
+    if text.contains([',', '"', '\n']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+impl<'a> CandleTable<'a> {
+    pub fn new(candles: impl IntoIterator<Item = &'a Candle>, timestamps: Timestamps) -> Self {
+        Self {
+            candles: candles.into_iter().collect(),
+            indicators: Vec::new(),
+            timestamps,
+        }
+    }
+
+    pub fn with_indicator(mut self, indicator: Indicator) -> Result<Self, String> {
+        if indicator.values.len() != self.candles.len() {
+            return Err(format!(
+                "{} has {} values for {} candles",
+                indicator.name,
+                indicator.values.len(),
+                self.candles.len()
+            ));
+        }
+
+        self.indicators.push(indicator);
+        Ok(self)
+    }
+
+    fn indicator_values(&self, row: usize) -> impl Iterator<Item = Option<Decimal>> + '_ {
+        self.indicators.iter().map(move |i| i.values[row])
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("open_time,close_time,");
+        csv.push_str(&PRICE_COLUMNS.join(","));
+        for indicator in &self.indicators {
+            csv.push(',');
+            csv.push_str(&csv_cell(&indicator.name));
+        }
+        csv.push('\n');
+
+        for (row, candle) in self.candles.iter().enumerate() {
+            csv.push_str(&self.timestamps.text(candle.open_time));
+            csv.push(',');
+            csv.push_str(&self.timestamps.text(candle.close_time));
+
+            for value in prices(candle) {
+                let _ = write!(csv, ",{}", value);
+            }
+            for value in self.indicator_values(row) {
+                csv.push(',');
+                if let Some(value) = value {
+                    csv.push_str(&value.to_string());
+                }
+            }
+
+            csv.push('\n');
+        }
+
+        csv
+    }
+
+    /// One object per line. Prices are JSON numbers, written from the decimals as is so
+    /// nothing is rounded through a float.
+    pub fn to_json_lines(&self) -> String {
+        let time = |millis: u64| match self.timestamps {
+            Timestamps::Millis => millis.to_string(),
+            _ => format!("\"{}\"", self.timestamps.text(millis)),
+        };
+
+        let mut lines = String::new();
+
+        for (row, candle) in self.candles.iter().enumerate() {
+            let _ = write!(
+                lines,
+                "{{\"open_time\":{},\"close_time\":{}",
+                time(candle.open_time),
+                time(candle.close_time)
+            );
+
+            for (name, value) in PRICE_COLUMNS.iter().zip(prices(candle)) {
+                let _ = write!(lines, ",\"{}\":{}", name, value.normalize());
+            }
+            for (indicator, value) in self.indicators.iter().zip(self.indicator_values(row)) {
+                let value = value.map_or("null".to_string(), |v| v.normalize().to_string());
+                let name = serde_json::to_string(&indicator.name).unwrap_or_default();
+                let _ = write!(lines, ",{}:{}", name, value);
+            }
+
+            lines.push_str("}\n");
+        }
+
+        lines
+    }
+
+    #[cfg(feature = "parquet")]
+    pub fn to_parquet(&self) -> Result<Vec<u8>, String> {
+        parquet_file::write(self).map_err(|err| format!("Cannot write Parquet: {}", err))
+    }
+
+    #[cfg(not(feature = "parquet"))]
+    pub fn to_parquet(&self) -> Result<Vec<u8>, String> {
+        Err("Parquet export needs the parquet feature".to_string())
+    }
+
+    pub fn encode(&self, format: DataFormat) -> Result<Vec<u8>, String> {
+        match format {
+            DataFormat::Csv => Ok(self.to_csv().into_bytes()),
+            DataFormat::JsonLines => Ok(self.to_json_lines().into_bytes()),
+            DataFormat::Parquet => self.to_parquet(),
+        }
+    }
+}
+
+/// Columns a candle file may keep its open times in, the first one present wins.
+const TIME_COLUMNS: [&str; 6] = [
+    "open_time",
+    "epoch_open",
+    "timestamp",
+    "time",
+    "date",
+    "iso8601_open",
+];
+
+/// Epoch seconds or millis, RFC 3339, `YYYY-MM-DD HH:MM:SS` with or without an offset
+/// (UTC without), or a UTC date.
+fn parse_time(text: &str) -> Option<u64> {
+    if let Ok(number) = text.parse::<u64>() {
+        // Seconds stay below this until the year 5138, millis passed it in 1973.
+        return Some(if number < 100_000_000_000 {
+            number * 1000
+        } else {
+            number
+        });
+    }
+
+    let time = DateTime::parse_from_rfc3339(text)
+        .or_else(|_| DateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%:z"))
+        .map(|time| time.timestamp_millis())
+        .ok()
+        .or_else(|| {
+            NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S")
+                .ok()
+                .or_else(|| {
+                    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+                        .ok()?
+                        .and_hms_opt(0, 0, 0)
+                })
+                .map(|time| time.and_utc().timestamp_millis())
+        })?;
+
+    u64::try_from(time).ok()
+}
+
+/// Splits CSV into records the way `csv_cell` quotes fields: a quoted field may hold
+/// commas, doubled quotes and line breaks. Each record comes with the line it starts on.
+fn csv_records(csv: &str) -> Vec<(usize, Vec<String>)> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let (mut line, mut start) = (1, 1);
+    let mut chars = csv.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            '\n' if !quoted => {
+                fields.push(std::mem::take(&mut field));
+                records.push((start, std::mem::take(&mut fields)));
+                line += 1;
+                start = line;
+            }
+            '\n' => {
+                line += 1;
+                field.push(c);
+            }
+            c => field.push(c),
+        }
+    }
+
+    if !field.is_empty() || !fields.is_empty() {
+        fields.push(field);
+        records.push((start, fields));
+    }
+
+    records
+}
+
+/// Reads candles from CSV with a header, like `to_csv` writes them or `data/btcusd.csv`
+/// keeps them: an open time, open, high, low and close in any order, volume and close
+/// time optional. Without close times each candle closes where the next one opens.
+pub fn parse_candles(csv: &str) -> Result<Vec<Candle>, String> {
+    let mut records = csv_records(csv)
+        .into_iter()
+        .filter(|(_, fields)| fields.iter().any(|field| !field.trim().is_empty()));
+    let header = records
+        .next()
+        .ok_or("The candle file is empty")?
+        .1
+        .iter()
+        .map(|column| column.trim().to_lowercase())
+        .collect::<Vec<_>>();
+    let column = |name: &str| header.iter().position(|c| c == name);
+
+    let open_time = TIME_COLUMNS
+        .iter()
+        .find_map(|name| column(name))
+        .ok_or_else(|| {
+            format!(
+                "The candle file needs a time column, one of {}",
+                TIME_COLUMNS.join(", ")
+            )
+        })?;
+    let mut price_columns = Vec::new();
+    for name in ["open", "high", "low", "close"] {
+        price_columns
+            .push(column(name).ok_or_else(|| format!("The candle file has no {} column", name))?);
+    }
+    let volume = column("volume");
+    let close_time = column("close_time");
+
+    let mut candles = records
+        .map(|(line_number, fields)| {
+            let fields = fields.iter().map(|field| field.trim()).collect::<Vec<_>>();
+            let field = |i: usize| {
+                fields
+                    .get(i)
+                    .copied()
+                    .ok_or_else(|| format!("Line {}: too few columns", line_number))
+            };
+            let time = |i: usize| {
+                let text = field(i)?;
+                parse_time(text).ok_or_else(|| {
+                    format!(
+                        "Line {}: {} is not a time, got {:?}",
+                        line_number, header[i], text
+                    )
+                })
+            };
+            let price = |i: usize| {
+                let text = field(i)?;
+                text.parse::<Decimal>().map_err(|_| {
+                    format!(
+                        "Line {}: {} must be a number, got {:?}",
+                        line_number, header[i], text
+                    )
+                })
+            };
+
+            Ok(Candle {
+                open: price(price_columns[0])?,
+                high: price(price_columns[1])?,
+                low: price(price_columns[2])?,
+                close: price(price_columns[3])?,
+                volume: volume.map(price).transpose()?.unwrap_or_default(),
+                open_time: time(open_time)?,
+                close_time: close_time.map(time).transpose()?.unwrap_or_default(),
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    candles.sort_by_key(|candle| candle.open_time);
+
+    if close_time.is_none() {
+        let interval = match candles.as_slice() {
+            [.., previous, last] => last.open_time - previous.open_time,
+            _ => 1,
+        };
+
+        for index in 0..candles.len() {
+            let next_open = candles
+                .get(index + 1)
+                .map(|next| next.open_time)
+                .unwrap_or(candles[index].open_time + interval);
+            candles[index].close_time = next_open.saturating_sub(1);
+        }
+    }
+
+    Ok(candles)
+}
+
+#[cfg(feature = "parquet")]
+mod parquet_file {
+    use super::{CandleTable, PRICE_COLUMNS, Timestamps, prices};
+    use crate::candle::Candle;
+    use chrono::{Local, TimeZone};
+    use parquet::basic::{LogicalType, Repetition, TimeUnit, Type as PhysicalType};
+    use parquet::data_type::{DoubleType, Int64Type};
+    use parquet::errors::Result;
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::format::MilliSeconds;
+    use parquet::schema::types::Type;
+    use rust_decimal::prelude::*;
+    use std::sync::Arc;
+
+    fn column(name: &str, physical: PhysicalType, repetition: Repetition) -> Result<Arc<Type>> {
+        Type::primitive_type_builder(name, physical)
+            .with_repetition(repetition)
+            .build()
+            .map(Arc::new)
+    }
+
+    fn time_column(name: &str, timestamps: Timestamps) -> Result<Arc<Type>> {
+        let logical_type = match timestamps {
+            Timestamps::Millis => None,
+            Timestamps::Utc | Timestamps::Local => Some(LogicalType::Timestamp {
+                is_adjusted_to_u_t_c: timestamps == Timestamps::Utc,
+                unit: TimeUnit::MILLIS(MilliSeconds::new()),
+            }),
+        };
+
+        Type::primitive_type_builder(name, PhysicalType::INT64)
+            .with_repetition(Repetition::REQUIRED)
+            .with_logical_type(logical_type)
+            .build()
+            .map(Arc::new)
+    }
+
+    /// Local timestamps are stored as wall-clock millis, the way Parquet expects times
+    /// that are not adjusted to UTC.
+    fn time_value(millis: u64, timestamps: Timestamps) -> i64 {
+        let millis = millis as i64;
+
+        match timestamps {
+            Timestamps::Local => Local
+                .timestamp_millis_opt(millis)
+                .single()
+                .map(|t| t.naive_local().and_utc().timestamp_millis())
+                .unwrap_or(millis),
+            _ => millis,
+        }
+    }
+
+    pub fn write(table: &CandleTable) -> Result<Vec<u8>> {
+        let mut fields = vec![
+            time_column("open_time", table.timestamps)?,
+            time_column("close_time", table.timestamps)?,
+        ];
+        for name in PRICE_COLUMNS {
+            fields.push(column(name, PhysicalType::DOUBLE, Repetition::REQUIRED)?);
+        }
+        for indicator in &table.indicators {
+            fields.push(column(
+                &indicator.name,
+                PhysicalType::DOUBLE,
+                Repetition::OPTIONAL,
+            )?);
+        }
+
+        let schema = Type::group_type_builder("candles")
+            .with_fields(fields)
+            .build()?;
+
+        let mut file = Vec::new();
+        let mut writer = SerializedFileWriter::new(
+            &mut file,
+            Arc::new(schema),
+            Arc::new(WriterProperties::builder().build()),
+        )?;
+        let mut row_group = writer.next_row_group()?;
+
+        let times: [fn(&Candle) -> u64; 2] = [|c| c.open_time, |c| c.close_time];
+
+        for time in times {
+            let values = table
+                .candles
+                .iter()
+                .map(|candle| time_value(time(candle), table.timestamps))
+                .collect::<Vec<_>>();
+
+            if let Some(mut column) = row_group.next_column()? {
+                column
+                    .typed::<Int64Type>()
+                    .write_batch(&values, None, None)?;
+                column.close()?;
+            }
+        }
+
+        for index in 0..PRICE_COLUMNS.len() {
+            let values = table
+                .candles
+                .iter()
+                .map(|candle| prices(candle)[index].to_f64().unwrap_or(f64::NAN))
+                .collect::<Vec<_>>();
+
+            if let Some(mut column) = row_group.next_column()? {
+                column
+                    .typed::<DoubleType>()
+                    .write_batch(&values, None, None)?;
+                column.close()?;
+            }
+        }
+
+        for indicator in &table.indicators {
+            let values = indicator
+                .values
+                .iter()
+                .flatten()
+                .map(|value| value.to_f64().unwrap_or(f64::NAN))
+                .collect::<Vec<_>>();
+            let defined = indicator
+                .values
+                .iter()
+                .map(|value| value.is_some() as i16)
+                .collect::<Vec<_>>();
+
+            if let Some(mut column) = row_group.next_column()? {
+                column
+                    .typed::<DoubleType>()
+                    .write_batch(&values, Some(&defined), None)?;
+                column.close()?;
+            }
+        }
+
+        row_group.close()?;
+        writer.close()?;
+
+        Ok(file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::prelude::*;
+
+    fn candles() -> Vec<Candle> {
+        vec![
+            Candle {
+                open: dec!(100.50),
+                high: dec!(102),
+                low: dec!(99.25),
+                close: dec!(101),
+                volume: dec!(12.5),
+                open_time: 1_704_067_200_000,
+                close_time: 1_704_070_799_999,
+            },
+            Candle {
+                open: dec!(101),
+                high: dec!(101.5),
+                low: dec!(100),
+                close: dec!(100.75),
+                volume: dec!(3),
+                open_time: 1_704_070_800_000,
+                close_time: 1_704_074_399_999,
+            },
+        ]
+    }
+
+    fn sma() -> Indicator {
+        Indicator {
+            name: "sma_2".to_string(),
+            values: vec![None, Some(dec!(100.875))],
+        }
+    }
+
+    #[test]
+    fn csv_and_json_lines_carry_times_prices_and_indicators() {
+        let candles = candles();
+        let table = CandleTable::new(&candles, Timestamps::Utc)
+            .with_indicator(sma())
+            .unwrap();
+
+        let csv = table.to_csv();
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("open_time,close_time,open,high,low,close,volume,sma_2")
+        );
+        assert_eq!(
+            lines.next(),
+            Some("2024-01-01T00:00:00.000Z,2024-01-01T00:59:59.999Z,100.50,102,99.25,101,12.5,")
+        );
+        assert!(lines.next().unwrap().ends_with(",100.875"));
+
+        let json = CandleTable::new(&candles, Timestamps::Millis)
+            .with_indicator(sma())
+            .unwrap()
+            .to_json_lines();
+        let first =
+            serde_json::from_str::<serde_json::Value>(json.lines().next().unwrap()).unwrap();
+        assert_eq!(first["open_time"], 1_704_067_200_000u64);
+        assert_eq!(first["open"], 100.5);
+        assert!(first["sma_2"].is_null());
+        assert_eq!(json.lines().count(), 2);
+
+        let err = CandleTable::new(&candles, Timestamps::Utc)
+            .with_indicator(Indicator {
+                name: "short".to_string(),
+                values: vec![None],
+            })
+            .unwrap_err();
+        assert_eq!(err, "short has 1 values for 2 candles");
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn parquet_reads_back_with_typed_columns() {
+        use parquet::file::reader::{FileReader, SerializedFileReader};
+
+        let candles = candles();
+        let parquet = CandleTable::new(&candles, Timestamps::Utc)
+            .with_indicator(sma())
+            .unwrap()
+            .to_parquet()
+            .unwrap();
+
+        let reader = SerializedFileReader::new(bytes::Bytes::from(parquet)).unwrap();
+        let metadata = reader.metadata().file_metadata();
+        assert_eq!(metadata.num_rows(), 2);
+
+        let columns = metadata
+            .schema_descr()
+            .columns()
+            .iter()
+            .map(|c| c.name().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            columns,
+            [
+                "open_time",
+                "close_time",
+                "open",
+                "high",
+                "low",
+                "close",
+                "volume",
+                "sma_2"
+            ]
+        );
+
+        let rows = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| row.unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert!(rows[0].contains("open: 100.5"), "{}", rows[0]);
+        assert!(rows[0].contains("sma_2: null"), "{}", rows[0]);
+        assert!(rows[1].contains("sma_2: 100.875"), "{}", rows[1]);
+    }
+
+    #[test]
+    fn candles_read_back_from_csv_in_either_layout() {
+        let candles = candles();
+        for timestamps in [Timestamps::Millis, Timestamps::Utc, Timestamps::Local] {
+            let csv = CandleTable::new(&candles, timestamps).to_csv();
+            assert_eq!(parse_candles(&csv), Ok(candles.clone()));
+        }
+
+        let csv = "epoch_open,iso8601_open,low,open,close,high\n\
+                   1704070800,2024-01-01 01:00:00+00:00,100,101,100.75,101.5\n\
+                   1704067200,2024-01-01 00:00:00+00:00,99.25,100.50,101,102\n";
+        let parsed = parse_candles(csv).unwrap();
+        assert_eq!(parsed.len(), 2);
+        assert_eq!(parsed[0].open_time, candles[0].open_time);
+        assert_eq!(parsed[0].close_time, candles[0].close_time);
+        assert_eq!(parsed[1].close_time, candles[1].close_time);
+        assert_eq!(parsed[1].high, dec!(101.5));
+        assert_eq!(parsed[1].volume, Decimal::ZERO);
+
+        let err = parse_candles("time,open,high,low\n1,2,3,4\n").unwrap_err();
+        assert_eq!(err, "The candle file has no close column");
+        let err = parse_candles("time,open,high,low,close\nsoon,1,2,0.5,1\n").unwrap_err();
+        assert!(err.contains("Line 2: time is not a time"), "{}", err);
+    }
+
+    #[test]
+    fn quoted_fields_read_back() {
+        let candles = candles();
+        let csv = CandleTable::new(&candles, Timestamps::Utc)
+            .with_indicator(Indicator {
+                name: "sma \"2, closes\"".to_string(),
+                values: vec![None, None],
+            })
+            .unwrap()
+            .to_csv();
+        assert!(csv.starts_with(
+            "open_time,close_time,open,high,low,close,volume,\"sma \"\"2, closes\"\"\"\n"
+        ));
+        assert_eq!(parse_candles(&csv), Ok(candles.clone()));
+
+        let csv = "\"time\",\"note\",open,high,low,close\n\
+                   \"1704067200\",\"two\nlines, \"\"quoted\"\"\",1,2,0.5,1\n\
+                   1704070800,,1,2,0.5,x\n";
+        let err = parse_candles(csv).unwrap_err();
+        assert!(err.starts_with("Line 4: close must be a number"), "{}", err);
+    }
+
+    #[test]
+    fn format_follows_the_extension() {
+        assert_eq!(
+            DataFormat::from_path(Path::new("btc.NDJSON")),
+            Ok(DataFormat::JsonLines)
+        );
+        assert_eq!(
+            DataFormat::from_path(Path::new("data/btc.parquet")),
+            Ok(DataFormat::Parquet)
+        );
+        assert!(DataFormat::from_path(Path::new("btc.xlsx")).is_err());
+        assert_eq!(Timestamps::parse("local"), Ok(Timestamps::Local));
+    }
+}
@@ -0,0 +1,41 @@
+//! Instruments and candles shared by the unit tests.
+
+use crate::candle::Candle;
+use crate::symbols::{Symbol, TRADING_STATUS};
+use rust_decimal::Decimal;
+use std::ops::Range;
+
+/// A trading `base`/`quote` pair on the 1h timeframe.
+pub fn pair(base: &str, quote: &str, decimals: u32) -> Symbol {
+    Symbol {
+        symbol: format!("{}{}", base, quote),
+        price: None,
+        decimals,
+        timeframe: "1h".to_string(),
+        base_asset: base.to_string(),
+        quote_asset: quote.to_string(),
+        status: TRADING_STATUS.to_string(),
+        permissions: Vec::new(),
+    }
+}
+
+/// Contiguous bars `interval` milliseconds apart, the i-th opening at `i * interval`.
+/// Prices wander between 98 and 108 and volume counts up, so bars differ.
+pub fn candles(range: Range<u64>, interval: u64) -> Vec<Candle> {
+    range
+        .map(|i| {
+            let open = Decimal::from(100 + i % 7);
+            let close = open + Decimal::from(i % 3) - Decimal::ONE;
+
+            Candle {
+                open_time: i * interval,
+                close_time: (i + 1) * interval - 1,
+                open,
+                high: open.max(close) + Decimal::ONE,
+                low: open.min(close) - Decimal::ONE,
+                close,
+                volume: Decimal::from(i),
+            }
+        })
+        .collect()
+}
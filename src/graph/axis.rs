@@ -1,14 +1,13 @@
-use crate::price_to_y;
-use crate::utils::{calculate_tick_count, estimate_y_axis_width};
+use crate::utils::{calculate_tick_count, estimate_y_axis_width, price_to_y};
 use chrono::Duration;
 use chrono::prelude::*;
 use iced::alignment::{Horizontal, Vertical};
 use iced::widget::canvas::{Frame, Path, Stroke, Text};
-use iced::{Color, Pixels, Point, Renderer, Theme};
+use iced::{Pixels, Point, Renderer};
 use rust_decimal::Decimal;
-use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::prelude::*;
-use crate::colors::{GRAY_COLOR, WHITE_COLOR};
+use crate::colors::color;
+use crate::theme::Palette;
 
 pub struct YAxisRenderer {
     pub screen_width: f32,
@@ -17,10 +16,21 @@ pub struct YAxisRenderer {
     pub display_max: Decimal,
     pub offset: f32,
     pub decimal_places: u32,
-    pub text_color: Color,
+    pub palette: Palette,
 }
 
 impl YAxisRenderer {
+    /// What `render_axis` returns, without drawing, so cached layers can key on the scale.
+    pub fn scale(&self) -> (Decimal, Decimal, f32) {
+        let (tick_count, tick_interval) = calculate_tick_count(self.display_min, self.display_max);
+        let axis_y_width = estimate_y_axis_width(self.display_min, tick_count, tick_interval, 12.0);
+        let mut tick_start = (self.display_min / tick_interval).floor() * tick_interval;
+        tick_start.rescale(self.decimal_places);
+
+        let display_max = tick_start + Decimal::from_usize(tick_count - 1).unwrap() * tick_interval;
+        (tick_start, display_max, axis_y_width)
+    }
+
     pub fn render_axis(&self, frame: &mut Frame<Renderer>) -> (Decimal, Decimal, f32) {
         let (mut tick_start, tick_count, tick_interval, axis_y_width) =
             self.render_axis_line(frame);
@@ -55,7 +65,7 @@ impl YAxisRenderer {
 
         frame.stroke(
             &y_axis,
-            Stroke::default().with_color([0.976, 0.980, 0.984].into()),
+            Stroke::default().with_color(color(self.palette.axis)),
         );
 
         (tick_start, tick_count, tick_interval, axis_y_width)
@@ -94,7 +104,7 @@ impl YAxisRenderer {
 
             frame.stroke(
                 &tick_line,
-                Stroke::default().with_color(GRAY_COLOR.into()),
+                Stroke::default().with_color(color(self.palette.grid)),
             );
 
             frame.fill_text(Text {
@@ -103,7 +113,7 @@ impl YAxisRenderer {
                     x: label_x,
                     y: y_pos + self.offset,
                 },
-                color: self.text_color,
+                color: color(self.palette.text),
                 size: Pixels(12.0),
                 line_height: Default::default(),
                 font: Default::default(),
@@ -123,6 +133,7 @@ pub struct XAxisRenderer {
     pub start_time: u64,
     pub end_time: u64,
     pub timeframe: String,
+    pub palette: Palette,
 }
 
 impl XAxisRenderer {
@@ -140,7 +151,7 @@ impl XAxisRenderer {
 
         frame.stroke(
             &x_axis,
-            Stroke::default().with_color(WHITE_COLOR.into()),
+            Stroke::default().with_color(color(self.palette.axis)),
         );
 
         let font_size = 12.0;
@@ -195,10 +206,10 @@ impl XAxisRenderer {
                         },
                     );
 
-                    frame.stroke(&tick, Stroke::default().with_color([0.6, 0.6, 0.6].into()));
+                    frame.stroke(&tick, Stroke::default().with_color(color(self.palette.axis)));
                 }
 
-                current_time = current_time + Duration::minutes(interval_minutes);
+                current_time += Duration::minutes(interval_minutes);
             }
         }
     }
@@ -216,8 +227,96 @@ impl XAxisRenderer {
                 y: y_pos,
             },
             size: Pixels(font_size),
-            color: [0.4, 0.4, 0.4].into(),
+            color: color(self.palette.muted),
             ..Text::default()
         });
     }
 }
+
+/// Horizontal price axis for charts that plot quantity against price, like the depth chart.
+pub struct PriceAxisRenderer {
+    pub screen_width: f32,
+    pub axis_y: f32,
+    pub display_min: Decimal,
+    pub display_max: Decimal,
+    pub decimal_places: u32,
+    pub palette: Palette,
+}
+
+impl PriceAxisRenderer {
+    pub fn price_to_x(&self, price: Decimal) -> f32 {
+        let range = self.display_max - self.display_min;
+
+        if range <= Decimal::ZERO {
+            return 0.0;
+        }
+
+        ((price - self.display_min) / range).to_f32().unwrap_or(0.0) * self.screen_width
+    }
+
+    pub fn x_to_price(&self, x: f32) -> Decimal {
+        let fraction = Decimal::from_f32(x / self.screen_width).unwrap_or_default();
+        self.display_min + (self.display_max - self.display_min) * fraction
+    }
+
+    pub fn render_axis(&self, frame: &mut Frame<Renderer>) {
+        let x_axis = Path::line(
+            Point {
+                x: 0.0,
+                y: self.axis_y,
+            },
+            Point {
+                x: self.screen_width,
+                y: self.axis_y,
+            },
+        );
+
+        frame.stroke(&x_axis, Stroke::default().with_color(color(self.palette.axis)));
+
+        let (tick_count, tick_interval) = calculate_tick_count(self.display_min, self.display_max);
+
+        if tick_interval <= Decimal::ZERO {
+            return;
+        }
+
+        let tick_start = (self.display_min / tick_interval).ceil() * tick_interval;
+        let font_size = 12.0;
+
+        for i in 0..tick_count {
+            let mut tick_value = tick_start + Decimal::from_usize(i).unwrap() * tick_interval;
+
+            if tick_value > self.display_max {
+                break;
+            }
+
+            tick_value.rescale(self.decimal_places);
+            let x_pos = self.price_to_x(tick_value);
+            let label = tick_value.to_string();
+            let text_width = label.len() as f32 * font_size * 0.6;
+
+            let tick = Path::line(
+                Point {
+                    x: x_pos,
+                    y: self.axis_y,
+                },
+                Point {
+                    x: x_pos,
+                    y: self.axis_y + 7.0,
+                },
+            );
+
+            frame.stroke(&tick, Stroke::default().with_color(color(self.palette.axis)));
+
+            frame.fill_text(Text {
+                content: label,
+                position: Point {
+                    x: x_pos - text_width / 2.0,
+                    y: self.axis_y + 10.0,
+                },
+                size: Pixels(font_size),
+                color: color(self.palette.text),
+                ..Text::default()
+            });
+        }
+    }
+}
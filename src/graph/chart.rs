@@ -0,0 +1,443 @@
+use crate::candle::{Candle, PlotCandle};
+use crate::colors::{candle_color, color};
+use crate::graph::axis::{XAxisRenderer, YAxisRenderer};
+use crate::graph::paper::{PaperMarks, PaperRenderer};
+use crate::graph::profile::VolumeProfileRenderer;
+use crate::profile::VolumeProfiles;
+use crate::theme::Palette;
+use crate::utils::PriceScale;
+use iced::widget::canvas::{Cache, Frame, Geometry, Path, Stroke, Text};
+use iced::{Color, Pixels, Point, Rectangle, Renderer, Size, mouse};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::*;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::ops::Range;
+
+/// A canvas cache that clears itself whenever the key it was drawn for changes, so a
+/// layer is only re-tessellated when its own inputs move.
+pub struct LayerCache<K> {
+    cache: Cache,
+    key: RefCell<Option<K>>,
+}
+
+impl<K> Default for LayerCache<K> {
+    fn default() -> Self {
+        Self {
+            cache: Cache::new(),
+            key: RefCell::new(None),
+        }
+    }
+}
+
+impl<K: PartialEq> LayerCache<K> {
+    pub fn draw(
+        &self,
+        renderer: &Renderer,
+        size: Size,
+        key: K,
+        draw_fn: impl Fn(&mut Frame<Renderer>),
+    ) -> Geometry {
+        if self.key.borrow().as_ref() != Some(&key) {
+            self.cache.clear();
+            *self.key.borrow_mut() = Some(key);
+        }
+
+        self.cache.draw(renderer, size, draw_fn)
+    }
+}
+
+/// Everything a layer's position depends on, shared by all layer keys.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChartScale {
+    size: Size,
+    display_min: Decimal,
+    display_max: Decimal,
+    axis_y_width: f32,
+    count: usize,
+}
+
+#[derive(Default)]
+pub struct ChartCaches {
+    grid: LayerCache<(ChartScale, u64, u64, String, Palette)>,
+    history: LayerCache<(ChartScale, u64, Palette)>,
+    forming: LayerCache<(ChartScale, Candle, Palette)>,
+    paper: LayerCache<(ChartScale, u64, Option<u64>, Palette)>,
+    backtest: LayerCache<(ChartScale, u64, Option<u64>, Palette)>,
+    overlays: LayerCache<(ChartScale, Option<u64>, Palette)>,
+    crosshair: LayerCache<(ChartScale, Option<Point>, Palette)>,
+}
+
+pub struct CandleChart<'a> {
+    pub candles: &'a VecDeque<Candle>,
+    /// The same bars as `f64`, kept next to them in the candle cache. Exports that draw
+    /// once leave it out and the bars are converted while drawing.
+    pub plot: Option<&'a VecDeque<PlotCandle>>,
+    /// How many of the newest candles fit the width, the zoom level.
+    pub visible: usize,
+    pub decimals: u32,
+    pub timeframe: &'a str,
+    pub profiles: Option<&'a VolumeProfiles>,
+    /// Paper orders and fills drawn over the candles.
+    pub paper: Option<PaperMarks<'a>>,
+    /// The fills of a backtest, with a number that changes on every run.
+    pub backtest: Option<(u64, PaperMarks<'a>)>,
+    pub caches: &'a ChartCaches,
+    pub palette: Palette,
+}
+
+const OFFSET: f32 = 30.0;
+
+/// Closed bars only change when the exchange revises them, hashing them is far cheaper
+/// than re-tessellating them.
+fn history_hash<'a>(candles: impl Iterator<Item = &'a Candle>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    for candle in candles {
+        candle.open_time.hash(&mut hasher);
+        candle.open.hash(&mut hasher);
+        candle.high.hash(&mut hasher);
+        candle.low.hash(&mut hasher);
+        candle.close.hash(&mut hasher);
+    }
+
+    hasher.finish()
+}
+
+impl CandleChart<'_> {
+    /// Indices of the candles on screen, the newest `visible` ones.
+    pub fn visible_range(&self) -> Range<usize> {
+        let count = self.visible.clamp(1, self.candles.len().max(1));
+        self.candles.len().saturating_sub(count)..self.candles.len()
+    }
+
+    pub fn draw(
+        &self,
+        renderer: &Renderer,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let visible = self.visible_range();
+        let (start, count) = (visible.start, visible.len());
+
+        let (Some(first), Some(last)) = (self.candles.get(start), self.candles.back()) else {
+            return Vec::new();
+        };
+
+        let display_max = self
+            .candles
+            .range(start..)
+            .fold(Decimal::MIN, |acc, c| acc.max(c.high.max(c.low)));
+        let display_min = self
+            .candles
+            .range(start..)
+            .fold(Decimal::MAX, |acc, c| acc.min(c.high.min(c.low)));
+
+        let screen_height = bounds.height - 140.0;
+        let screen_width = bounds.width;
+
+        let y_axis = YAxisRenderer {
+            screen_width,
+            screen_height,
+            display_min,
+            display_max,
+            offset: OFFSET,
+            decimal_places: self.decimals,
+            palette: self.palette,
+        };
+
+        let (display_min, display_max, axis_y_width) = y_axis.scale();
+        let plot_width = screen_width - axis_y_width;
+
+        let scale = ChartScale {
+            size: bounds.size(),
+            display_min,
+            display_max,
+            axis_y_width,
+            count,
+        };
+
+        let grid = self.caches.grid.draw(
+            renderer,
+            bounds.size(),
+            (
+                scale.clone(),
+                first.open_time,
+                last.close_time,
+                self.timeframe.to_string(),
+                self.palette,
+            ),
+            |frame| {
+                y_axis.render_axis(frame);
+
+                XAxisRenderer {
+                    screen_width: plot_width,
+                    screen_height,
+                    start_time: first.open_time,
+                    end_time: last.close_time,
+                    timeframe: self.timeframe.to_string(),
+                    palette: self.palette,
+                }
+                .render_axis(frame);
+            },
+        );
+
+        let overlays = self.caches.overlays.draw(
+            renderer,
+            bounds.size(),
+            (
+                scale.clone(),
+                self.profiles.map(|profiles| profiles.revision),
+                self.palette,
+            ),
+            |frame| {
+                if let Some(profiles) = self.profiles {
+                    VolumeProfileRenderer {
+                        profiles,
+                        plot_width,
+                        screen_height,
+                        display_min,
+                        display_max,
+                        offset: OFFSET,
+                        palette: self.palette,
+                    }
+                    .render(frame);
+                }
+            },
+        );
+
+        let unit_width = (plot_width - 10.0) / count as f32;
+        let layout = CandleLayout {
+            display_min,
+            display_max,
+            price_scale: PriceScale::new(display_min, display_max, screen_height),
+            screen_height,
+            unit_width,
+        };
+
+        let history_len = count - 1;
+        let history_range = start..start + history_len;
+
+        let history = self.caches.history.draw(
+            renderer,
+            bounds.size(),
+            (
+                scale.clone(),
+                history_hash(self.candles.range(history_range.clone())),
+                self.palette,
+            ),
+            |frame| {
+                if let Some(plot) = self.plot {
+                    for (i, candle) in plot.range(history_range.clone()).enumerate() {
+                        layout.draw_candle(frame, i, candle, &self.palette);
+                    }
+                } else {
+                    for (i, candle) in self.candles.range(history_range.clone()).enumerate() {
+                        layout.draw_candle(frame, i, &candle.into(), &self.palette);
+                    }
+                }
+            },
+        );
+
+        let forming = self.caches.forming.draw(
+            renderer,
+            bounds.size(),
+            (scale.clone(), last.clone(), self.palette),
+            |frame| layout.draw_candle(frame, history_len, &last.into(), &self.palette),
+        );
+
+        let render_marks = |frame: &mut Frame<Renderer>, marks| {
+            PaperRenderer {
+                marks,
+                candles: self.candles.range(visible.clone()).collect(),
+                price_scale: layout.price_scale,
+                display_min,
+                display_max,
+                plot_width,
+                unit_width,
+                offset: OFFSET,
+                palette: self.palette,
+            }
+            .render(frame)
+        };
+
+        let paper = self.caches.paper.draw(
+            renderer,
+            bounds.size(),
+            (
+                scale.clone(),
+                first.open_time,
+                self.paper.map(|marks| marks.account.revision),
+                self.palette,
+            ),
+            |frame| {
+                if let Some(marks) = self.paper {
+                    render_marks(frame, marks);
+                }
+            },
+        );
+
+        let backtest = self.caches.backtest.draw(
+            renderer,
+            bounds.size(),
+            (
+                scale.clone(),
+                first.open_time,
+                self.backtest.map(|(run, _)| run),
+                self.palette,
+            ),
+            |frame| {
+                if let Some((_, marks)) = self.backtest {
+                    render_marks(frame, marks);
+                }
+            },
+        );
+
+        let position = cursor
+            .position_in(bounds)
+            .filter(|p| p.x < plot_width && p.y >= OFFSET && p.y <= screen_height + OFFSET);
+
+        let crosshair = self.caches.crosshair.draw(
+            renderer,
+            bounds.size(),
+            (scale, position, self.palette),
+            |frame| {
+                if let Some(position) = position {
+                    layout.draw_crosshair(
+                        frame,
+                        position,
+                        plot_width,
+                        self.decimals,
+                        color(self.palette.text),
+                    );
+                }
+            },
+        );
+
+        vec![grid, overlays, history, forming, paper, backtest, crosshair]
+    }
+}
+
+struct CandleLayout {
+    display_min: Decimal,
+    display_max: Decimal,
+    price_scale: PriceScale,
+    screen_height: f32,
+    unit_width: f32,
+}
+
+impl CandleLayout {
+    fn draw_candle(
+        &self,
+        frame: &mut Frame<Renderer>,
+        i: usize,
+        candle: &PlotCandle,
+        palette: &Palette,
+    ) {
+        let color = candle_color(palette, candle);
+        let candle_width = self.unit_width * 0.9;
+        let candle_spacing = self.unit_width * 0.1;
+
+        let open_y = self.price_scale.y(candle.open);
+        let close_y = self.price_scale.y(candle.close);
+        let low_y = self.price_scale.y(candle.low);
+        let high_y = self.price_scale.y(candle.high);
+        let height = (open_y - close_y).abs().max(1.0);
+
+        let x_position = i as f32 * self.unit_width;
+        let candle_center_x = x_position + (candle_spacing / 2.0) + (candle_width / 2.0);
+
+        let wick = Path::line(
+            Point {
+                x: candle_center_x,
+                y: high_y + OFFSET,
+            },
+            Point {
+                x: candle_center_x,
+                y: low_y + OFFSET,
+            },
+        );
+
+        let rectangle = Path::rectangle(
+            Point {
+                x: x_position + (candle_spacing / 2.0),
+                y: open_y.min(close_y) + OFFSET,
+            },
+            Size {
+                width: candle_width,
+                height,
+            },
+        );
+
+        frame.fill(&rectangle, color);
+        frame.stroke(&wick, Stroke::default().with_color(color));
+    }
+
+    fn draw_crosshair(
+        &self,
+        frame: &mut Frame<Renderer>,
+        position: Point,
+        plot_width: f32,
+        decimals: u32,
+        text_color: Color,
+    ) {
+        let color = Color {
+            a: 0.5,
+            ..text_color
+        };
+
+        frame.stroke(
+            &Path::line(
+                Point::new(0.0, position.y),
+                Point::new(plot_width, position.y),
+            ),
+            Stroke::default().with_color(color),
+        );
+        frame.stroke(
+            &Path::line(
+                Point::new(position.x, OFFSET),
+                Point::new(position.x, self.screen_height + OFFSET),
+            ),
+            Stroke::default().with_color(color),
+        );
+
+        let fraction =
+            Decimal::from_f32((position.y - OFFSET) / self.screen_height).unwrap_or_default();
+        let price = (self.display_max - (self.display_max - self.display_min) * fraction)
+            .round_dp(decimals);
+
+        frame.fill_text(Text {
+            content: price.to_string(),
+            position: Point::new(plot_width + 10.0, position.y),
+            size: Pixels(12.0),
+            color: text_color,
+            vertical_alignment: iced::alignment::Vertical::Center,
+            ..Text::default()
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::theme::LIGHT;
+
+    #[test]
+    fn plot_candle_keeps_color() {
+        let candle = Candle {
+            open_time: 0,
+            close_time: 59_999,
+            open: dec!(100.5),
+            high: dec!(101.25),
+            low: dec!(99.75),
+            close: dec!(101),
+            volume: dec!(3),
+        };
+        let plot = PlotCandle::from(&candle);
+
+        assert_eq!(plot.close, 101.0);
+        assert_eq!(candle_color(&LIGHT, &plot), color(LIGHT.bull));
+    }
+}
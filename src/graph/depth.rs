@@ -0,0 +1,259 @@
+use crate::colors::color;
+use crate::graph::axis::{PriceAxisRenderer, YAxisRenderer};
+use crate::orderbook::{CumulativeLevel, OrderBook, Side};
+use crate::theme::Palette;
+use crate::utils::{nice_step_from_range, price_to_y};
+use iced::widget::canvas::{self, Event, Frame, Geometry, Path, Stroke, Text, event};
+use iced::{Color, Pixels, Point, Rectangle, Renderer, Size, Theme, mouse};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::*;
+
+/// Fraction of the mid price shown on each side before zooming in.
+pub const DEPTH_CHART_RANGE: Decimal = dec!(0.01);
+const MAX_ZOOM: f32 = 50.0;
+const ZOOM_STEP: f32 = 1.2;
+
+pub struct DepthChart<'a> {
+    pub book: &'a OrderBook,
+    pub decimals: u32,
+    pub palette: Palette,
+}
+
+pub struct DepthChartState {
+    zoom: f32,
+}
+
+impl Default for DepthChartState {
+    fn default() -> Self {
+        Self { zoom: 1.0 }
+    }
+}
+
+/// Quantity resting between the mid and `price`, on whichever side of the mid it is.
+fn level_at(
+    bids: &[CumulativeLevel],
+    asks: &[CumulativeLevel],
+    mid: Decimal,
+    price: Decimal,
+) -> Option<CumulativeLevel> {
+    if price <= mid {
+        bids.iter().take_while(|l| l.price >= price).last().cloned()
+    } else {
+        asks.iter().take_while(|l| l.price <= price).last().cloned()
+    }
+}
+
+impl<Message> canvas::Program<Message> for DepthChart<'_> {
+    type State = DepthChartState;
+
+    fn update(
+        &self,
+        state: &mut Self::State,
+        event: Event,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> (event::Status, Option<Message>) {
+        if let Event::Mouse(mouse::Event::WheelScrolled { delta }) = event
+            && cursor.is_over(bounds)
+        {
+            let y = match delta {
+                mouse::ScrollDelta::Lines { y, .. } | mouse::ScrollDelta::Pixels { y, .. } => y,
+            };
+
+            state.zoom = if y > 0.0 {
+                state.zoom * ZOOM_STEP
+            } else {
+                state.zoom / ZOOM_STEP
+            }
+            .clamp(1.0, MAX_ZOOM);
+
+            return (event::Status::Captured, None);
+        }
+
+        (event::Status::Ignored, None)
+    }
+
+    fn draw(
+        &self,
+        state: &Self::State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+
+        let Some(mid) = self.book.mid_price() else {
+            return vec![frame.into_geometry()];
+        };
+
+        let half_range =
+            mid * DEPTH_CHART_RANGE / Decimal::from_f32(state.zoom).unwrap_or(Decimal::ONE);
+        let from = mid - half_range;
+        let to = mid + half_range;
+
+        let bids = self.book.cumulative(Side::Bid, from);
+        let asks = self.book.cumulative(Side::Ask, to);
+
+        let max_quantity = bids
+            .last()
+            .map(|l| l.quantity)
+            .unwrap_or_default()
+            .max(asks.last().map(|l| l.quantity).unwrap_or_default());
+
+        if max_quantity <= Decimal::ZERO {
+            return vec![frame.into_geometry()];
+        }
+
+        let offset = 10.0;
+        let screen_height = bounds.height - 40.0;
+        let text_color = color(self.palette.text);
+
+        let y_axis = YAxisRenderer {
+            screen_width: bounds.width,
+            screen_height,
+            display_min: Decimal::ZERO,
+            display_max: max_quantity,
+            offset,
+            decimal_places: nice_step_from_range(max_quantity).normalize().scale(),
+            palette: self.palette,
+        };
+
+        let (display_min, display_max, axis_y_width) = y_axis.render_axis(&mut frame);
+
+        let x_axis = PriceAxisRenderer {
+            screen_width: bounds.width - axis_y_width,
+            axis_y: screen_height + offset,
+            display_min: from,
+            display_max: to,
+            decimal_places: self.decimals,
+            palette: self.palette,
+        };
+
+        x_axis.render_axis(&mut frame);
+
+        let quantity_y = |quantity: Decimal| {
+            price_to_y(quantity, display_min, display_max, screen_height)
+                .to_f32()
+                .unwrap_or(0.0)
+                + offset
+        };
+        let base_y = quantity_y(Decimal::ZERO);
+
+        for (levels, edge, side_color) in [
+            (&bids, from, color(self.palette.bull)),
+            (&asks, to, color(self.palette.bear)),
+        ] {
+            let Some(first) = levels.first() else {
+                continue;
+            };
+
+            let mut points = vec![Point::new(x_axis.price_to_x(first.price), base_y)];
+            let mut previous = Decimal::ZERO;
+
+            for level in levels.iter() {
+                let x = x_axis.price_to_x(level.price);
+                points.push(Point::new(x, quantity_y(previous)));
+                points.push(Point::new(x, quantity_y(level.quantity)));
+                previous = level.quantity;
+            }
+
+            let edge_x = x_axis.price_to_x(edge);
+            points.push(Point::new(edge_x, quantity_y(previous)));
+
+            let outline = Path::new(|builder| {
+                builder.move_to(points[0]);
+                for point in &points[1..] {
+                    builder.line_to(*point);
+                }
+            });
+
+            let area = Path::new(|builder| {
+                builder.move_to(points[0]);
+                for point in &points[1..] {
+                    builder.line_to(*point);
+                }
+                builder.line_to(Point::new(edge_x, base_y));
+                builder.close();
+            });
+
+            frame.fill(&area, Color { a: 0.2, ..side_color });
+            frame.stroke(
+                &outline,
+                Stroke::default().with_color(side_color).with_width(1.5),
+            );
+        }
+
+        let mid_x = x_axis.price_to_x(mid);
+        frame.stroke(
+            &Path::line(Point::new(mid_x, offset), Point::new(mid_x, base_y)),
+            Stroke::default().with_color(Color {
+                a: 0.6,
+                ..text_color
+            }),
+        );
+        frame.fill_text(Text {
+            content: format!("Mid {}", mid.round_dp(self.decimals + 1).normalize()),
+            position: Point::new(mid_x + 4.0, offset),
+            size: Pixels(12.0),
+            color: text_color,
+            ..Text::default()
+        });
+
+        if let Some(position) = cursor.position_in(bounds)
+            && position.x < x_axis.screen_width
+            && position.y < base_y
+        {
+            let price = x_axis.x_to_price(position.x);
+
+            frame.stroke(
+                &Path::line(
+                    Point::new(position.x, offset),
+                    Point::new(position.x, base_y),
+                ),
+                Stroke::default().with_color(Color {
+                    a: 0.4,
+                    ..text_color
+                }),
+            );
+
+            if let Some(level) = level_at(&bids, &asks, mid, price) {
+                let lines = [
+                    format!("Price {}", price.round_dp(self.decimals)),
+                    format!("Qty {}", level.quantity.normalize()),
+                    format!("Notional {}", level.notional.round_dp(2)),
+                ];
+
+                let width =
+                    lines.iter().map(|l| l.len()).max().unwrap_or(0) as f32 * 12.0 * 0.6 + 10.0;
+                let x = if position.x + width + 10.0 > x_axis.screen_width {
+                    position.x - width - 10.0
+                } else {
+                    position.x + 10.0
+                };
+                let y = position.y.min(base_y - 50.0).max(offset);
+
+                frame.fill_rectangle(
+                    Point::new(x, y),
+                    Size::new(width, 48.0),
+                    Color {
+                        a: 0.85,
+                        ..color(self.palette.background)
+                    },
+                );
+
+                for (i, line) in lines.into_iter().enumerate() {
+                    frame.fill_text(Text {
+                        content: line,
+                        position: Point::new(x + 5.0, y + 4.0 + i as f32 * 14.0),
+                        size: Pixels(12.0),
+                        color: text_color,
+                        ..Text::default()
+                    });
+                }
+            }
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
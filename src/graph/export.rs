@@ -0,0 +1,513 @@
+use crate::candle::Candle;
+use crate::colors::color;
+use crate::graph::chart::{CandleChart, ChartCaches};
+use crate::graph::paper::PaperMarks;
+use crate::paper::PaperAccount;
+use crate::profile::VolumeProfiles;
+use crate::theme::Palette;
+use iced::advanced::graphics::Viewport;
+use iced::advanced::graphics::geometry::Renderer as _;
+use iced::alignment::{Horizontal, Vertical};
+use iced::{Color, Font, Pixels, Rectangle, Renderer, Size, mouse};
+use iced_tiny_skia::Primitive;
+use iced_tiny_skia::graphics::Text;
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tiny_skia::{FillRule, Mask, PathSegment, Pixmap, PixmapPaint, Shader, Transform};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Png,
+    Svg,
+}
+
+impl ExportFormat {
+    pub fn from_path(path: &Path) -> Result<Self, String> {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("png") => Ok(ExportFormat::Png),
+            Some(ext) if ext.eq_ignore_ascii_case("svg") => Ok(ExportFormat::Svg),
+            _ => Err(format!(
+                "Cannot export {}, expected a .png or .svg file",
+                path.display()
+            )),
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Png => "png",
+            ExportFormat::Svg => "svg",
+        }
+    }
+}
+
+/// Draws `chart` like the window does, minus the crosshair, with the software renderer
+/// that needs no window or GPU. It gets caches of its own, the window's hold geometry of
+/// another renderer.
+fn draw_offscreen(chart: &CandleChart, size: Size) -> iced_tiny_skia::Renderer {
+    let caches = ChartCaches::default();
+    let mut renderer =
+        Renderer::Secondary(iced_tiny_skia::Renderer::new(Font::default(), Pixels(16.0)));
+
+    let geometries = CandleChart {
+        caches: &caches,
+        ..*chart
+    }
+    .draw(
+        &renderer,
+        Rectangle::with_size(size),
+        mouse::Cursor::Unavailable,
+    );
+
+    for geometry in geometries {
+        renderer.draw_geometry(geometry);
+    }
+
+    match renderer {
+        Renderer::Secondary(renderer) => renderer,
+        Renderer::Primary(_) => unreachable!("the offscreen renderer is tiny-skia"),
+    }
+}
+
+/// The tiny-skia backend swaps red and blue for its BGRA surface, in paints and pixels.
+fn from_skia(color: tiny_skia::Color) -> Color {
+    Color::from_rgba(color.blue(), color.green(), color.red(), color.alpha())
+}
+
+/// `width` x `height` pixels, `scale` pixels per point so text and lines grow with it.
+pub fn render_png(
+    chart: &CandleChart,
+    width: u32,
+    height: u32,
+    scale: f32,
+) -> Result<Vec<u8>, String> {
+    let size = Size::new(width as f32 / scale, height as f32 / scale);
+    let mut renderer = draw_offscreen(chart, size);
+
+    let new_pixmap = || {
+        Pixmap::new(width, height)
+            .ok_or_else(|| format!("Cannot render a {}x{} image", width, height))
+    };
+    let mut pixmap = new_pixmap()?;
+    let mut drawn = new_pixmap()?;
+    let mut clip_mask = Mask::new(width, height)
+        .ok_or_else(|| format!("Cannot render a {}x{} image", width, height))?;
+
+    let background = color(chart.palette.background);
+    pixmap.fill(
+        tiny_skia::Color::from_rgba(background.b, background.g, background.r, background.a)
+            .unwrap_or(tiny_skia::Color::BLACK),
+    );
+
+    // The tiny-skia engine skips strokes whose bounds have no area, which are all the
+    // grid lines, axes and wicks, so those go down first and the rest on top of them.
+    let transform = Transform::from_scale(scale, scale);
+
+    for layer in renderer.layers() {
+        for primitive in layer.primitives.iter().flat_map(|item| item.as_slice()) {
+            if let Primitive::Stroke {
+                path,
+                paint,
+                stroke,
+            } = primitive
+                && (path.bounds().width() == 0.0 || path.bounds().height() == 0.0)
+            {
+                pixmap.stroke_path(path, paint, stroke, transform, None);
+            }
+        }
+    }
+
+    renderer.draw(
+        &mut drawn.as_mut(),
+        &mut clip_mask,
+        &Viewport::with_physical_size(Size::new(width, height), scale as f64),
+        &[Rectangle::with_size(size)],
+        Color::TRANSPARENT,
+        &[] as &[&str],
+    );
+
+    pixmap.draw_pixmap(
+        0,
+        0,
+        drawn.as_ref(),
+        &PixmapPaint::default(),
+        Transform::identity(),
+        None,
+    );
+
+    let rgba = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let pixel = pixel.demultiply();
+            [pixel.blue(), pixel.green(), pixel.red(), pixel.alpha()]
+        })
+        .collect::<Vec<_>>();
+
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&rgba))
+        .map_err(|err| err.to_string())?;
+
+    Ok(png)
+}
+
+fn svg_color(color: Color) -> String {
+    let [r, g, b, _] = color.into_rgba8();
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn svg_paint(attribute: &str, shader: &Shader) -> String {
+    match shader {
+        Shader::SolidColor(paint) => {
+            let paint = from_skia(*paint);
+            format!(
+                "{}=\"{}\" {}-opacity=\"{:.3}\"",
+                attribute,
+                svg_color(paint),
+                attribute,
+                paint.a
+            )
+        }
+        // The charts only paint solid colors.
+        _ => format!("{}=\"none\"", attribute),
+    }
+}
+
+fn svg_path_data(path: &tiny_skia::Path) -> String {
+    let mut data = String::new();
+
+    for segment in path.segments() {
+        let _ = match segment {
+            PathSegment::MoveTo(p) => write!(data, "M{:.2} {:.2}", p.x, p.y),
+            PathSegment::LineTo(p) => write!(data, "L{:.2} {:.2}", p.x, p.y),
+            PathSegment::QuadTo(c, p) => {
+                write!(data, "Q{:.2} {:.2} {:.2} {:.2}", c.x, c.y, p.x, p.y)
+            }
+            PathSegment::CubicTo(c1, c2, p) => write!(
+                data,
+                "C{:.2} {:.2} {:.2} {:.2} {:.2} {:.2}",
+                c1.x, c1.y, c2.x, c2.y, p.x, p.y
+            ),
+            PathSegment::Close => write!(data, "Z"),
+        };
+    }
+
+    data
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn svg_primitive(svg: &mut String, primitive: &Primitive) {
+    let _ = match primitive {
+        Primitive::Fill { path, paint, rule } => writeln!(
+            svg,
+            "<path d=\"{}\" {} fill-rule=\"{}\"/>",
+            svg_path_data(path),
+            svg_paint("fill", &paint.shader),
+            match rule {
+                FillRule::Winding => "nonzero",
+                FillRule::EvenOdd => "evenodd",
+            }
+        ),
+        Primitive::Stroke {
+            path,
+            paint,
+            stroke,
+        } => {
+            // SVG could dash by itself, but the pattern is private to tiny-skia.
+            let dashed = stroke.dash.as_ref().and_then(|dash| path.dash(dash, 1.0));
+
+            writeln!(
+                svg,
+                "<path d=\"{}\" fill=\"none\" {} stroke-width=\"{}\"/>",
+                svg_path_data(dashed.as_ref().unwrap_or(path)),
+                svg_paint("stroke", &paint.shader),
+                stroke.width
+            )
+        }
+    };
+}
+
+fn svg_text(svg: &mut String, text: &Text) {
+    // Canvas text is always cached text, paragraphs and editors belong to widgets.
+    let Text::Cached {
+        content,
+        bounds,
+        color,
+        size,
+        horizontal_alignment,
+        vertical_alignment,
+        ..
+    } = text
+    else {
+        return;
+    };
+
+    let anchor = match horizontal_alignment {
+        Horizontal::Left => "start",
+        Horizontal::Center => "middle",
+        Horizontal::Right => "end",
+    };
+    let baseline = match vertical_alignment {
+        Vertical::Top => "hanging",
+        Vertical::Center => "central",
+        Vertical::Bottom => "text-after-edge",
+    };
+
+    let _ = writeln!(
+        svg,
+        "<text x=\"{:.2}\" y=\"{:.2}\" font-family=\"sans-serif\" font-size=\"{}\" fill=\"{}\" \
+         fill-opacity=\"{:.3}\" text-anchor=\"{}\" dominant-baseline=\"{}\">{}</text>",
+        bounds.x,
+        bounds.y,
+        size.0,
+        svg_color(*color),
+        color.a,
+        anchor,
+        baseline,
+        escape_xml(content)
+    );
+}
+
+/// The same drawing commands as the window and the PNG, as vector paths and text.
+pub fn render_svg(chart: &CandleChart, width: u32, height: u32) -> String {
+    let mut renderer = draw_offscreen(chart, Size::new(width as f32, height as f32));
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" \
+         viewBox=\"0 0 {0} {1}\">\n<rect width=\"100%\" height=\"100%\" fill=\"{2}\"/>\n",
+        width,
+        height,
+        svg_color(color(chart.palette.background))
+    );
+
+    // Layers keep the order the canvas drew in, primitives before text within each.
+    for layer in renderer.layers() {
+        for primitive in layer.primitives.iter().flat_map(|item| item.as_slice()) {
+            svg_primitive(&mut svg, primitive);
+        }
+
+        for text in layer.text.iter().flat_map(|item| item.as_slice()) {
+            svg_text(&mut svg, text);
+        }
+    }
+
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Writes `chart` to `path`, PNG or SVG by its extension.
+pub fn export_chart(
+    chart: &CandleChart,
+    path: &Path,
+    width: u32,
+    height: u32,
+    scale: f32,
+) -> Result<(), String> {
+    let content = match ExportFormat::from_path(path)? {
+        ExportFormat::Png => render_png(chart, width, height, scale)?,
+        ExportFormat::Svg => render_svg(chart, width, height).into_bytes(),
+    };
+
+    fs::write(path, content).map_err(|err| format!("Cannot write {}: {}", path.display(), err))
+}
+
+/// What a chart export draws, owned so the image can be rendered off the UI thread.
+pub struct ChartSnapshot {
+    candles: VecDeque<Candle>,
+    visible: usize,
+    decimals: u32,
+    timeframe: String,
+    profiles: Option<VolumeProfiles>,
+    symbol: String,
+    paper: Option<PaperAccount>,
+    backtest: Option<(u64, PaperAccount)>,
+    palette: Palette,
+}
+
+impl ChartSnapshot {
+    pub fn new(chart: &CandleChart) -> Self {
+        let marks = chart.paper.or(chart.backtest.map(|(_, marks)| marks));
+
+        Self {
+            candles: chart.candles.clone(),
+            visible: chart.visible,
+            decimals: chart.decimals,
+            timeframe: chart.timeframe.to_string(),
+            profiles: chart.profiles.cloned(),
+            symbol: marks.map_or(String::new(), |marks| marks.symbol.to_string()),
+            paper: chart.paper.map(|marks| marks.account.clone()),
+            backtest: chart
+                .backtest
+                .map(|(run, marks)| (run, marks.account.clone())),
+            palette: chart.palette,
+        }
+    }
+
+    fn chart<'a>(&'a self, caches: &'a ChartCaches) -> CandleChart<'a> {
+        let marks = |account| PaperMarks {
+            account,
+            symbol: &self.symbol,
+        };
+
+        CandleChart {
+            candles: &self.candles,
+            plot: None,
+            visible: self.visible,
+            decimals: self.decimals,
+            timeframe: &self.timeframe,
+            profiles: self.profiles.as_ref(),
+            paper: self.paper.as_ref().map(marks),
+            backtest: self
+                .backtest
+                .as_ref()
+                .map(|(run, account)| (*run, marks(account))),
+            caches,
+            palette: self.palette,
+        }
+    }
+}
+
+/// Renders and writes a snapshot like `export_chart`, for a task.
+pub async fn write_chart(
+    snapshot: ChartSnapshot,
+    path: PathBuf,
+    width: u32,
+    height: u32,
+    scale: f32,
+) -> Result<PathBuf, String> {
+    let caches = ChartCaches::default();
+    export_chart(&snapshot.chart(&caches), &path, width, height, scale)?;
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{candles, pair};
+    use crate::paper::{OrderKind, OrderSide};
+    use crate::theme::LIGHT;
+    use rust_decimal::Decimal;
+    use std::collections::BTreeMap;
+
+    fn chart<'a>(candles: &'a VecDeque<Candle>, caches: &'a ChartCaches) -> CandleChart<'a> {
+        CandleChart {
+            candles,
+            plot: None,
+            visible: candles.len(),
+            decimals: 2,
+            timeframe: "1h",
+            profiles: None,
+            paper: None,
+            backtest: None,
+            caches,
+            palette: LIGHT,
+        }
+    }
+
+    #[test]
+    fn png_has_the_requested_size_and_palette() {
+        let candles = VecDeque::from(candles(0..30, 3_600_000));
+        let caches = ChartCaches::default();
+        let png = render_png(&chart(&candles, &caches), 640, 360, 2.0).unwrap();
+
+        let mut reader = png::Decoder::new(png.as_slice()).read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+
+        assert_eq!((info.width, info.height), (640, 360));
+        assert_eq!(info.color_type, png::ColorType::Rgba);
+
+        let background = LIGHT.background;
+        assert_eq!(
+            &pixels[..4],
+            &[background.0, background.1, background.2, 255]
+        );
+
+        let bull = [LIGHT.bull.0, LIGHT.bull.1, LIGHT.bull.2, 255];
+        assert!(pixels.chunks(4).any(|pixel| pixel == bull));
+
+        // Axis lines are strokes without area, which tiny-skia skips unless pre-stroked.
+        let axis = [LIGHT.axis.0, LIGHT.axis.1, LIGHT.axis.2, 255];
+        assert!(pixels.chunks(4).any(|pixel| pixel == axis));
+    }
+
+    #[test]
+    fn svg_has_the_candles_and_axis_labels() {
+        let candles = VecDeque::from(candles(0..30, 3_600_000));
+        let caches = ChartCaches::default();
+        let svg = render_svg(&chart(&candles, &caches), 800, 450);
+
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"800\""));
+        assert!(svg.contains(&format!("fill=\"{}\"", LIGHT.bull.hex())));
+        assert!(svg.contains(&format!("fill=\"{}\"", LIGHT.bear.hex())));
+        assert!(svg.contains(">105.00</text>"), "{}", svg);
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+
+    #[test]
+    fn svg_has_the_paper_order_lines() {
+        let candles = VecDeque::from(candles(0..30, 3_600_000));
+        let caches = ChartCaches::default();
+        let symbol = pair("BTC", "USDT", 2);
+        let mut account = PaperAccount::new(
+            BTreeMap::from([("USDT".to_string(), Decimal::from(10000))]),
+            Decimal::ZERO,
+            Decimal::ZERO,
+        );
+        let limit = OrderKind::Limit(Decimal::from(101));
+        account
+            .place(&symbol, OrderSide::Buy, limit, Decimal::TWO, None, 0)
+            .unwrap();
+
+        let mut chart = chart(&candles, &caches);
+        chart.paper = Some(PaperMarks {
+            account: &account,
+            symbol: "BTCUSDT",
+        });
+        let svg = render_svg(&chart, 800, 450);
+
+        assert!(svg.contains(">Buy LMT 2</text>"), "{}", svg);
+        // Dashes come out as one subpath per segment.
+        let stroke = format!("stroke=\"{}\"", LIGHT.bull.hex());
+        assert!(
+            svg.lines()
+                .filter(|line| line.contains(&stroke))
+                .any(|line| line.matches('M').count() > 10),
+            "{}",
+            svg
+        );
+    }
+
+    #[test]
+    fn format_follows_the_extension() {
+        assert_eq!(
+            ExportFormat::from_path(Path::new("btc.PNG")),
+            Ok(ExportFormat::Png)
+        );
+        assert_eq!(
+            ExportFormat::from_path(Path::new("reports/btc.svg")),
+            Ok(ExportFormat::Svg)
+        );
+        assert!(ExportFormat::from_path(Path::new("btc.jpg")).is_err());
+
+        for format in [ExportFormat::Png, ExportFormat::Svg] {
+            let path = format!("btc.{}", format.extension());
+            assert_eq!(ExportFormat::from_path(Path::new(&path)), Ok(format));
+        }
+    }
+}
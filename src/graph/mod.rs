@@ -1,2 +1,8 @@
-pub mod candle;
 pub mod axis;
+pub mod chart;
+pub mod depth;
+pub mod export;
+pub mod paper;
+pub mod portfolio;
+pub mod profile;
+pub mod sparkline;
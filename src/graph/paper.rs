@@ -0,0 +1,125 @@
+use crate::candle::Candle;
+use crate::colors::color;
+use crate::paper::{OrderSide, PaperAccount};
+use crate::theme::Palette;
+use crate::utils::PriceScale;
+use iced::alignment::{Horizontal, Vertical};
+use iced::widget::canvas::{Frame, LineDash, Path, Stroke, Text};
+use iced::{Color, Pixels, Point, Renderer};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::*;
+
+const MARKER_SIZE: f32 = 6.0;
+
+/// The paper account's orders and fills of one symbol, for the candle pane.
+#[derive(Debug, Clone, Copy)]
+pub struct PaperMarks<'a> {
+    pub account: &'a PaperAccount,
+    pub symbol: &'a str,
+}
+
+/// Open orders as dashed lines at the prices they wait for, fills as triangles on the
+/// candle they happened in, pointing up for buys and down for sells.
+pub struct PaperRenderer<'a> {
+    pub marks: PaperMarks<'a>,
+    /// The candles on screen, oldest first.
+    pub candles: Vec<&'a Candle>,
+    pub price_scale: PriceScale,
+    pub display_min: Decimal,
+    pub display_max: Decimal,
+    pub plot_width: f32,
+    pub unit_width: f32,
+    pub offset: f32,
+    pub palette: Palette,
+}
+
+impl PaperRenderer<'_> {
+    fn y(&self, price: Decimal) -> f32 {
+        self.price_scale.y(price.to_f64().unwrap_or_default()) + self.offset
+    }
+
+    fn side_color(&self, side: OrderSide) -> Color {
+        match side {
+            OrderSide::Buy => color(self.palette.bull),
+            OrderSide::Sell => color(self.palette.bear),
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame<Renderer>) {
+        let account = self.marks.account;
+
+        for order in account
+            .orders
+            .iter()
+            .filter(|o| o.symbol == self.marks.symbol)
+        {
+            let side_color = self.side_color(order.side);
+
+            for (tag, price) in order.levels() {
+                if price < self.display_min || price > self.display_max {
+                    continue;
+                }
+
+                let y = self.y(price);
+
+                frame.stroke(
+                    &Path::line(Point::new(0.0, y), Point::new(self.plot_width, y)),
+                    Stroke {
+                        line_dash: LineDash {
+                            segments: &[6.0, 4.0],
+                            offset: 0,
+                        },
+                        ..Stroke::default().with_color(side_color).with_width(1.0)
+                    },
+                );
+
+                frame.fill_text(Text {
+                    content: format!("{} {} {}", order.side, tag, order.quantity),
+                    position: Point::new(self.plot_width - 4.0, y - 2.0),
+                    size: Pixels(11.0),
+                    color: side_color,
+                    horizontal_alignment: Horizontal::Right,
+                    vertical_alignment: Vertical::Bottom,
+                    ..Text::default()
+                });
+            }
+        }
+
+        for fill in account
+            .fills
+            .iter()
+            .filter(|f| f.symbol == self.marks.symbol)
+        {
+            let Some(index) = self
+                .candles
+                .iter()
+                .position(|c| c.open_time <= fill.time && fill.time <= c.close_time)
+            else {
+                continue;
+            };
+
+            if fill.price < self.display_min || fill.price > self.display_max {
+                continue;
+            }
+
+            let x = (index as f32 + 0.5) * self.unit_width;
+            let y = self.y(fill.price);
+
+            // The tip touches the fill price, the body sits outside the candle's range.
+            let direction = match fill.side {
+                OrderSide::Buy => 1.0,
+                OrderSide::Sell => -1.0,
+            };
+            let base = y + direction * MARKER_SIZE * 1.5;
+
+            let triangle = Path::new(|builder| {
+                builder.move_to(Point::new(x, y));
+                builder.line_to(Point::new(x - MARKER_SIZE, base));
+                builder.line_to(Point::new(x + MARKER_SIZE, base));
+                builder.close();
+            });
+
+            frame.fill(&triangle, self.side_color(fill.side));
+        }
+    }
+}
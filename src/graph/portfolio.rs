@@ -0,0 +1,159 @@
+use crate::colors::color;
+use crate::portfolio::ValuePoint;
+use crate::theme::Palette;
+use chrono::{Local, TimeZone};
+use iced::alignment::Vertical;
+use iced::widget::canvas::{self, Frame, Geometry, Path, Stroke, Text};
+use iced::{Color, Pixels, Point, Rectangle, Renderer, Theme, mouse};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::*;
+
+const LABEL_HEIGHT: f32 = 16.0;
+const DAY_MILLIS: u64 = 24 * 60 * 60 * 1000;
+
+/// A value over time, like the portfolio's total or a backtest's equity, with its high,
+/// low and time span.
+pub struct ValueChart<'a> {
+    /// Oldest first, in two parts like `VecDeque::as_slices`.
+    pub points: (&'a [ValuePoint], &'a [ValuePoint]),
+    pub valuation: &'a str,
+    pub palette: Palette,
+}
+
+impl ValueChart<'_> {
+    fn points(&self) -> impl DoubleEndedIterator<Item = &ValuePoint> {
+        self.points.0.iter().chain(self.points.1)
+    }
+}
+
+/// Clock times within a day, dates for longer spans.
+fn time_label(millis: u64, span: u64) -> String {
+    let format = if span > DAY_MILLIS {
+        "%Y-%m-%d"
+    } else {
+        "%H:%M"
+    };
+
+    Local
+        .timestamp_millis_opt(millis as i64)
+        .single()
+        .map(|t| t.format(format).to_string())
+        .unwrap_or_default()
+}
+
+impl<Message> canvas::Program<Message> for ValueChart<'_> {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+        let text_color = color(self.palette.muted);
+
+        let (Some((first_time, first)), Some((last_time, last))) =
+            (self.points().next(), self.points().next_back())
+        else {
+            return vec![frame.into_geometry()];
+        };
+
+        let len = self.points.0.len() + self.points.1.len();
+        if len < 2 {
+            frame.fill_text(Text {
+                content: "Collecting values...".to_string(),
+                position: Point::new(0.0, 0.0),
+                size: Pixels(12.0),
+                color: text_color,
+                ..Text::default()
+            });
+            return vec![frame.into_geometry()];
+        }
+
+        let min = self.points().map(|(_, v)| *v).min().unwrap_or_default();
+        let max = self.points().map(|(_, v)| *v).max().unwrap_or_default();
+        let range = (max - min).to_f32().unwrap_or(0.0);
+        let height = (bounds.height - LABEL_HEIGHT * 2.0).max(1.0);
+        let step = bounds.width / (len - 1) as f32;
+
+        let point = |i: usize, value: Decimal| {
+            let normalized = if range > 0.0 {
+                (value - min).to_f32().unwrap_or(0.0) / range
+            } else {
+                0.5
+            };
+            Point::new(i as f32 * step, LABEL_HEIGHT + height - normalized * height)
+        };
+
+        let line = Path::new(|builder| {
+            for (i, (_, value)) in self.points().enumerate() {
+                if i == 0 {
+                    builder.move_to(point(i, *value));
+                } else {
+                    builder.line_to(point(i, *value));
+                }
+            }
+        });
+        let area = Path::new(|builder| {
+            builder.move_to(Point::new(0.0, LABEL_HEIGHT + height));
+            for (i, (_, value)) in self.points().enumerate() {
+                builder.line_to(point(i, *value));
+            }
+            builder.line_to(Point::new(bounds.width, LABEL_HEIGHT + height));
+            builder.close();
+        });
+
+        let line_color = if last >= first {
+            color(self.palette.bull)
+        } else {
+            color(self.palette.bear)
+        };
+
+        frame.fill(
+            &area,
+            Color {
+                a: 0.15,
+                ..line_color
+            },
+        );
+        frame.stroke(
+            &line,
+            Stroke::default().with_color(line_color).with_width(1.5),
+        );
+
+        let labels = [
+            (
+                format!("High {} {}", max.round_dp(2), self.valuation),
+                Point::new(0.0, 0.0),
+                Vertical::Top,
+            ),
+            (
+                format!(
+                    "Low {} {}  {} - {}",
+                    min.round_dp(2),
+                    self.valuation,
+                    time_label(*first_time, last_time - first_time),
+                    time_label(*last_time, last_time - first_time)
+                ),
+                Point::new(0.0, bounds.height),
+                Vertical::Bottom,
+            ),
+        ];
+
+        for (content, position, vertical_alignment) in labels {
+            frame.fill_text(Text {
+                content,
+                position,
+                size: Pixels(11.0),
+                color: text_color,
+                vertical_alignment,
+                ..Text::default()
+            });
+        }
+
+        vec![frame.into_geometry()]
+    }
+}
@@ -0,0 +1,106 @@
+use iced::widget::canvas::{Frame, Path, Stroke, Text};
+use crate::colors::color;
+use iced::{Color, Pixels, Point, Renderer, Size};
+use crate::profile::VolumeProfiles;
+use crate::theme::Palette;
+use crate::utils::price_to_y;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::*;
+
+/// Draws on the candle pane using the same scale the `YAxisRenderer` returned.
+pub struct VolumeProfileRenderer<'a> {
+    pub profiles: &'a VolumeProfiles,
+    pub plot_width: f32,
+    pub screen_height: f32,
+    pub display_min: Decimal,
+    pub display_max: Decimal,
+    pub offset: f32,
+    pub palette: Palette,
+}
+
+impl VolumeProfileRenderer<'_> {
+    fn y(&self, price: Decimal) -> f32 {
+        price_to_y(
+            price,
+            self.display_min,
+            self.display_max,
+            self.screen_height,
+        )
+        .to_f32()
+        .unwrap_or(0.0)
+            + self.offset
+    }
+
+    pub fn render(&self, frame: &mut Frame<Renderer>) {
+        let visible = &self.profiles.visible;
+        let max_volume = visible.max_volume();
+        let max_width = self.plot_width * 0.25;
+
+        if max_volume > Decimal::ZERO {
+            for (price, volume) in &visible.rows {
+                if *price < self.display_min || *price + visible.row_size > self.display_max {
+                    continue;
+                }
+
+                let top = self.y(*price + visible.row_size);
+                let bottom = self.y(*price);
+                let width = (volume / max_volume).to_f32().unwrap_or(0.0) * max_width;
+
+                frame.fill_rectangle(
+                    Point::new(self.plot_width - width, top),
+                    Size::new(width, (bottom - top - 1.0).max(1.0)),
+                    Color {
+                        a: 0.25,
+                        ..color(self.palette.primary)
+                    },
+                );
+            }
+        }
+
+        let session = &self.profiles.session;
+
+        if let Some((low, high)) = session.value_area() {
+            for (label, price) in [("VAH", high), ("VAL", low)] {
+                self.render_level(
+                    frame,
+                    label,
+                    price,
+                    Color {
+                        a: 0.5,
+                        ..color(self.palette.primary)
+                    },
+                );
+            }
+        }
+
+        if let Some(poc) = session.poc() {
+            self.render_level(
+                frame,
+                "VPOC",
+                poc + session.row_size / Decimal::TWO,
+                color(self.palette.highlight),
+            );
+        }
+    }
+
+    fn render_level(&self, frame: &mut Frame<Renderer>, label: &str, price: Decimal, color: Color) {
+        if price < self.display_min || price > self.display_max {
+            return;
+        }
+
+        let y = self.y(price);
+
+        frame.stroke(
+            &Path::line(Point::new(0.0, y), Point::new(self.plot_width, y)),
+            Stroke::default().with_color(color).with_width(1.0),
+        );
+
+        frame.fill_text(Text {
+            content: label.to_string(),
+            position: Point::new(4.0, y - 14.0),
+            size: Pixels(11.0),
+            color,
+            ..Text::default()
+        });
+    }
+}
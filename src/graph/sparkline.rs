@@ -0,0 +1,103 @@
+use crate::colors::color;
+use iced::futures::future::join_all;
+use iced::widget::canvas::{self, Frame, Geometry, Path, Stroke};
+use iced::{Rectangle, Renderer, Theme, mouse};
+use crate::candle::get_candles_with_limit;
+use crate::theme::Palette;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::*;
+use std::collections::HashMap;
+
+pub const SPARKLINE_TIMEFRAME: &str = "1h";
+pub const SPARKLINE_POINTS: u32 = 24;
+const SPARKLINE_BATCH_SIZE: usize = 5;
+
+/// Closing prices per symbol, kept apart from the main `CandleCache`.
+pub type SparklineCache = HashMap<String, Vec<Decimal>>;
+
+pub struct Sparkline<'a> {
+    pub closes: &'a [Decimal],
+    pub palette: Palette,
+}
+
+impl<Message> canvas::Program<Message> for Sparkline<'_> {
+    type State = ();
+
+    fn draw(
+        &self,
+        _state: &Self::State,
+        renderer: &Renderer,
+        _theme: &Theme,
+        bounds: Rectangle,
+        _cursor: mouse::Cursor,
+    ) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+
+        if self.closes.len() < 2 {
+            return vec![frame.into_geometry()];
+        }
+
+        let min = self.closes.iter().min().copied().unwrap_or_default();
+        let max = self.closes.iter().max().copied().unwrap_or_default();
+        let range = (max - min).to_f32().unwrap_or(0.0);
+        let step = bounds.width / (self.closes.len() - 1) as f32;
+
+        let path = Path::new(|builder| {
+            for (i, close) in self.closes.iter().enumerate() {
+                let normalized = if range > 0.0 {
+                    (*close - min).to_f32().unwrap_or(0.0) / range
+                } else {
+                    0.5
+                };
+                let point = iced::Point {
+                    x: i as f32 * step,
+                    y: bounds.height - normalized * bounds.height,
+                };
+
+                if i == 0 {
+                    builder.move_to(point);
+                } else {
+                    builder.line_to(point);
+                }
+            }
+        });
+
+        let color = if self.closes.last() >= self.closes.first() {
+            color(self.palette.bull)
+        } else {
+            color(self.palette.bear)
+        };
+
+        frame.stroke(&path, Stroke::default().with_color(color).with_width(1.0));
+
+        vec![frame.into_geometry()]
+    }
+}
+
+/// Fetches sparkline closes a few symbols at a time so a long list does not burst the API.
+pub async fn get_sparklines(symbols: Vec<(String, u32)>) -> Vec<(String, Vec<Decimal>)> {
+    let mut sparklines = Vec::with_capacity(symbols.len());
+
+    for batch in symbols.chunks(SPARKLINE_BATCH_SIZE) {
+        let requests = batch.iter().map(|(symbol, decimals)| async move {
+            let candles = get_candles_with_limit(
+                symbol,
+                SPARKLINE_TIMEFRAME,
+                *decimals,
+                SPARKLINE_POINTS,
+            )
+            .await;
+
+            (symbol.clone(), candles)
+        });
+
+        for (symbol, candles) in join_all(requests).await {
+            match candles {
+                Ok(candles) => sparklines.push((symbol, candles.iter().map(|c| c.close).collect())),
+                Err(err) => println!("Sparkline error for {}: {}", symbol, err),
+            }
+        }
+    }
+
+    sparklines
+}
@@ -0,0 +1,243 @@
+use serde::de::{self, Deserializer, Visitor};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Keys that are written by name, everything else is a single character.
+pub const NAMED_KEYS: [&str; 14] = [
+    "up",
+    "down",
+    "left",
+    "right",
+    "delete",
+    "backspace",
+    "enter",
+    "escape",
+    "tab",
+    "space",
+    "home",
+    "end",
+    "pageup",
+    "pagedown",
+];
+
+/// A key and its modifiers, written like `ctrl+k`, `shift+up` or `/`. `ctrl` is the
+/// command key on macOS. An empty chord binds nothing.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+pub struct Chord {
+    pub ctrl: bool,
+    pub alt: bool,
+    pub shift: bool,
+    /// A lowercase character or one of `NAMED_KEYS`.
+    pub key: String,
+}
+
+impl Chord {
+    pub fn key(key: &str) -> Self {
+        Self {
+            key: key.to_string(),
+            ..Self::default()
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim().to_lowercase();
+
+        if text.is_empty() {
+            return Ok(Self::default());
+        }
+
+        // `+` is a key too, so `ctrl++` and a lone `+` split differently.
+        let (modifiers, key) = match text.strip_suffix("++") {
+            Some(modifiers) => (modifiers, "+"),
+            None => match text.rsplit_once('+') {
+                Some((modifiers, key)) if !key.is_empty() => (modifiers, key),
+                _ => ("", text.as_str()),
+            },
+        };
+
+        let mut chord = Self::key(key);
+
+        for modifier in modifiers.split('+').filter(|m| !m.is_empty()) {
+            match modifier {
+                "ctrl" | "cmd" => chord.ctrl = true,
+                "alt" => chord.alt = true,
+                "shift" => chord.shift = true,
+                other => {
+                    return Err(format!(
+                        "unknown modifier {:?} in {:?}, expected ctrl, alt or shift",
+                        other, text
+                    ));
+                }
+            }
+        }
+
+        if key.chars().count() == 1 {
+            if chord.shift {
+                return Err(format!(
+                    "{:?}: write the shifted character instead of shift+{}",
+                    text, key
+                ));
+            }
+        } else if !NAMED_KEYS.contains(&key) {
+            return Err(format!(
+                "unknown key {:?}, expected a single character or one of {}",
+                key,
+                NAMED_KEYS.join(", ")
+            ));
+        }
+
+        Ok(chord)
+    }
+
+    pub fn is_bound(&self) -> bool {
+        !self.key.is_empty()
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (held, name) in [
+            (self.ctrl, "ctrl+"),
+            (self.alt, "alt+"),
+            (self.shift, "shift+"),
+        ] {
+            if held {
+                f.write_str(name)?;
+            }
+        }
+
+        f.write_str(&self.key)
+    }
+}
+
+impl Serialize for Chord {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+/// Takes numbers as well, so `--set keys.timeframe_1h=3` does not need quotes.
+impl<'de> Deserialize<'de> for Chord {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct ChordVisitor;
+
+        impl Visitor<'_> for ChordVisitor {
+            type Value = Chord;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a key like \"ctrl+k\"")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Chord, E> {
+                Chord::parse(value).map_err(E::custom)
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Chord, E> {
+                Chord::parse(&value.to_string()).map_err(E::custom)
+            }
+        }
+
+        deserializer.deserialize_any(ChordVisitor)
+    }
+}
+
+/// What a shortcut or a command palette entry does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Timeframe(&'static str),
+    PreviousSymbol,
+    NextSymbol,
+    RemoveSymbol,
+    FocusSearch,
+    ZoomIn,
+    ZoomOut,
+    CommandPalette,
+}
+
+impl Action {
+    pub fn label(&self) -> String {
+        match self {
+            Action::Timeframe(timeframe) => format!("Timeframe {}", timeframe),
+            Action::PreviousSymbol => "Previous symbol".to_string(),
+            Action::NextSymbol => "Next symbol".to_string(),
+            Action::RemoveSymbol => "Remove symbol".to_string(),
+            Action::FocusSearch => "Search instruments".to_string(),
+            Action::ZoomIn => "Zoom in".to_string(),
+            Action::ZoomOut => "Zoom out".to_string(),
+            Action::CommandPalette => "Command palette".to_string(),
+        }
+    }
+}
+
+/// Chords to actions, the first binding of a chord wins.
+#[derive(Debug, Clone, Default)]
+pub struct Keymap {
+    bindings: Vec<(Chord, Action)>,
+}
+
+impl Keymap {
+    pub fn new(bindings: impl IntoIterator<Item = (Chord, Action)>) -> Self {
+        Self {
+            bindings: bindings
+                .into_iter()
+                .filter(|(chord, _)| chord.is_bound())
+                .collect(),
+        }
+    }
+
+    pub fn action(&self, chord: &Chord) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(bound, _)| bound == chord)
+            .map(|(_, action)| *action)
+    }
+
+    /// Shown next to the action in the command palette.
+    pub fn chord(&self, action: Action) -> Option<&Chord> {
+        self.bindings
+            .iter()
+            .find(|(_, bound)| *bound == action)
+            .map(|(chord, _)| chord)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chords_parse_and_print_back() {
+        for text in ["ctrl+k", "/", "+", "ctrl++", "shift+up", "alt+1", ""] {
+            assert_eq!(Chord::parse(text).unwrap().to_string(), text);
+        }
+
+        let chord = Chord::parse("Ctrl+Shift+Delete").unwrap();
+        assert!(chord.ctrl && chord.shift && !chord.alt);
+        assert_eq!(chord.key, "delete");
+
+        let err = Chord::parse("hyper+k").unwrap_err();
+        assert!(err.starts_with("unknown modifier \"hyper\""), "{}", err);
+        let err = Chord::parse("ctrl+pgup").unwrap_err();
+        assert!(err.starts_with("unknown key \"pgup\""), "{}", err);
+        let err = Chord::parse("shift+=").unwrap_err();
+        assert!(err.contains("write the shifted character"), "{}", err);
+    }
+
+    #[test]
+    fn first_binding_of_a_chord_wins() {
+        let keymap = Keymap::new([
+            (Chord::key("1"), Action::Timeframe("1m")),
+            (Chord::default(), Action::ZoomIn),
+            (Chord::key("1"), Action::ZoomOut),
+        ]);
+
+        assert_eq!(
+            keymap.action(&Chord::key("1")),
+            Some(Action::Timeframe("1m"))
+        );
+        assert_eq!(keymap.action(&Chord::default()), None);
+        assert_eq!(keymap.chord(Action::ZoomIn), None);
+        assert_eq!(keymap.chord(Action::ZoomOut), Some(&Chord::key("1")));
+    }
+}
@@ -0,0 +1,26 @@
+//! Market data types, Binance fetchers and the chart math behind Juicy Lemon. Only
+//! `graph` and `colors` draw with iced, behind the `gui` feature, so the charts render
+//! the same in the `juicy-lemon-iced` window and in exported files.
+
+pub mod backtest;
+pub mod cache;
+#[cfg(feature = "gui")]
+pub mod colors;
+pub mod config;
+pub mod dataset;
+#[cfg(test)]
+mod fixtures;
+#[cfg(feature = "gui")]
+pub mod graph;
+pub mod keys;
+pub mod candle;
+pub mod orderbook;
+pub mod paper;
+pub mod portfolio;
+pub mod profile;
+pub mod store;
+pub mod symbols;
+pub mod theme;
+pub mod trades;
+pub mod utils;
+pub mod watchlist;
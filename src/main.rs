@@ -1,27 +1,51 @@
-mod graph;
-mod symbols;
 mod ui;
 mod update;
-mod utils;
 mod view;
-mod colors;
+mod shortcuts;
+mod websocket;
 
-use crate::graph::axis::{XAxisRenderer, YAxisRenderer};
-use crate::symbols::{Symbol, SymbolWithPrice};
+use crate::shortcuts::{CommandPalette, key_event};
+use crate::ui::chart_export::{CandleRange, size_label, size_options};
+use crate::ui::paper_panel::PaperOrderType;
 use crate::update::update;
 use crate::view::view;
-use graph::candle::Candle;
+use crate::websocket::depth::depth_updates;
+use crate::websocket::trades::trade_updates;
+use juicy_lemon::backtest::BacktestReport;
+use juicy_lemon::cache::CandleCache;
+use juicy_lemon::candle::{Candle, TIMEFRAMES};
+use juicy_lemon::colors::color;
+use juicy_lemon::config::{self, Config};
+use juicy_lemon::dataset::DataFormat;
+use juicy_lemon::graph::chart::{CandleChart, ChartCaches};
+use juicy_lemon::graph::export::ExportFormat;
+use juicy_lemon::graph::paper::PaperMarks;
+use juicy_lemon::graph::sparkline::SparklineCache;
+use juicy_lemon::keys::{Chord, Keymap};
+use juicy_lemon::orderbook::source::DepthSource;
+use juicy_lemon::orderbook::{DepthSnapshot, DepthUpdate, OrderBookSync, grouping_label, tick_size};
+use juicy_lemon::paper::{OrderSide, PaperAccount};
+use juicy_lemon::portfolio::{Portfolio, Rates, ValueHistory, pricing_pairs};
+use juicy_lemon::profile::VolumeProfiles;
+use juicy_lemon::store::{Appended, CandleStore};
+use juicy_lemon::symbols::{Symbol, SymbolWithPrice};
+use juicy_lemon::theme::{Palette, load_palettes};
+use juicy_lemon::trades::{AggTrade, TradeFeed};
+use juicy_lemon::utils::QUOTE_FILTERS;
+use juicy_lemon::watchlist::Watchlist;
+use juicy_lemon::watchlist::io::WatchlistFile;
+use juicy_lemon::watchlist::tick::{FLASH_FRAME, PriceTick, is_flashing};
 use iced::Theme;
-use iced::theme::{Custom, Palette};
+use iced::theme::Custom;
 use iced::time::{self};
-use iced::widget::canvas::{Cache, Geometry, Path, Stroke};
+use iced::widget::canvas::Geometry;
 use iced::widget::{canvas, combo_box};
-use iced::{Color, Point, Rectangle, Renderer, Size, Subscription, Task, mouse};
-use std::collections::{HashMap, VecDeque};
-use std::sync::Arc;
-use std::time::Duration;
+use iced::{Rectangle, Renderer, Subscription, Task, event, mouse};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use rust_decimal::Decimal;
-use rust_decimal::prelude::*;
 
 #[derive(Debug, Clone)]
 enum Message {
@@ -33,16 +57,76 @@ enum Message {
     SelectSymbol(String),
     FetchError(String),
     PricesUpdated(Vec<SymbolWithPrice>),
-    CandlesFetched(Vec<Candle>, String),
+    CandlesFetched(Vec<Candle>, String, String),
+    CandlesLoaded(Vec<Candle>, String, String),
+    CandlesStored(Result<Appended, String>),
     FilterInput(String),
     UpdateSelectOptions,
     InitApp,
-}
-
-fn price_to_y(price: Decimal, min_price: Decimal, max_price: Decimal, height: f32) -> Decimal {
-    let normalized = (price - min_price) / (max_price - min_price);
-    let height_decimal = Decimal::from_f32(height).unwrap();
-    height_decimal - normalized * height_decimal
+    SwitchWatchlist(String),
+    WatchlistNameInput(String),
+    CreateWatchlist,
+    RenameWatchlist,
+    DuplicateWatchlist,
+    DeleteWatchlist,
+    WatchlistPathInput(String),
+    ImportWatchlist,
+    ExportWatchlist,
+    WatchlistImported(Result<WatchlistFile, String>),
+    /// The watchlist's name and the path it was written to.
+    WatchlistExported(String, Result<String, String>),
+    RefetchSparklines,
+    SparklinesFetched(Vec<(String, Vec<Decimal>)>),
+    AnimationTick(Instant),
+    ToggleTickArrows(bool),
+    SelectQuoteFilter(String),
+    DepthUpdated(DepthUpdate),
+    DepthSnapshotFetched(String, Result<DepthSnapshot, String>),
+    DepthDisconnected(String),
+    SelectDepthGrouping(String),
+    ToggleDepthChart(bool),
+    TradeReceived(String, AggTrade),
+    TradesFetched(String, Result<Vec<AggTrade>, String>),
+    LargeTradeInput(String),
+    ToggleVolumeProfile(bool),
+    ToggleCacheDebug(bool),
+    RefetchVolumeProfile,
+    VolumeProfileFetched(Result<VolumeProfiles, String>),
+    SelectTheme(String),
+    KeyPressed(Chord),
+    FocusSearch,
+    ZoomIn,
+    ZoomOut,
+    OpenCommandPalette,
+    CloseCommandPalette,
+    CommandInput(String),
+    RunCommand(usize),
+    SelectExportSize(String),
+    ExportChart(ExportFormat),
+    ChartExported(Result<PathBuf, String>),
+    ExportCandles(DataFormat, CandleRange),
+    TogglePaperTrading(bool),
+    PaperSide(OrderSide),
+    PaperOrderType(PaperOrderType),
+    PaperQuantityInput(String),
+    PaperPriceInput(String),
+    PaperStopInput(String),
+    PlacePaperOrder,
+    CancelPaperOrder(u64),
+    TogglePortfolio(bool),
+    PortfolioAssetInput(String),
+    PortfolioQuantityInput(String),
+    PortfolioCostInput(String),
+    SetHolding,
+    RemoveHolding(String),
+    PortfolioPathInput(String),
+    ImportTrades,
+    TradeHistoryRead(Result<String, String>),
+    PortfolioSaved(Result<(), String>),
+    ToggleBacktest(bool),
+    BacktestStrategyInput(String),
+    RunBacktest,
+    BacktestFinished(String, Result<BacktestReport, String>),
 }
 
 impl<Message> canvas::Program<Message> for State {
@@ -52,174 +136,431 @@ impl<Message> canvas::Program<Message> for State {
         &self,
         _state: &Self::State,
         renderer: &Renderer,
-        theme: &Theme,
+        _theme: &Theme,
         bounds: Rectangle,
-        _cursor: mouse::Cursor,
+        cursor: mouse::Cursor,
     ) -> Vec<Geometry> {
-        let rectangle = self.graph.draw(renderer, bounds.size(), |frame| {
-            let mut current_candles = VecDeque::new();
-            if let (Some(symbol), Some(timeframe)) =
-                (&self.displayed_symbol, &self.selected_timeframe)
-            {
-                if let Some(symbol_map) = self.candles.get(symbol.symbol.as_str()) {
-                    if let Some(candles) = symbol_map.get(timeframe) {
-                        current_candles = candles.clone();
-                    }
-                }
-            }
-
-            if current_candles.is_empty() {
-                return;
-            }
-
-            let display_max = current_candles
-                .iter()
-                .fold(Decimal::MIN, |acc, c| acc.max(c.high.max(c.low)));
-            let display_min = current_candles
-                .iter()
-                .fold(Decimal::MAX, |acc, c| acc.min(c.high.min(c.low)));
-
-            let offset = 30.0;
-
-            let screen_height = bounds.height - 140.0;
-            let screen_width = bounds.width;
-            
-            let y_axis = YAxisRenderer {
-                screen_width,
-                screen_height,
-                display_min,
-                display_max,
-                offset,
-                decimal_places: self.displayed_symbol.as_ref().unwrap().decimals,
-                text_color: theme.palette().text,
-            };
-
-            let (display_min, display_max, axis_y_width) = y_axis.render_axis(frame);
-            let timeframe = self.selected_timeframe.as_ref().unwrap();
-            
-            let x_axis = XAxisRenderer {
-                screen_width: screen_width - axis_y_width,
-                screen_height,
-                start_time: current_candles.get(0).unwrap().open_time,
-                end_time: current_candles.get(current_candles.len() - 1).unwrap().close_time,
-                timeframe: timeframe.to_string(),
-            };
-
-            x_axis.render_axis(frame);
-
-            let unit_width = (screen_width - axis_y_width - 10.0) / current_candles.len().max(1) as f32;
-            let candle_width = unit_width * 0.9;
-            let candle_spacing = unit_width * 0.1;
-
-            for (i, candle) in current_candles.iter().enumerate() {
-                let open_y = price_to_y(candle.open, display_min, display_max, screen_height).to_f32().unwrap();
-                let close_y = price_to_y(candle.close, display_min, display_max, screen_height).to_f32().unwrap();
-                let low_y = price_to_y(candle.low, display_min, display_max, screen_height).to_f32().unwrap();
-                let high_y = price_to_y(candle.high, display_min, display_max, screen_height).to_f32().unwrap();
-                let height = (open_y - close_y).abs().max(1.0);
-
-                let x_position = i as f32 * unit_width;
-                let candle_center_x = x_position + (candle_spacing / 2.0) + (candle_width / 2.0);
-
-                let wick = Path::line(
-                    Point {
-                        x: candle_center_x,
-                        y: high_y + offset,
-                    },
-                    Point {
-                        x: candle_center_x,
-                        y: low_y + offset,
-                    },
-                );
-
-                let rectangle = Path::rectangle(
-                    Point {
-                        x: x_position + (candle_spacing / 2.0),
-                        y: open_y.min(close_y) + offset,
-                    },
-                    Size {
-                        width: candle_width,
-                        height,
-                    },
-                );
-
-                frame.fill(&rectangle, candle.get_color());
-                frame.stroke(&wick, Stroke::default().with_color(candle.get_color()));
-            }
-        });
-
-        vec![rectangle]
+        match self.chart() {
+            Some(chart) => chart.draw(renderer, bounds, cursor),
+            None => Vec::new(),
+        }
     }
 }
 
-type Timeframe = String;
-type CandleCache = HashMap<String, HashMap<Timeframe, VecDeque<Candle>>>;
-
 struct State {
     instruments: Vec<Symbol>,
-    watchlist: Vec<Symbol>,
+    watchlists: Vec<Watchlist>,
+    active_watchlist: usize,
+    watchlist_name_input: String,
+    watchlist_path_input: String,
     loading: bool,
     input_text: String,
     error_message: String,
+    watchlist_select_state: combo_box::State<String>,
     symbol_select_state: combo_box::State<String>,
+    quote_select_state: combo_box::State<String>,
+    quote_filter_label: String,
     timeframe_select_state: combo_box::State<String>,
     selected_timeframe: Option<String>,
     selected_symbol: Option<String>,
     displayed_symbol: Option<Symbol>,
     candles: CandleCache,
-    graph: Cache,
+    candle_store: CandleStore,
+    show_cache_debug: bool,
+    sparklines: SparklineCache,
+    price_ticks: HashMap<String, PriceTick>,
+    show_tick_arrows: bool,
+    now: Instant,
+    order_book: Option<OrderBookSync>,
+    depth_source: DepthSource,
+    depth_grouping: u32,
+    depth_grouping_label: String,
+    depth_grouping_state: combo_box::State<String>,
+    show_depth_chart: bool,
+    trade_feed: Option<TradeFeed>,
+    large_trade_input: String,
+    large_trade_notional: Decimal,
+    show_volume_profile: bool,
+    volume_profiles: Option<VolumeProfiles>,
+    volume_profile_loading: bool,
+    chart_caches: ChartCaches,
+    /// Width and height of exported images, picked next to the export buttons.
+    export_size: (u32, u32),
+    export_size_label: String,
+    export_size_state: combo_box::State<String>,
+    /// Newest candles on screen, `+` and `-` change it.
+    visible_candles: usize,
+    keymap: Keymap,
+    command_palette: Option<CommandPalette>,
+    palettes: Vec<(String, Palette)>,
+    active_palette: usize,
+    theme_select_state: combo_box::State<String>,
+    paper: PaperAccount,
+    show_paper_trading: bool,
+    paper_side: OrderSide,
+    paper_order_type: PaperOrderType,
+    paper_quantity_input: String,
+    /// The limit, or the stop of a plain stop order.
+    paper_price_input: String,
+    paper_stop_input: String,
+    portfolio: Portfolio,
+    /// Bumped on every change, saves write in the background and may finish out of order.
+    portfolio_revision: u64,
+    /// The revision last written to the portfolio file.
+    portfolio_saved: Arc<Mutex<u64>>,
+    show_portfolio: bool,
+    /// Last price of every symbol fetched, watchlist or not, for cross rates.
+    portfolio_prices: HashMap<String, Decimal>,
+    portfolio_history: ValueHistory,
+    portfolio_asset_input: String,
+    portfolio_quantity_input: String,
+    portfolio_cost_input: String,
+    portfolio_path_input: String,
+    show_backtest: bool,
+    backtest_strategy_input: String,
+    /// The latest report and the timeframe it ran on, marked on that series' chart.
+    backtest: Option<(String, BacktestReport)>,
+    /// Counts runs, so the chart redraws the marks of a new report.
+    backtest_run: u64,
+}
+
+impl State {
+    fn palette(&self) -> Palette {
+        self.palettes[self.active_palette].1
+    }
+
+    fn palette_name(&self) -> &String {
+        &self.palettes[self.active_palette].0
+    }
+
+    fn watchlist(&self) -> &Watchlist {
+        &self.watchlists[self.active_watchlist]
+    }
+
+    fn watchlist_mut(&mut self) -> &mut Watchlist {
+        &mut self.watchlists[self.active_watchlist]
+    }
+
+    /// `None` when the "All" entry is selected.
+    fn quote_filter(&self) -> Option<&str> {
+        if self.quote_filter_label == QUOTE_FILTERS[0] {
+            None
+        } else {
+            Some(self.quote_filter_label.as_str())
+        }
+    }
+
+    /// The (symbol, timeframe) series the candle pane is drawing.
+    fn visible_series(&self) -> Option<(&str, &str)> {
+        let symbol = self.displayed_symbol.as_ref()?;
+        let timeframe = self.selected_timeframe.as_ref()?;
+        Some((symbol.symbol.as_str(), timeframe.as_str()))
+    }
+
+    fn watchlist_names(&self) -> Vec<String> {
+        self.watchlists.iter().map(|w| w.name.clone()).collect()
+    }
+
+    /// Only the book of the displayed symbol, a switch leaves the old one behind briefly.
+    fn displayed_order_book(&self) -> Option<&OrderBookSync> {
+        let symbol = self.displayed_symbol.as_ref()?;
+        self.order_book
+            .as_ref()
+            .filter(|sync| sync.symbol == symbol.symbol)
+    }
+
+    fn displayed_trade_feed(&self) -> Option<&TradeFeed> {
+        let symbol = self.displayed_symbol.as_ref()?;
+        self.trade_feed
+            .as_ref()
+            .filter(|feed| feed.symbol == symbol.symbol)
+    }
+
+    /// Profiles only count while they match what the candle pane shows.
+    fn displayed_volume_profiles(&self) -> Option<&VolumeProfiles> {
+        let symbol = self.displayed_symbol.as_ref()?;
+        let timeframe = self.selected_timeframe.as_ref()?;
+
+        self.volume_profiles
+            .as_ref()
+            .filter(|p| p.symbol == symbol.symbol && &p.timeframe == timeframe)
+    }
+
+    /// The backtest drawn on the candle pane, when shown and run on what it shows.
+    fn displayed_backtest(&self) -> Option<&BacktestReport> {
+        let (symbol, timeframe) = self.visible_series()?;
+
+        self.backtest
+            .as_ref()
+            .filter(|(tf, report)| self.show_backtest && tf == timeframe && report.symbol == symbol)
+            .map(|(_, report)| report)
+    }
+
+    /// The latest trade of the displayed symbol, otherwise the watchlist's ticker price.
+    fn last_price(&self, symbol: &str) -> Option<Decimal> {
+        self.displayed_trade_feed()
+            .filter(|feed| feed.symbol == symbol)
+            .and_then(|feed| feed.latest().next())
+            .map(|trade| trade.price)
+            .or_else(|| {
+                self.watchlist()
+                    .symbols
+                    .iter()
+                    .find(|s| s.symbol == symbol)
+                    .and_then(|s| s.price)
+            })
+    }
+
+    fn rates(&self) -> Rates {
+        Rates::new(&self.instruments, &self.portfolio_prices)
+    }
+
+    /// Symbols to fetch so every holding has a price in the valuation asset.
+    fn portfolio_pairs(&self) -> Vec<String> {
+        let valuation = &config::get().portfolio.valuation;
+        let mut pairs = Vec::new();
+
+        for holding in &self.portfolio.holdings {
+            for pair in pricing_pairs(&self.instruments, &holding.asset, valuation) {
+                if !pairs.contains(&pair) {
+                    pairs.push(pair);
+                }
+            }
+        }
+
+        pairs
+    }
+
+    /// The candle pane as drawn, for the window and for exports.
+    fn chart(&self) -> Option<CandleChart<'_>> {
+        let (symbol, timeframe) = self.visible_series()?;
+
+        Some(CandleChart {
+            candles: self.candles.get(symbol, timeframe)?,
+            plot: self.candles.plot(symbol, timeframe),
+            visible: self.visible_candles,
+            decimals: self.displayed_symbol.as_ref()?.decimals,
+            timeframe,
+            profiles: self
+                .displayed_volume_profiles()
+                .filter(|_| self.show_volume_profile),
+            paper: self.show_paper_trading.then_some(PaperMarks {
+                account: &self.paper,
+                symbol,
+            }),
+            backtest: self.displayed_backtest().map(|report| {
+                (
+                    self.backtest_run,
+                    PaperMarks {
+                        account: &report.account,
+                        symbol,
+                    },
+                )
+            }),
+            caches: &self.chart_caches,
+            palette: self.palette(),
+        })
+    }
+
+    fn depth_increment(&self) -> Decimal {
+        let decimals = self.displayed_symbol.as_ref().map(|s| s.decimals).unwrap_or(8);
+        tick_size(decimals) * Decimal::from(self.depth_grouping)
+    }
 }
 
-fn theme(_: &State) -> Theme {
+fn theme(state: &State) -> Theme {
+    let palette = state.palette();
     let custom_theme = Arc::new(Custom::new(
-        "My Dark Theme".into(),
-        Palette {
-            background: [0.012, 0.027, 0.071].into(),
-            text: [0.976, 0.980, 0.984].into(),
-            primary: Color::from_rgb(0.3, 0.6, 0.9),
-            success: Color::from_rgb(0.2, 0.8, 0.4),
-            danger: Color::from_rgb(0.9, 0.2, 0.2),
+        state.palette_name().clone(),
+        iced::theme::Palette {
+            background: color(palette.background),
+            text: color(palette.text),
+            primary: color(palette.primary),
+            success: color(palette.bull),
+            danger: color(palette.bear),
         },
     ));
 
     Theme::Custom(custom_theme)
 }
 
-fn init() -> (State, Task<Message>) {
+fn init(palettes: Vec<(String, Palette)>, active_palette: usize) -> (State, Task<Message>) {
+    let config = config::get();
+    let watchlists = vec![Watchlist::new(
+        config.defaults.watchlist.clone(),
+        config.defaults.timeframe.clone(),
+    )];
+
+    let (portfolio, portfolio_error) = match Portfolio::load(&config.portfolio.file) {
+        Ok(portfolio) => (portfolio, String::new()),
+        Err(err) => (Portfolio::default(), err),
+    };
+
     let state = State {
         instruments: Vec::new(),
-        watchlist: Vec::new(),
-        error_message: "".to_string(),
+        watchlist_select_state: combo_box::State::new(
+            watchlists.iter().map(|w| w.name.clone()).collect(),
+        ),
+        watchlists,
+        active_watchlist: 0,
+        watchlist_name_input: "".to_string(),
+        watchlist_path_input: "".to_string(),
+        error_message: portfolio_error,
         input_text: "".to_string(),
         loading: true,
-        selected_timeframe: Some("1m".to_string()),
-        timeframe_select_state: combo_box::State::new(vec![
-            "1m".to_string(),
-            "5m".to_string(),
-            "1h".to_string(),
-            "4h".to_string(),
-            "1d".to_string(),
-        ]),
+        selected_timeframe: Some(config.defaults.timeframe.clone()),
+        timeframe_select_state: combo_box::State::new(
+            TIMEFRAMES.iter().map(|t| t.to_string()).collect(),
+        ),
         selected_symbol: None,
         displayed_symbol: None,
         symbol_select_state: combo_box::State::default(),
-        candles: HashMap::new(),
-        graph: Cache::new(),
+        quote_select_state: combo_box::State::new(
+            QUOTE_FILTERS.iter().map(|q| q.to_string()).collect(),
+        ),
+        quote_filter_label: QUOTE_FILTERS[0].to_string(),
+        candles: CandleCache::from_config(),
+        candle_store: CandleStore::from_config(),
+        show_cache_debug: false,
+        sparklines: HashMap::new(),
+        price_ticks: HashMap::new(),
+        show_tick_arrows: config.ui.show_tick_arrows,
+        now: Instant::now(),
+        order_book: None,
+        depth_source: DepthSource::from_env(),
+        depth_grouping: 1,
+        depth_grouping_label: grouping_label(8, 1),
+        depth_grouping_state: combo_box::State::default(),
+        show_depth_chart: config.ui.show_depth_chart,
+        trade_feed: None,
+        large_trade_input: config.ui.large_trade_notional.to_string(),
+        large_trade_notional: config.ui.large_trade_notional,
+        show_volume_profile: config.ui.show_volume_profile,
+        volume_profiles: None,
+        volume_profile_loading: false,
+        chart_caches: ChartCaches::default(),
+        export_size: (config.export.width, config.export.height),
+        export_size_label: size_label((config.export.width, config.export.height)),
+        export_size_state: combo_box::State::new(size_options()),
+        visible_candles: config.history.candles,
+        keymap: config.keys.keymap(),
+        command_palette: None,
+        theme_select_state: combo_box::State::new(
+            palettes.iter().map(|(name, _)| name.clone()).collect(),
+        ),
+        palettes,
+        active_palette,
+        paper: PaperAccount::new(
+            config.paper.balances.clone(),
+            config.paper.fee_rate,
+            config.paper.slippage,
+        ),
+        show_paper_trading: config.ui.show_paper_trading,
+        paper_side: OrderSide::Buy,
+        paper_order_type: PaperOrderType::Market,
+        paper_quantity_input: "".to_string(),
+        paper_price_input: "".to_string(),
+        paper_stop_input: "".to_string(),
+        portfolio,
+        portfolio_revision: 0,
+        portfolio_saved: Arc::new(Mutex::new(0)),
+        show_portfolio: config.ui.show_portfolio,
+        portfolio_prices: HashMap::new(),
+        portfolio_history: ValueHistory::new(config.portfolio.history_points),
+        portfolio_asset_input: "".to_string(),
+        portfolio_quantity_input: "".to_string(),
+        portfolio_cost_input: "".to_string(),
+        portfolio_path_input: "".to_string(),
+        show_backtest: config.ui.show_backtest,
+        backtest_strategy_input: config.backtest.strategy.clone(),
+        backtest: None,
+        backtest_run: 0,
     };
     (state, Task::perform(async {}, |_| Message::InitApp))
 }
 
 fn subscription(state: &State) -> Subscription<Message> {
     if !state.instruments.is_empty() {
-        return time::every(Duration::from_secs(1)).map(|_| Message::RefetchData);
+        let refresh = &config::get().refresh;
+        let mut subscriptions = vec![
+            event::listen_with(key_event),
+            time::every(Duration::from_secs(refresh.prices_secs)).map(|_| Message::RefetchData),
+            time::every(Duration::from_secs(refresh.sparklines_secs))
+                .map(|_| Message::RefetchSparklines),
+        ];
+
+        if let Some(symbol) = &state.displayed_symbol {
+            let source = state.depth_source.clone();
+            let symbol = symbol.symbol.clone();
+
+            subscriptions.push(Subscription::run_with_id(
+                (source.clone(), symbol.clone()),
+                depth_updates(source, symbol.clone()),
+            ));
+            subscriptions.push(Subscription::run_with_id(
+                ("aggTrade", symbol.clone()),
+                trade_updates(symbol),
+            ));
+        }
+
+        if state.show_volume_profile {
+            subscriptions.push(
+                time::every(Duration::from_secs(refresh.volume_profile_secs))
+                    .map(|_| Message::RefetchVolumeProfile),
+            );
+        }
+
+        if is_flashing(&state.price_ticks, state.now) {
+            subscriptions.push(time::every(FLASH_FRAME).map(Message::AnimationTick));
+        }
+
+        return Subscription::batch(subscriptions);
     }
 
     Subscription::none()
 }
 
 fn main() -> iced::Result {
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+
+    match Config::from_args(&mut args) {
+        Ok(_) if !args.is_empty() => {
+            eprintln!(
+                "Unknown argument {}\n\nUsage: juicy-lemon-iced [--config FILE] [--set section.key=value]...",
+                args[0]
+            );
+            std::process::exit(2);
+        }
+        Ok(config) => config::init(config),
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        }
+    }
+
+    let ui = &config::get().ui;
+    let palettes = match load_palettes(&ui.palettes_dir) {
+        Ok(palettes) => palettes,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        }
+    };
+    let Some(active_palette) = palettes.iter().position(|(name, _)| *name == ui.theme) else {
+        eprintln!(
+            "Unknown theme {}, available: {}",
+            ui.theme,
+            palettes
+                .iter()
+                .map(|(name, _)| name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        std::process::exit(2);
+    };
+
     iced::application("Juicy Lemon", update, view)
         .theme(theme)
         .subscription(subscription)
-        .run_with(init)
+        .run_with(move || init(palettes, active_palette))
 }
@@ -1,3 +0,0 @@
-pub mod symbols;
-pub mod view;
-pub mod update;
@@ -0,0 +1,360 @@
+pub mod source;
+
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::{BTreeMap, VecDeque};
+
+pub const DEPTH_ROWS: usize = 12;
+pub const DEPTH_GROUPINGS: [u32; 4] = [1, 10, 100, 1000];
+
+/// Diff events kept while no snapshot arrives. Older ones are dropped, any snapshot
+/// fetched later is newer than them anyway.
+pub const MAX_PENDING_UPDATES: usize = 1000;
+
+/// REST `/api/v3/depth` response.
+#[derive(Deserialize, Debug, Clone)]
+pub struct DepthSnapshot {
+    #[serde(rename = "lastUpdateId")]
+    pub last_update_id: u64,
+    pub bids: Vec<(Decimal, Decimal)>,
+    pub asks: Vec<(Decimal, Decimal)>,
+}
+
+/// `<symbol>@depth` diff event, a zero quantity removes the level.
+#[derive(Deserialize, Debug, Clone)]
+pub struct DepthUpdate {
+    #[serde(rename = "s")]
+    pub symbol: String,
+    #[serde(rename = "U")]
+    pub first_update_id: u64,
+    #[serde(rename = "u")]
+    pub final_update_id: u64,
+    #[serde(rename = "b")]
+    pub bids: Vec<(Decimal, Decimal)>,
+    #[serde(rename = "a")]
+    pub asks: Vec<(Decimal, Decimal)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Side {
+    Bid,
+    Ask,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DepthLevel {
+    pub price: Decimal,
+    pub size: Decimal,
+    pub cumulative: Decimal,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CumulativeLevel {
+    pub price: Decimal,
+    pub quantity: Decimal,
+    pub notional: Decimal,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DepthGap {
+    pub expected: u64,
+    pub received: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct OrderBook {
+    pub last_update_id: u64,
+    bridged: bool,
+    bids: BTreeMap<Decimal, Decimal>,
+    asks: BTreeMap<Decimal, Decimal>,
+}
+
+impl OrderBook {
+    pub fn from_snapshot(snapshot: &DepthSnapshot) -> Self {
+        let mut book = Self {
+            last_update_id: snapshot.last_update_id,
+            bridged: false,
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+        };
+
+        set_levels(&mut book.bids, &snapshot.bids);
+        set_levels(&mut book.asks, &snapshot.asks);
+
+        book
+    }
+
+    /// `Ok(false)` for events the book already covers, `Err` when update ids skip ahead.
+    pub fn apply(&mut self, update: &DepthUpdate) -> Result<bool, DepthGap> {
+        if update.final_update_id <= self.last_update_id {
+            return Ok(false);
+        }
+
+        let next_id = self.last_update_id + 1;
+        let in_sequence = if self.bridged {
+            update.first_update_id == next_id
+        } else {
+            update.first_update_id <= next_id
+        };
+
+        if !in_sequence {
+            return Err(DepthGap {
+                expected: next_id,
+                received: update.first_update_id,
+            });
+        }
+
+        set_levels(&mut self.bids, &update.bids);
+        set_levels(&mut self.asks, &update.asks);
+        self.last_update_id = update.final_update_id;
+        self.bridged = true;
+
+        Ok(true)
+    }
+
+    pub fn best_bid(&self) -> Option<Decimal> {
+        self.bids.keys().next_back().copied()
+    }
+
+    pub fn best_ask(&self) -> Option<Decimal> {
+        self.asks.keys().next().copied()
+    }
+
+    pub fn mid_price(&self) -> Option<Decimal> {
+        Some((self.best_bid()? + self.best_ask()?) / Decimal::TWO)
+    }
+
+    /// Raw levels from the spread outwards until `limit`, with running quantity and notional.
+    pub fn cumulative(&self, side: Side, limit: Decimal) -> Vec<CumulativeLevel> {
+        let levels: Box<dyn Iterator<Item = (&Decimal, &Decimal)>> = match side {
+            Side::Bid => Box::new(self.bids.range(limit..).rev()),
+            Side::Ask => Box::new(self.asks.range(..=limit)),
+        };
+
+        let mut quantity = Decimal::ZERO;
+        let mut notional = Decimal::ZERO;
+
+        levels
+            .map(|(price, size)| {
+                quantity += size;
+                notional += price * size;
+
+                CumulativeLevel {
+                    price: *price,
+                    quantity,
+                    notional,
+                }
+            })
+            .collect()
+    }
+
+    /// Buckets levels by `increment` away from the spread: bids round down, asks round up.
+    pub fn grouped(&self, side: Side, increment: Decimal, rows: usize) -> Vec<DepthLevel> {
+        let levels: Box<dyn Iterator<Item = (&Decimal, &Decimal)>> = match side {
+            Side::Bid => Box::new(self.bids.iter().rev()),
+            Side::Ask => Box::new(self.asks.iter()),
+        };
+
+        let mut grouped: Vec<DepthLevel> = Vec::with_capacity(rows);
+        let mut cumulative = Decimal::ZERO;
+
+        for (price, size) in levels {
+            let bucket = if increment > Decimal::ZERO {
+                match side {
+                    Side::Bid => (price / increment).floor() * increment,
+                    Side::Ask => (price / increment).ceil() * increment,
+                }
+            } else {
+                *price
+            };
+
+            cumulative += size;
+
+            if let Some(level) = grouped.last_mut()
+                && level.price == bucket
+            {
+                level.size += size;
+                level.cumulative = cumulative;
+            } else if grouped.len() == rows {
+                break;
+            } else {
+                grouped.push(DepthLevel {
+                    price: bucket,
+                    size: *size,
+                    cumulative,
+                });
+            }
+        }
+
+        grouped
+    }
+}
+
+fn set_levels(levels: &mut BTreeMap<Decimal, Decimal>, changes: &[(Decimal, Decimal)]) {
+    for (price, quantity) in changes {
+        if quantity.is_zero() {
+            levels.remove(price);
+        } else {
+            levels.insert(*price, *quantity);
+        }
+    }
+}
+
+/// Diff events buffered until a snapshot they bridge onto has been loaded.
+#[derive(Debug, Clone)]
+pub struct OrderBookSync {
+    pub symbol: String,
+    pub book: Option<OrderBook>,
+    pub snapshot_requests: usize,
+    pending: VecDeque<DepthUpdate>,
+    awaiting_snapshot: bool,
+}
+
+impl OrderBookSync {
+    pub fn new(symbol: String) -> Self {
+        Self {
+            symbol,
+            book: None,
+            snapshot_requests: 0,
+            pending: VecDeque::new(),
+            awaiting_snapshot: false,
+        }
+    }
+
+    /// Returns `true` when a fresh snapshot has to be requested. A gap drops the book and
+    /// comes back as the error, a snapshot is always needed then.
+    pub fn on_update(&mut self, update: DepthUpdate) -> Result<bool, DepthGap> {
+        let gap = match self.book.as_mut().map(|book| book.apply(&update)) {
+            Some(Ok(_)) => return Ok(false),
+            Some(Err(gap)) => Some(gap),
+            None => None,
+        };
+        self.book = None;
+
+        if self.pending.len() == MAX_PENDING_UPDATES {
+            self.pending.pop_front();
+        }
+        self.pending.push_back(update);
+        let needs_snapshot = self.request_snapshot();
+
+        gap.map_or(Ok(needs_snapshot), Err)
+    }
+
+    /// Replays buffered events on top of `snapshot`, returns `true` when it was too old.
+    pub fn on_snapshot(&mut self, snapshot: &DepthSnapshot) -> bool {
+        self.awaiting_snapshot = false;
+
+        let mut book = OrderBook::from_snapshot(snapshot);
+        let mut pending = std::mem::take(&mut self.pending);
+
+        while let Some(update) = pending.front() {
+            if book.apply(update).is_err() {
+                self.pending = pending;
+                return self.request_snapshot();
+            }
+
+            pending.pop_front();
+        }
+
+        self.book = Some(book);
+        false
+    }
+
+    pub fn on_snapshot_failed(&mut self) {
+        self.awaiting_snapshot = false;
+    }
+
+    fn request_snapshot(&mut self) -> bool {
+        if self.awaiting_snapshot {
+            return false;
+        }
+
+        self.awaiting_snapshot = true;
+        self.snapshot_requests += 1;
+        true
+    }
+}
+
+/// Binance tick sizes are powers of ten, so the symbol's decimals describe them.
+pub fn tick_size(decimals: u32) -> Decimal {
+    Decimal::new(1, decimals)
+}
+
+pub fn grouping_label(decimals: u32, multiplier: u32) -> String {
+    (tick_size(decimals) * Decimal::from(multiplier))
+        .normalize()
+        .to_string()
+}
+
+pub fn grouping_labels(decimals: u32) -> Vec<String> {
+    DEPTH_GROUPINGS
+        .iter()
+        .map(|multiplier| grouping_label(decimals, *multiplier))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::dec;
+
+    fn update(first: u64, last: u64, bid: Decimal) -> DepthUpdate {
+        DepthUpdate {
+            symbol: "BTCUSDT".to_string(),
+            first_update_id: first,
+            final_update_id: last,
+            bids: vec![(bid, dec!(1))],
+            asks: vec![(dec!(200), dec!(1))],
+        }
+    }
+
+    fn snapshot(last_update_id: u64) -> DepthSnapshot {
+        DepthSnapshot {
+            last_update_id,
+            bids: vec![(dec!(90), dec!(2))],
+            asks: vec![(dec!(210), dec!(2))],
+        }
+    }
+
+    #[test]
+    fn buffers_until_a_snapshot_bridges_and_resyncs_after_a_gap() {
+        let mut sync = OrderBookSync::new("BTCUSDT".to_string());
+
+        assert!(sync.on_update(update(8, 10, dec!(100))).unwrap());
+        assert!(!sync.on_update(update(11, 12, dec!(101))).unwrap());
+        assert_eq!(sync.snapshot_requests, 1);
+
+        // Event 8..10 is older than the snapshot and skipped, 11..12 bridges onto it.
+        assert!(!sync.on_snapshot(&snapshot(10)));
+        let book = sync.book.as_ref().unwrap();
+        assert_eq!(book.last_update_id, 12);
+        assert_eq!(book.best_bid(), Some(dec!(101)));
+
+        assert!(!sync.on_update(update(13, 13, dec!(102))).unwrap());
+        let gap = sync.on_update(update(20, 21, dec!(103))).unwrap_err();
+        assert_eq!((gap.expected, gap.received), (14, 20));
+        assert!(sync.book.is_none());
+
+        // A snapshot older than the buffered events cannot be joined to them.
+        assert!(sync.on_snapshot(&snapshot(15)));
+        assert!(!sync.on_snapshot(&snapshot(19)));
+        assert_eq!(sync.book.as_ref().unwrap().last_update_id, 21);
+        assert_eq!(sync.snapshot_requests, 3);
+    }
+
+    #[test]
+    fn keeps_only_the_newest_updates_while_snapshots_fail() {
+        let mut sync = OrderBookSync::new("BTCUSDT".to_string());
+        let updates = MAX_PENDING_UPDATES as u64 + 500;
+
+        for id in 1..=updates {
+            if sync.on_update(update(id, id, dec!(100))).unwrap() {
+                sync.on_snapshot_failed();
+            }
+        }
+        assert_eq!(sync.pending.len(), MAX_PENDING_UPDATES);
+
+        assert!(!sync.on_snapshot(&snapshot(updates - 10)));
+        assert_eq!(sync.book.unwrap().last_update_id, updates);
+    }
+}
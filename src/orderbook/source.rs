@@ -0,0 +1,72 @@
+use crate::config;
+use crate::orderbook::DepthSnapshot;
+use std::fs;
+use std::path::PathBuf;
+
+pub const DEPTH_SNAPSHOT_LIMIT: u32 = 1000;
+
+/// Setting this to a fixture directory replays recorded messages instead of Binance.
+pub const REPLAY_ENV: &str = "JUICY_DEPTH_REPLAY";
+
+/// A replay directory holds `snapshots.jsonl` and `updates.jsonl`, one raw message per line.
+/// Snapshots are served in request order and the last one repeats, updates are
+/// relabelled to whichever symbol is displayed.
+#[derive(Debug, Clone, Hash)]
+pub enum DepthSource {
+    Binance,
+    Replay(PathBuf),
+}
+
+impl DepthSource {
+    pub fn from_env() -> Self {
+        match std::env::var(REPLAY_ENV) {
+            Ok(path) if !path.trim().is_empty() => DepthSource::Replay(PathBuf::from(path.trim())),
+            _ => DepthSource::Binance,
+        }
+    }
+}
+
+/// Non-empty lines of a replay file, one raw message each.
+pub fn read_lines(path: PathBuf) -> Result<Vec<String>, String> {
+    fs::read_to_string(&path)
+        .map(|content| {
+            content
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .map(|line| line.to_string())
+                .collect()
+        })
+        .map_err(|err| format!("Cannot read {}: {}", path.display(), err))
+}
+
+/// `request` is the zero-based snapshot request for the symbol, only replays use it.
+pub async fn get_depth_snapshot(
+    source: DepthSource,
+    symbol: String,
+    request: usize,
+) -> Result<DepthSnapshot, String> {
+    match source {
+        DepthSource::Binance => {
+            let url = format!(
+                "{}/api/v3/depth?symbol={symbol}&limit={DEPTH_SNAPSHOT_LIMIT}",
+                config::get().api.rest()
+            );
+
+            match reqwest::get(&url).await {
+                Ok(response) => response
+                    .json::<DepthSnapshot>()
+                    .await
+                    .map_err(|err| format!("Failed to parse depth snapshot: {}", err)),
+                Err(err) => Err(format!("Failed to fetch depth snapshot: {}", err)),
+            }
+        }
+        DepthSource::Replay(dir) => {
+            let snapshots = read_lines(dir.join("snapshots.jsonl"))?;
+            let line = snapshots
+                .get(request.min(snapshots.len().saturating_sub(1)))
+                .ok_or_else(|| String::from("Replay has no snapshots"))?;
+
+            serde_json::from_str(line).map_err(|err| format!("Invalid replay snapshot: {}", err))
+        }
+    }
+}
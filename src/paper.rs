@@ -0,0 +1,649 @@
+use crate::candle::Candle;
+use crate::symbols::Symbol;
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderSide {
+    Buy,
+    Sell,
+}
+
+impl fmt::Display for OrderSide {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            OrderSide::Buy => "Buy",
+            OrderSide::Sell => "Sell",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderKind {
+    Market,
+    Limit(Decimal),
+    /// Becomes a market order once the price reaches the stop.
+    Stop(Decimal),
+    /// Becomes a limit order once the price reaches the stop.
+    StopLimit {
+        stop: Decimal,
+        limit: Decimal,
+    },
+}
+
+impl OrderKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            OrderKind::Market => "Market",
+            OrderKind::Limit(_) => "Limit",
+            OrderKind::Stop(_) => "Stop",
+            OrderKind::StopLimit { .. } => "Stop limit",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Order {
+    pub id: u64,
+    pub symbol: String,
+    pub base: String,
+    pub quote: String,
+    pub decimals: u32,
+    pub side: OrderSide,
+    pub kind: OrderKind,
+    pub quantity: Decimal,
+    /// Epoch millis, candles that opened earlier never fill it.
+    pub placed_at: u64,
+    /// A stop limit whose stop was reached and now waits as a limit.
+    pub triggered: bool,
+}
+
+impl Order {
+    /// Prices the order waits for, drawn as lines on the chart.
+    pub fn levels(&self) -> Vec<(&'static str, Decimal)> {
+        match self.kind {
+            OrderKind::Market => Vec::new(),
+            OrderKind::Limit(limit) => vec![("LMT", limit)],
+            OrderKind::Stop(stop) => vec![("STP", stop)],
+            OrderKind::StopLimit { limit, .. } if self.triggered => vec![("LMT", limit)],
+            OrderKind::StopLimit { stop, limit } => vec![("STP", stop), ("LMT", limit)],
+        }
+    }
+}
+
+impl fmt::Display for Order {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.side, self.quantity, self.symbol)?;
+
+        match self.kind {
+            OrderKind::Market => write!(f, " at market"),
+            OrderKind::Limit(limit) => write!(f, " limit {}", limit),
+            OrderKind::Stop(stop) => write!(f, " stop {}", stop),
+            OrderKind::StopLimit { stop, limit } => write!(f, " stop {} limit {}", stop, limit),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fill {
+    pub order_id: u64,
+    pub symbol: String,
+    pub side: OrderSide,
+    pub price: Decimal,
+    pub quantity: Decimal,
+    /// Charged in the quote asset.
+    pub fee: Decimal,
+    pub time: u64,
+}
+
+impl fmt::Display for Fill {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} at {}",
+            self.side, self.quantity, self.symbol, self.price
+        )
+    }
+}
+
+/// What one symbol's fills add up to, long only like a spot account.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Position {
+    pub symbol: String,
+    pub quantity: Decimal,
+    pub average_price: Decimal,
+    /// Sale proceeds over the average price, net of the fees of both sides.
+    pub realized: Decimal,
+}
+
+impl Position {
+    pub fn unrealized(&self, price: Decimal) -> Decimal {
+        (price - self.average_price) * self.quantity
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PaperEvent {
+    Filled(Fill),
+    /// The funds were gone by the time the order filled.
+    Rejected(Order, String),
+}
+
+impl fmt::Display for PaperEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PaperEvent::Filled(fill) => write!(f, "Filled {}", fill),
+            PaperEvent::Rejected(order, reason) => write!(f, "Rejected {}: {}", order, reason),
+        }
+    }
+}
+
+/// A simulated spot account: balances per asset, open orders and their fills.
+#[derive(Debug, Clone, Default)]
+pub struct PaperAccount {
+    pub balances: BTreeMap<String, Decimal>,
+    pub orders: Vec<Order>,
+    pub fills: Vec<Fill>,
+    pub positions: Vec<Position>,
+    /// Fraction of the traded value charged per fill.
+    pub fee_rate: Decimal,
+    /// Fraction market and stop fills move against the order.
+    pub slippage: Decimal,
+    /// Bumped on every change, keys the chart layer that draws orders and fills.
+    pub revision: u64,
+    next_id: u64,
+}
+
+/// Where an order fills within the range a tick or a candle traded through, `None` while
+/// it waits. `reference` is the first price of the range, the tick itself or the open.
+fn fill_price(
+    order: &mut Order,
+    low: Decimal,
+    high: Decimal,
+    reference: Decimal,
+) -> Option<(Decimal, bool)> {
+    let buy = order.side == OrderSide::Buy;
+
+    match order.kind {
+        OrderKind::Market => Some((reference, true)),
+        OrderKind::Limit(limit) | OrderKind::StopLimit { limit, .. } if order.triggered => {
+            limit_price(buy, limit, low, high, reference)
+        }
+        OrderKind::Limit(limit) => limit_price(buy, limit, low, high, reference),
+        OrderKind::Stop(stop) => match buy {
+            true if high >= stop => Some((reference.max(stop), true)),
+            false if low <= stop => Some((reference.min(stop), true)),
+            _ => None,
+        },
+        OrderKind::StopLimit { stop, limit } => {
+            let reached = if buy { high >= stop } else { low <= stop };
+            if !reached {
+                return None;
+            }
+
+            order.triggered = true;
+            let start = if buy {
+                reference.max(stop)
+            } else {
+                reference.min(stop)
+            };
+            limit_price(buy, limit, low, high, start)
+        }
+    }
+}
+
+fn limit_price(
+    buy: bool,
+    limit: Decimal,
+    low: Decimal,
+    high: Decimal,
+    reference: Decimal,
+) -> Option<(Decimal, bool)> {
+    match buy {
+        true if low <= limit => Some((reference.min(limit), false)),
+        false if high >= limit => Some((reference.max(limit), false)),
+        _ => None,
+    }
+}
+
+impl PaperAccount {
+    pub fn new(balances: BTreeMap<String, Decimal>, fee_rate: Decimal, slippage: Decimal) -> Self {
+        Self {
+            balances,
+            fee_rate,
+            slippage,
+            ..Self::default()
+        }
+    }
+
+    pub fn balance(&self, asset: &str) -> Decimal {
+        self.balances.get(asset).copied().unwrap_or_default()
+    }
+
+    pub fn position(&self, symbol: &str) -> Option<&Position> {
+        self.positions.iter().find(|p| p.symbol == symbol)
+    }
+
+    /// Checks the order against the balances at its own price, or at `last_price` for a
+    /// market order, which fills right away when there is one. Buys that fill with
+    /// slippage are checked at the slipped price.
+    pub fn place(
+        &mut self,
+        symbol: &Symbol,
+        side: OrderSide,
+        kind: OrderKind,
+        quantity: Decimal,
+        last_price: Option<Decimal>,
+        time: u64,
+    ) -> Result<Vec<PaperEvent>, String> {
+        if quantity <= Decimal::ZERO {
+            return Err("Quantity must be above zero".to_string());
+        }
+
+        let price = match kind {
+            OrderKind::Market => {
+                last_price.ok_or_else(|| format!("No price for {} yet", symbol.symbol))?
+            }
+            OrderKind::Limit(price) | OrderKind::Stop(price) => price,
+            OrderKind::StopLimit { stop, limit } => {
+                if stop <= Decimal::ZERO {
+                    return Err("Stop must be above zero".to_string());
+                }
+                limit
+            }
+        };
+        if price <= Decimal::ZERO {
+            return Err("Price must be above zero".to_string());
+        }
+
+        match side {
+            OrderSide::Buy => {
+                // Market and stop buys fill with slippage, the check pays it too.
+                let price = match kind {
+                    OrderKind::Market | OrderKind::Stop(_) => {
+                        (price * (Decimal::ONE + self.slippage)).round_dp(symbol.decimals)
+                    }
+                    _ => price,
+                };
+                let cost = price * quantity * (Decimal::ONE + self.fee_rate);
+                let available = self.balance(&symbol.quote_asset);
+                if cost > available {
+                    return Err(format!(
+                        "Needs {} {}, {} available",
+                        cost.round_dp(8).normalize(),
+                        symbol.quote_asset,
+                        available.normalize()
+                    ));
+                }
+            }
+            OrderSide::Sell => {
+                let available = self.balance(&symbol.base_asset);
+                if quantity > available {
+                    return Err(format!(
+                        "Needs {} {}, {} available",
+                        quantity.normalize(),
+                        symbol.base_asset,
+                        available.normalize()
+                    ));
+                }
+            }
+        }
+
+        self.next_id += 1;
+        self.orders.push(Order {
+            id: self.next_id,
+            symbol: symbol.symbol.clone(),
+            base: symbol.base_asset.clone(),
+            quote: symbol.quote_asset.clone(),
+            decimals: symbol.decimals,
+            side,
+            kind,
+            quantity,
+            placed_at: time,
+            triggered: false,
+        });
+        self.revision += 1;
+
+        Ok(match (kind, last_price) {
+            (OrderKind::Market, Some(price)) => self.on_price(&symbol.symbol, price, time),
+            _ => Vec::new(),
+        })
+    }
+
+    pub fn cancel(&mut self, id: u64) -> Option<Order> {
+        let index = self.orders.iter().position(|o| o.id == id)?;
+        self.revision += 1;
+        Some(self.orders.remove(index))
+    }
+
+    /// A trade or ticker price of `symbol`.
+    pub fn on_price(&mut self, symbol: &str, price: Decimal, time: u64) -> Vec<PaperEvent> {
+        self.match_orders(symbol, |order| {
+            (order.placed_at <= time).then_some((price, price, price, time))
+        })
+    }
+
+    /// Catches the highs and lows that fell between ticks. A candle only fills orders
+    /// placed before it opened, its range may predate anything placed later.
+    pub fn on_candle(&mut self, symbol: &str, candle: &Candle) -> Vec<PaperEvent> {
+        self.match_orders(symbol, |order| {
+            (order.placed_at <= candle.open_time).then_some((
+                candle.low,
+                candle.high,
+                candle.open,
+                candle.open_time,
+            ))
+        })
+    }
+
+    /// `range` is the low, high, first price and time an order could fill at.
+    fn match_orders(
+        &mut self,
+        symbol: &str,
+        range: impl Fn(&Order) -> Option<(Decimal, Decimal, Decimal, u64)>,
+    ) -> Vec<PaperEvent> {
+        let mut events = Vec::new();
+        let mut index = 0;
+
+        while index < self.orders.len() {
+            let order = &mut self.orders[index];
+            let was_triggered = order.triggered;
+
+            let fill = (order.symbol == symbol)
+                .then(|| range(order))
+                .flatten()
+                .and_then(|(low, high, reference, at)| {
+                    fill_price(order, low, high, reference).map(|fill| (fill, at))
+                });
+
+            if order.triggered != was_triggered {
+                self.revision += 1;
+            }
+
+            let Some(((price, slips), at)) = fill else {
+                index += 1;
+                continue;
+            };
+
+            let order = self.orders.remove(index);
+            let price = match (slips, order.side) {
+                (true, OrderSide::Buy) => price * (Decimal::ONE + self.slippage),
+                (true, OrderSide::Sell) => price * (Decimal::ONE - self.slippage),
+                (false, _) => price,
+            }
+            .round_dp(order.decimals);
+
+            events.push(self.execute(order, price, at));
+        }
+
+        if !events.is_empty() {
+            self.revision += 1;
+        }
+
+        events
+    }
+
+    fn execute(&mut self, order: Order, price: Decimal, time: u64) -> PaperEvent {
+        let value = price * order.quantity;
+        let fee = value * self.fee_rate;
+
+        match order.side {
+            OrderSide::Buy => {
+                let available = self.balance(&order.quote);
+                if value + fee > available {
+                    let reason = format!("{} {} available", available.normalize(), order.quote);
+                    return PaperEvent::Rejected(order, reason);
+                }
+
+                *self.balances.entry(order.quote.clone()).or_default() -= value + fee;
+                *self.balances.entry(order.base.clone()).or_default() += order.quantity;
+            }
+            OrderSide::Sell => {
+                let available = self.balance(&order.base);
+                if order.quantity > available {
+                    let reason = format!("{} {} available", available.normalize(), order.base);
+                    return PaperEvent::Rejected(order, reason);
+                }
+
+                *self.balances.entry(order.base.clone()).or_default() -= order.quantity;
+                *self.balances.entry(order.quote.clone()).or_default() += value - fee;
+            }
+        }
+
+        let position = match self.positions.iter_mut().find(|p| p.symbol == order.symbol) {
+            Some(position) => position,
+            None => {
+                self.positions.push(Position {
+                    symbol: order.symbol.clone(),
+                    quantity: Decimal::ZERO,
+                    average_price: Decimal::ZERO,
+                    realized: Decimal::ZERO,
+                });
+                self.positions.last_mut().unwrap()
+            }
+        };
+
+        match order.side {
+            OrderSide::Buy => {
+                let quantity = position.quantity + order.quantity;
+                position.average_price =
+                    (position.average_price * position.quantity + value + fee) / quantity;
+                position.quantity = quantity;
+            }
+            OrderSide::Sell => {
+                // Sold from balances the fills never bought count at no gain.
+                let matched = order.quantity.min(position.quantity);
+                position.realized += (price - position.average_price) * matched - fee;
+                position.quantity -= matched;
+            }
+        }
+
+        let fill = Fill {
+            order_id: order.id,
+            symbol: order.symbol,
+            side: order.side,
+            price,
+            quantity: order.quantity,
+            fee,
+            time,
+        };
+        self.fills.push(fill.clone());
+
+        PaperEvent::Filled(fill)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::pair;
+    use rust_decimal::prelude::*;
+
+    fn btc() -> Symbol {
+        pair("BTC", "USDT", 2)
+    }
+
+    fn account() -> PaperAccount {
+        PaperAccount::new(
+            BTreeMap::from([("USDT".to_string(), dec!(10000))]),
+            dec!(0.001),
+            dec!(0.01),
+        )
+    }
+
+    fn filled(events: &[PaperEvent]) -> Vec<Decimal> {
+        events
+            .iter()
+            .filter_map(|event| match event {
+                PaperEvent::Filled(fill) => Some(fill.price),
+                PaperEvent::Rejected(..) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn market_orders_fill_with_slippage_and_fees() {
+        let mut account = account();
+
+        let events = account
+            .place(
+                &btc(),
+                OrderSide::Buy,
+                OrderKind::Market,
+                dec!(10),
+                Some(dec!(100)),
+                0,
+            )
+            .unwrap();
+        assert_eq!(filled(&events), [dec!(101)]);
+        assert_eq!(account.balance("BTC"), dec!(10));
+        assert_eq!(
+            account.balance("USDT"),
+            dec!(10000) - dec!(1010) - dec!(1.01)
+        );
+        assert!(account.orders.is_empty());
+
+        account
+            .place(
+                &btc(),
+                OrderSide::Sell,
+                OrderKind::Market,
+                dec!(10),
+                Some(dec!(110)),
+                1,
+            )
+            .unwrap();
+        let position = account.position("BTCUSDT").unwrap();
+        assert_eq!(position.quantity, Decimal::ZERO);
+        // Bought at 101.101 with the fee, sold at 108.9 less 1.089 of fee.
+        assert_eq!(position.realized, dec!(76.901));
+
+        let err = account
+            .place(
+                &btc(),
+                OrderSide::Sell,
+                OrderKind::Market,
+                dec!(1),
+                Some(dec!(110)),
+                2,
+            )
+            .unwrap_err();
+        assert_eq!(err, "Needs 1 BTC, 0 available");
+    }
+
+    #[test]
+    fn market_buys_are_checked_at_the_slipped_price() {
+        let mut account = account();
+
+        // 99 at 100 would fit the balance, but the fill pays 101.
+        let err = account
+            .place(
+                &btc(),
+                OrderSide::Buy,
+                OrderKind::Market,
+                dec!(99),
+                Some(dec!(100)),
+                3,
+            )
+            .unwrap_err();
+        assert!(err.starts_with("Needs 10008.999 USDT"), "{}", err);
+        assert!(account.orders.is_empty());
+    }
+
+    #[test]
+    fn limits_and_stops_wait_for_their_price() {
+        let mut account = account();
+        let symbol = btc();
+
+        account
+            .place(
+                &symbol,
+                OrderSide::Buy,
+                OrderKind::Limit(dec!(95)),
+                dec!(1),
+                None,
+                0,
+            )
+            .unwrap();
+        account
+            .place(
+                &symbol,
+                OrderSide::Buy,
+                OrderKind::Stop(dec!(105)),
+                dec!(1),
+                None,
+                0,
+            )
+            .unwrap();
+        account
+            .place(
+                &symbol,
+                OrderSide::Buy,
+                OrderKind::StopLimit {
+                    stop: dec!(110),
+                    limit: dec!(108),
+                },
+                dec!(1),
+                None,
+                0,
+            )
+            .unwrap();
+
+        assert!(account.on_price("BTCUSDT", dec!(100), 1).is_empty());
+        assert!(account.on_price("ETHUSDT", dec!(90), 1).is_empty());
+        assert_eq!(
+            filled(&account.on_price("BTCUSDT", dec!(94), 2)),
+            [dec!(94)]
+        );
+        assert_eq!(
+            filled(&account.on_price("BTCUSDT", dec!(106), 3)),
+            [dec!(107.06)]
+        );
+
+        // Gapping over the stop and the limit triggers the stop limit but leaves it waiting.
+        assert!(account.on_price("BTCUSDT", dec!(111), 4).is_empty());
+        assert!(account.orders[0].triggered);
+        assert_eq!(account.orders[0].levels(), [("LMT", dec!(108))]);
+
+        let candle = Candle {
+            open: dec!(111),
+            high: dec!(112),
+            low: dec!(107),
+            close: dec!(109),
+            volume: dec!(5),
+            open_time: 60_000,
+            close_time: 119_999,
+        };
+        assert_eq!(filled(&account.on_candle("BTCUSDT", &candle)), [dec!(108)]);
+        assert!(account.orders.is_empty());
+    }
+
+    #[test]
+    fn candles_ignore_orders_placed_after_they_opened() {
+        let mut account = account();
+
+        account
+            .place(
+                &btc(),
+                OrderSide::Buy,
+                OrderKind::Limit(dec!(95)),
+                dec!(1),
+                None,
+                90_000,
+            )
+            .unwrap();
+
+        let candle = Candle {
+            open: dec!(100),
+            high: dec!(101),
+            low: dec!(90),
+            close: dec!(100),
+            volume: dec!(5),
+            open_time: 60_000,
+            close_time: 119_999,
+        };
+        assert!(account.on_candle("BTCUSDT", &candle).is_empty());
+        assert_eq!(account.orders.len(), 1);
+    }
+}
@@ -0,0 +1,497 @@
+use crate::paper::OrderSide;
+use crate::symbols::Symbol;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fs;
+use std::path::Path;
+
+/// Epoch millis and a value.
+pub type ValuePoint = (u64, Decimal);
+
+const TRADE_COLUMNS: [&str; 4] = ["asset", "side", "quantity", "price"];
+
+/// One asset held, its cost and what selling some of it made, in the valuation asset.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Holding {
+    pub asset: String,
+    pub quantity: Decimal,
+    /// Per unit, fees of the buys included.
+    pub average_cost: Decimal,
+    /// Sales over the average cost, net of their fees.
+    #[serde(default)]
+    pub realized: Decimal,
+}
+
+/// A row of an imported trade history, priced in the valuation asset.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Trade {
+    pub asset: String,
+    pub side: OrderSide,
+    pub quantity: Decimal,
+    pub price: Decimal,
+    pub fee: Decimal,
+}
+
+/// Reads `asset,side,quantity,price` with an optional `fee` column, in any order and
+/// case, extra columns like a date are skipped.
+pub fn parse_trades(csv: &str) -> Result<Vec<Trade>, String> {
+    let mut lines = csv.lines().filter(|line| !line.trim().is_empty());
+    let header = lines
+        .next()
+        .ok_or("The trade history is empty")?
+        .split(',')
+        .map(|column| column.trim().to_lowercase())
+        .collect::<Vec<_>>();
+
+    let column = |name: &str| header.iter().position(|c| c == name);
+    let mut indices = Vec::new();
+    for name in TRADE_COLUMNS {
+        indices.push(column(name).ok_or_else(|| {
+            format!(
+                "The trade history needs the columns {}, {} is missing",
+                TRADE_COLUMNS.join(", "),
+                name
+            )
+        })?);
+    }
+    let fee = column("fee");
+
+    lines
+        .enumerate()
+        .map(|(index, line)| {
+            let line_number = index + 2;
+            let fields = line.split(',').map(str::trim).collect::<Vec<_>>();
+            let field = |i: usize| {
+                fields
+                    .get(i)
+                    .copied()
+                    .ok_or_else(|| format!("Line {}: too few columns", line_number))
+            };
+            let number = |i: usize| {
+                let value = field(i)?;
+                value.parse::<Decimal>().map_err(|_| {
+                    format!(
+                        "Line {}: {} must be a number, got {:?}",
+                        line_number, header[i], value
+                    )
+                })
+            };
+
+            let side = match field(indices[1])?.to_lowercase().as_str() {
+                "buy" => OrderSide::Buy,
+                "sell" => OrderSide::Sell,
+                other => {
+                    return Err(format!(
+                        "Line {}: side must be buy or sell, got {:?}",
+                        line_number, other
+                    ));
+                }
+            };
+
+            Ok(Trade {
+                asset: field(indices[0])?.to_uppercase(),
+                side,
+                quantity: number(indices[2])?,
+                price: number(indices[3])?,
+                fee: match fee {
+                    Some(i) if !field(i)?.is_empty() => number(i)?,
+                    _ => Decimal::ZERO,
+                },
+            })
+        })
+        .collect()
+}
+
+/// Holdings entered by hand or built from trades, saved as TOML.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Portfolio {
+    #[serde(default)]
+    pub holdings: Vec<Holding>,
+}
+
+impl Portfolio {
+    /// An empty portfolio when `path` does not exist yet.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        match fs::read_to_string(path) {
+            Ok(content) => toml::from_str(&content)
+                .map_err(|err| format!("{}: {}", path.display(), err.message())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(format!("Cannot read {}: {}", path.display(), err)),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let content = toml::to_string(self).map_err(|err| err.to_string())?;
+        fs::write(path, content).map_err(|err| format!("Cannot write {}: {}", path.display(), err))
+    }
+
+    pub fn holding(&self, asset: &str) -> Option<&Holding> {
+        self.holdings.iter().find(|h| h.asset == asset)
+    }
+
+    /// Replaces the quantity and cost of `asset`, keeping what it already realized.
+    pub fn set(
+        &mut self,
+        asset: &str,
+        quantity: Decimal,
+        average_cost: Decimal,
+    ) -> Result<(), String> {
+        let asset = asset.trim().to_uppercase();
+        if asset.is_empty() {
+            return Err("Asset must not be empty".to_string());
+        }
+        if quantity.is_sign_negative() || average_cost.is_sign_negative() {
+            return Err("Quantity and average cost must not be negative".to_string());
+        }
+
+        match self.holdings.iter_mut().find(|h| h.asset == asset) {
+            Some(holding) => {
+                holding.quantity = quantity;
+                holding.average_cost = average_cost;
+            }
+            None => self.holdings.push(Holding {
+                asset,
+                quantity,
+                average_cost,
+                realized: Decimal::ZERO,
+            }),
+        }
+
+        Ok(())
+    }
+
+    pub fn remove(&mut self, asset: &str) -> Option<Holding> {
+        let index = self.holdings.iter().position(|h| h.asset == asset)?;
+        Some(self.holdings.remove(index))
+    }
+
+    /// Buys move the average cost, sells realize against it.
+    pub fn apply(&mut self, trade: &Trade) -> Result<(), String> {
+        if trade.quantity <= Decimal::ZERO || trade.price.is_sign_negative() {
+            return Err(format!(
+                "{} {} at {} is not a valid trade",
+                trade.side, trade.quantity, trade.price
+            ));
+        }
+
+        if self.holding(&trade.asset).is_none() {
+            self.set(&trade.asset, Decimal::ZERO, Decimal::ZERO)?;
+        }
+        let holding = self
+            .holdings
+            .iter_mut()
+            .find(|h| h.asset == trade.asset)
+            .expect("holding was just added");
+
+        match trade.side {
+            OrderSide::Buy => {
+                let quantity = holding.quantity + trade.quantity;
+                holding.average_cost = (holding.quantity * holding.average_cost
+                    + trade.quantity * trade.price
+                    + trade.fee)
+                    / quantity;
+                holding.quantity = quantity;
+            }
+            OrderSide::Sell => {
+                if trade.quantity > holding.quantity {
+                    return Err(format!(
+                        "Sells {} {}, holds {}",
+                        trade.quantity.normalize(),
+                        trade.asset,
+                        holding.quantity.normalize()
+                    ));
+                }
+
+                holding.realized +=
+                    (trade.price - holding.average_cost) * trade.quantity - trade.fee;
+                holding.quantity -= trade.quantity;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Applies every trade of the history or none, returns how many there were.
+    pub fn import_trades(&mut self, csv: &str) -> Result<usize, String> {
+        let trades = parse_trades(csv)?;
+        let mut imported = self.clone();
+
+        for (index, trade) in trades.iter().enumerate() {
+            imported
+                .apply(trade)
+                .map_err(|err| format!("Trade {}: {}", index + 1, err))?;
+        }
+
+        *self = imported;
+        Ok(trades.len())
+    }
+
+    /// Every holding at `rates` in `valuation`, the ones without a rate count as zero.
+    pub fn value(&self, rates: &Rates, valuation: &str) -> Valuation {
+        let mut assets = self
+            .holdings
+            .iter()
+            .map(|holding| {
+                let price = rates.rate(&holding.asset, valuation);
+                AssetValue {
+                    holding: holding.clone(),
+                    price,
+                    value: price.map(|p| p * holding.quantity),
+                    unrealized: price.map(|p| (p - holding.average_cost) * holding.quantity),
+                    allocation: None,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let total = assets.iter().filter_map(|a| a.value).sum::<Decimal>();
+        if total > Decimal::ZERO {
+            for asset in &mut assets {
+                asset.allocation = asset.value.map(|v| v / total * Decimal::ONE_HUNDRED);
+            }
+        }
+
+        Valuation {
+            unrealized: assets.iter().filter_map(|a| a.unrealized).sum(),
+            realized: assets.iter().map(|a| a.holding.realized).sum(),
+            total,
+            assets,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AssetValue {
+    pub holding: Holding,
+    /// One unit in the valuation asset, `None` without a pair to price it.
+    pub price: Option<Decimal>,
+    pub value: Option<Decimal>,
+    pub unrealized: Option<Decimal>,
+    /// Percent of the total value.
+    pub allocation: Option<Decimal>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Valuation {
+    pub assets: Vec<AssetValue>,
+    pub total: Decimal,
+    pub unrealized: Decimal,
+    pub realized: Decimal,
+}
+
+/// Last prices by base and quote asset, converting through one other asset when no pair
+/// joins two assets directly, like ETH to USDT over ETHBTC and BTCUSDT.
+#[derive(Debug, Clone, Default)]
+pub struct Rates {
+    prices: BTreeMap<(String, String), Decimal>,
+}
+
+impl Rates {
+    /// `prices` by symbol name, `instruments` tell their base and quote.
+    pub fn new(instruments: &[Symbol], prices: &HashMap<String, Decimal>) -> Self {
+        Self {
+            prices: instruments
+                .iter()
+                .filter_map(|symbol| {
+                    let price = prices.get(&symbol.symbol)?;
+                    Some((
+                        (symbol.base_asset.clone(), symbol.quote_asset.clone()),
+                        *price,
+                    ))
+                })
+                .collect(),
+        }
+    }
+
+    fn direct(&self, from: &str, to: &str) -> Option<Decimal> {
+        if from == to {
+            return Some(Decimal::ONE);
+        }
+
+        if let Some(price) = self.prices.get(&(from.to_string(), to.to_string())) {
+            return Some(*price);
+        }
+
+        self.prices
+            .get(&(to.to_string(), from.to_string()))
+            .filter(|price| !price.is_zero())
+            .map(|price| Decimal::ONE / price)
+    }
+
+    /// What one `from` is worth in `to`.
+    pub fn rate(&self, from: &str, to: &str) -> Option<Decimal> {
+        self.direct(from, to).or_else(|| {
+            let assets = self
+                .prices
+                .keys()
+                .flat_map(|(base, quote)| [base, quote])
+                .collect::<BTreeSet<_>>();
+
+            assets
+                .into_iter()
+                .find_map(|via| Some(self.direct(from, via)? * self.direct(via, to)?))
+        })
+    }
+}
+
+fn find_pair<'a>(instruments: &'a [Symbol], a: &str, b: &str) -> Option<&'a Symbol> {
+    instruments.iter().find(|s| {
+        s.is_trading()
+            && ((s.base_asset == a && s.quote_asset == b)
+                || (s.base_asset == b && s.quote_asset == a))
+    })
+}
+
+/// Symbols whose prices value `asset` in `valuation`, one pair or two through another
+/// quote asset, empty when nothing connects them.
+pub fn pricing_pairs(instruments: &[Symbol], asset: &str, valuation: &str) -> Vec<String> {
+    if asset == valuation {
+        return Vec::new();
+    }
+
+    if let Some(pair) = find_pair(instruments, asset, valuation) {
+        return vec![pair.symbol.clone()];
+    }
+
+    instruments
+        .iter()
+        .filter(|s| s.is_trading() && s.base_asset == asset)
+        .find_map(|first| {
+            let second = find_pair(instruments, &first.quote_asset, valuation)?;
+            Some(vec![first.symbol.clone(), second.symbol.clone()])
+        })
+        .unwrap_or_default()
+}
+
+/// Total values over time, the oldest dropped past `capacity`.
+#[derive(Debug, Clone, Default)]
+pub struct ValueHistory {
+    /// Epoch millis and total value, oldest first.
+    points: VecDeque<ValuePoint>,
+    pub capacity: usize,
+}
+
+impl ValueHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            points: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, time: u64, value: Decimal) {
+        if self.points.len() == self.capacity {
+            self.points.pop_front();
+        }
+
+        self.points.push_back((time, value));
+    }
+
+    /// Epoch millis and total value, oldest first, in the two parts of the ring buffer.
+    pub fn points(&self) -> (&[ValuePoint], &[ValuePoint]) {
+        self.points.as_slices()
+    }
+
+    pub fn clear(&mut self) {
+        self.points.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures;
+    use rust_decimal::prelude::*;
+
+    fn pair(base: &str, quote: &str) -> Symbol {
+        fixtures::pair(base, quote, 8)
+    }
+
+    #[test]
+    fn rates_convert_through_another_asset() {
+        let instruments = [pair("ETH", "BTC"), pair("BTC", "USDT"), pair("USDT", "TRY")];
+        let prices = HashMap::from([
+            ("ETHBTC".to_string(), dec!(0.05)),
+            ("BTCUSDT".to_string(), dec!(60000)),
+            ("USDTTRY".to_string(), dec!(40)),
+        ]);
+        let rates = Rates::new(&instruments, &prices);
+
+        assert_eq!(rates.rate("USDT", "USDT"), Some(Decimal::ONE));
+        assert_eq!(rates.rate("BTC", "USDT"), Some(dec!(60000)));
+        assert_eq!(rates.rate("TRY", "USDT"), Some(dec!(0.025)));
+        assert_eq!(rates.rate("ETH", "USDT"), Some(dec!(3000)));
+        assert_eq!(rates.rate("SOL", "USDT"), None);
+
+        assert_eq!(
+            pricing_pairs(&instruments, "ETH", "USDT"),
+            vec!["ETHBTC".to_string(), "BTCUSDT".to_string()]
+        );
+        assert_eq!(pricing_pairs(&instruments, "TRY", "USDT"), vec!["USDTTRY"]);
+        assert!(pricing_pairs(&instruments, "SOL", "USDT").is_empty());
+    }
+
+    #[test]
+    fn trades_move_the_average_cost_and_realize_on_sells() {
+        let csv = "Date,Asset,Side,Quantity,Price,Fee\n\
+                   2024-01-01,btc,BUY,1,40000,10\n\
+                   2024-02-01,BTC,buy,1,50000,10\n\
+                   2024-03-01,BTC,sell,0.5,60000,5\n";
+
+        let mut portfolio = Portfolio::default();
+        assert_eq!(portfolio.import_trades(csv), Ok(3));
+
+        let btc = portfolio.holding("BTC").unwrap();
+        assert_eq!(btc.quantity, dec!(1.5));
+        assert_eq!(btc.average_cost, dec!(45010));
+        assert_eq!(btc.realized, dec!(7490));
+
+        let oversold = "asset,side,quantity,price\nBTC,sell,2,60000\n";
+        let err = portfolio.import_trades(oversold).unwrap_err();
+        assert_eq!(err, "Trade 1: Sells 2 BTC, holds 1.5");
+        assert_eq!(portfolio.holding("BTC").unwrap().quantity, dec!(1.5));
+
+        let err = Portfolio::default()
+            .import_trades("asset,side,price\nBTC,buy,1\n")
+            .unwrap_err();
+        assert!(err.contains("quantity is missing"), "{}", err);
+    }
+
+    #[test]
+    fn valuation_sums_priced_holdings_into_allocations() {
+        let mut portfolio = Portfolio::default();
+        portfolio.set("btc", dec!(0.5), dec!(40000)).unwrap();
+        portfolio.set("USDT", dec!(10000), Decimal::ONE).unwrap();
+        portfolio.set("SOL", dec!(10), dec!(100)).unwrap();
+
+        let instruments = [pair("BTC", "USDT")];
+        let prices = HashMap::from([("BTCUSDT".to_string(), dec!(60000))]);
+        let valuation = portfolio.value(&Rates::new(&instruments, &prices), "USDT");
+
+        assert_eq!(valuation.total, dec!(40000));
+        assert_eq!(valuation.unrealized, dec!(10000));
+
+        let btc = &valuation.assets[0];
+        assert_eq!(btc.holding.asset, "BTC");
+        assert_eq!(btc.value, Some(dec!(30000)));
+        assert_eq!(btc.allocation, Some(dec!(75)));
+        assert_eq!(valuation.assets[2].value, None);
+    }
+
+    #[test]
+    fn value_history_keeps_the_newest_points_in_order() {
+        let mut history = ValueHistory::new(3);
+        for time in 0..5 {
+            history.push(time, Decimal::from(time * 10));
+        }
+
+        let (older, newer) = history.points();
+        assert_eq!(
+            [older, newer].concat(),
+            [(2, dec!(20)), (3, dec!(30)), (4, dec!(40))]
+        );
+
+        history.clear();
+        assert_eq!(history.points(), (&[][..], &[][..]));
+    }
+}
@@ -0,0 +1,249 @@
+use crate::candle::{Candle, get_candles_since};
+use crate::orderbook::tick_size;
+use chrono::{Datelike, TimeZone, Utc};
+use rust_decimal::Decimal;
+use rust_decimal::prelude::*;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+pub const VOLUME_PROFILE_ROWS: u32 = 48;
+const VALUE_AREA_SHARE: Decimal = dec!(0.7);
+const SESSION_TIMEFRAME: &str = "15m";
+pub const MAX_KLINES: u32 = 1000;
+
+static REVISION: AtomicU64 = AtomicU64::new(0);
+
+/// Lower timeframe whose candles split the visible range finely without passing the kline limit.
+pub fn profile_timeframe(timeframe: &str) -> &'static str {
+    match timeframe {
+        "1m" | "5m" => "1m",
+        "1h" => "15m",
+        "4h" => "1h",
+        _ => "4h",
+    }
+}
+
+/// Volume at price, keyed by the lower edge of each row.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VolumeProfile {
+    pub row_size: Decimal,
+    pub rows: BTreeMap<Decimal, Decimal>,
+}
+
+impl VolumeProfile {
+    /// Spreads each candle's volume evenly over the rows its range touches.
+    pub fn from_candles(candles: &[Candle], tick_size: Decimal) -> Self {
+        let low = candles.iter().map(|c| c.low).min().unwrap_or_default();
+        let high = candles.iter().map(|c| c.high).max().unwrap_or_default();
+
+        let row_size = ((high - low) / Decimal::from(VOLUME_PROFILE_ROWS) / tick_size)
+            .ceil()
+            .max(Decimal::ONE)
+            * tick_size;
+
+        let mut profile = Self {
+            row_size,
+            rows: BTreeMap::new(),
+        };
+
+        for candle in candles {
+            profile.add(candle.low, candle.high, candle.volume);
+        }
+
+        profile
+    }
+
+    fn row_of(&self, price: Decimal) -> Decimal {
+        (price / self.row_size).floor() * self.row_size
+    }
+
+    fn add(&mut self, low: Decimal, high: Decimal, volume: Decimal) {
+        if volume <= Decimal::ZERO || self.row_size <= Decimal::ZERO {
+            return;
+        }
+
+        let first = self.row_of(low);
+        let last = self.row_of(high);
+
+        if first == last {
+            *self.rows.entry(first).or_default() += volume;
+            return;
+        }
+
+        let range = high - low;
+        let mut row = first;
+
+        while row <= last {
+            let overlap = (row + self.row_size).min(high) - row.max(low);
+            *self.rows.entry(row).or_default() += volume * overlap / range;
+            row += self.row_size;
+        }
+    }
+
+    pub fn max_volume(&self) -> Decimal {
+        self.rows.values().max().copied().unwrap_or_default()
+    }
+
+    /// Row with the most volume, the volume point of control.
+    pub fn poc(&self) -> Option<Decimal> {
+        self.rows
+            .iter()
+            .max_by(|a, b| a.1.cmp(b.1))
+            .map(|(price, _)| *price)
+    }
+
+    /// Grows from the POC towards the heavier neighbour until 70% of the volume is inside.
+    pub fn value_area(&self) -> Option<(Decimal, Decimal)> {
+        let poc = self.poc()?;
+        let total: Decimal = self.rows.values().sum();
+        let target = total * VALUE_AREA_SHARE;
+
+        let mut low = poc;
+        let mut high = poc;
+        let mut volume = self.rows[&poc];
+
+        while volume < target {
+            let below = self.rows.range(..low).next_back();
+            let above = self.rows.range(high + self.row_size..).next();
+
+            match (below, above) {
+                (Some((price, v)), Some((_, above_v))) if v >= above_v => {
+                    low = *price;
+                    volume += v;
+                }
+                (_, Some((price, v))) => {
+                    high = *price;
+                    volume += v;
+                }
+                (Some((price, v)), None) => {
+                    low = *price;
+                    volume += v;
+                }
+                (None, None) => break,
+            }
+        }
+
+        Some((low, high + self.row_size))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VolumeProfiles {
+    pub symbol: String,
+    pub timeframe: String,
+    pub visible: VolumeProfile,
+    pub session: VolumeProfile,
+    /// A range held more candles than one request returns, so a profile stops early.
+    pub truncated: bool,
+    /// Differs between fetches, the chart redraws the profiles when it changes.
+    pub revision: u64,
+}
+
+/// Builds the visible range profile from `start_time` and the profile of the current UTC day.
+pub async fn get_volume_profiles(
+    symbol: String,
+    timeframe: String,
+    decimals: u32,
+    start_time: u64,
+) -> Result<VolumeProfiles, String> {
+    let now = Utc::now();
+    let session_start = Utc
+        .with_ymd_and_hms(now.year(), now.month(), now.day(), 0, 0, 0)
+        .single()
+        .map(|t| t.timestamp_millis() as u64)
+        .unwrap_or(start_time);
+
+    let visible = get_candles_since(
+        &symbol,
+        profile_timeframe(&timeframe),
+        decimals,
+        start_time,
+        MAX_KLINES,
+    )
+    .await?;
+    let session = get_candles_since(
+        &symbol,
+        SESSION_TIMEFRAME,
+        decimals,
+        session_start,
+        MAX_KLINES,
+    )
+    .await?;
+
+    Ok(VolumeProfiles {
+        symbol,
+        timeframe,
+        visible: VolumeProfile::from_candles(&visible, tick_size(decimals)),
+        session: VolumeProfile::from_candles(&session, tick_size(decimals)),
+        truncated: visible.len() == MAX_KLINES as usize || session.len() == MAX_KLINES as usize,
+        revision: REVISION.fetch_add(1, Ordering::Relaxed),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn candle(low: Decimal, high: Decimal, volume: Decimal) -> Candle {
+        Candle {
+            open: low,
+            high,
+            low,
+            close: high,
+            volume,
+            open_time: 0,
+            close_time: 0,
+        }
+    }
+
+    fn profile() -> VolumeProfile {
+        VolumeProfile::from_candles(
+            &[
+                candle(dec!(100), dec!(102), dec!(4)),
+                candle(dec!(101), dec!(101.5), dec!(6)),
+                candle(dec!(102), dec!(104), dec!(2)),
+                candle(dec!(90), dec!(95), dec!(0)),
+            ],
+            Decimal::ONE,
+        )
+    }
+
+    #[test]
+    fn spreads_volume_over_the_rows_a_candle_covers() {
+        let profile = profile();
+
+        // 90..104 over 48 rows is under one tick, so rows are one tick tall.
+        assert_eq!(profile.row_size, Decimal::ONE);
+        assert_eq!(
+            profile
+                .rows
+                .iter()
+                .filter(|(_, volume)| !volume.is_zero())
+                .map(|(price, volume)| (*price, *volume))
+                .collect::<Vec<_>>(),
+            [
+                (dec!(100), dec!(2)),
+                (dec!(101), dec!(8)),
+                (dec!(102), dec!(1)),
+                (dec!(103), dec!(1)),
+            ]
+        );
+        assert_eq!(profile.max_volume(), dec!(8));
+
+        let coarse = VolumeProfile::from_candles(&[candle(dec!(0), dec!(1), dec!(1))], dec!(0.01));
+        assert_eq!(coarse.row_size, dec!(0.03));
+    }
+
+    #[test]
+    fn value_area_grows_from_the_poc_towards_more_volume() {
+        let profile = profile();
+
+        // 8 of 12 is short of 70%, row 100 holds more than row 102 and closes it.
+        assert_eq!(profile.poc(), Some(dec!(101)));
+        assert_eq!(profile.value_area(), Some((dec!(100), dec!(102))));
+
+        let empty = VolumeProfile::from_candles(&[], Decimal::ONE);
+        assert_eq!(empty.poc(), None);
+        assert_eq!(empty.value_area(), None);
+    }
+}
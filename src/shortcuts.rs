@@ -0,0 +1,274 @@
+use crate::ui::chart_export::CandleRange;
+use crate::{Message, State};
+use iced::keyboard::key::Named;
+use iced::keyboard::{self, Key};
+use iced::{Event, event, window};
+use juicy_lemon::config;
+use juicy_lemon::dataset::DataFormat;
+use juicy_lemon::graph::export::ExportFormat;
+use juicy_lemon::keys::{Action, Chord};
+use juicy_lemon::utils::text_match_score;
+
+/// Fewest candles zooming in leaves on screen.
+pub const MIN_VISIBLE_CANDLES: usize = 10;
+
+/// Entries the command palette lists at once.
+pub const COMMAND_LIMIT: usize = 12;
+
+#[derive(Debug, Clone)]
+pub struct Command {
+    pub label: String,
+    /// The bound chord, for actions that have one.
+    pub shortcut: Option<String>,
+    pub message: Message,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CommandPalette {
+    pub input: String,
+    pub matches: Vec<Command>,
+    pub selected: usize,
+}
+
+/// The keymap's name for `key`, `None` for keys it cannot bind like bare modifiers.
+fn key_name(key: &Key) -> Option<String> {
+    let name = match key {
+        Key::Character(c) => return Some(c.to_lowercase()),
+        Key::Named(Named::ArrowUp) => "up",
+        Key::Named(Named::ArrowDown) => "down",
+        Key::Named(Named::ArrowLeft) => "left",
+        Key::Named(Named::ArrowRight) => "right",
+        Key::Named(Named::Delete) => "delete",
+        Key::Named(Named::Backspace) => "backspace",
+        Key::Named(Named::Enter) => "enter",
+        Key::Named(Named::Escape) => "escape",
+        Key::Named(Named::Tab) => "tab",
+        Key::Named(Named::Space) => "space",
+        Key::Named(Named::Home) => "home",
+        Key::Named(Named::End) => "end",
+        Key::Named(Named::PageUp) => "pageup",
+        Key::Named(Named::PageDown) => "pagedown",
+        _ => return None,
+    };
+
+    Some(name.to_string())
+}
+
+/// Key presses no widget took, so typing into an input never fires a shortcut. Escape
+/// always comes through to close the command palette from inside its input.
+pub fn key_event(event: Event, status: event::Status, _window: window::Id) -> Option<Message> {
+    let Event::Keyboard(keyboard::Event::KeyPressed { key, modifiers, .. }) = event else {
+        return None;
+    };
+
+    let name = key_name(&key)?;
+
+    if status == event::Status::Captured && name != "escape" {
+        return None;
+    }
+
+    // A character already carries shift, `+` arrives as `+` and not as `shift+=`.
+    Some(Message::KeyPressed(Chord {
+        ctrl: modifiers.command(),
+        alt: modifiers.alt(),
+        shift: modifiers.shift() && !matches!(key, Key::Character(_)),
+        key: name,
+    }))
+}
+
+/// The watchlist entry next to the displayed one, the first entry when none is shown.
+fn neighbor_symbol(state: &State, forward: bool) -> Option<String> {
+    let symbols = &state.watchlist().symbols;
+    let current = state
+        .displayed_symbol
+        .as_ref()
+        .and_then(|displayed| symbols.iter().position(|s| s.symbol == displayed.symbol));
+
+    let index = match current {
+        Some(index) if forward => index + 1,
+        Some(index) => index.checked_sub(1)?,
+        None => 0,
+    };
+
+    symbols.get(index).map(|s| s.symbol.clone())
+}
+
+/// `None` when the action has nothing to act on, like removing with no symbol shown.
+pub fn action_message(state: &State, action: Action) -> Option<Message> {
+    match action {
+        Action::Timeframe(timeframe) => Some(Message::ChangeTimeframe(timeframe.to_string())),
+        Action::PreviousSymbol => neighbor_symbol(state, false).map(Message::SelectSymbol),
+        Action::NextSymbol => neighbor_symbol(state, true).map(Message::SelectSymbol),
+        Action::RemoveSymbol => state
+            .displayed_symbol
+            .as_ref()
+            .map(|s| Message::SymbolRemove(s.symbol.clone())),
+        Action::FocusSearch => Some(Message::FocusSearch),
+        Action::ZoomIn => Some(Message::ZoomIn),
+        Action::ZoomOut => Some(Message::ZoomOut),
+        Action::CommandPalette => Some(Message::OpenCommandPalette),
+    }
+}
+
+fn commands(state: &State, query: &str) -> Vec<Command> {
+    let mut commands = Vec::new();
+    let mut push = |label: String, shortcut: Option<String>, message: Message| {
+        commands.push(Command {
+            label,
+            shortcut,
+            message,
+        })
+    };
+
+    for (_, _, action) in config::get().keys.bindings() {
+        if action == Action::CommandPalette {
+            continue;
+        }
+
+        if let Some(message) = action_message(state, action) {
+            let shortcut = state.keymap.chord(action).map(|chord| chord.to_string());
+            push(action.label(), shortcut, message);
+        }
+    }
+
+    let toggles = [
+        (
+            state.show_volume_profile,
+            "volume profile",
+            Message::ToggleVolumeProfile(!state.show_volume_profile),
+        ),
+        (
+            state.show_depth_chart,
+            "depth chart",
+            Message::ToggleDepthChart(!state.show_depth_chart),
+        ),
+        (
+            state.show_tick_arrows,
+            "tick arrows",
+            Message::ToggleTickArrows(!state.show_tick_arrows),
+        ),
+        (
+            state.show_cache_debug,
+            "cache debug",
+            Message::ToggleCacheDebug(!state.show_cache_debug),
+        ),
+        (
+            state.show_paper_trading,
+            "paper trading",
+            Message::TogglePaperTrading(!state.show_paper_trading),
+        ),
+        (
+            state.show_portfolio,
+            "portfolio",
+            Message::TogglePortfolio(!state.show_portfolio),
+        ),
+        (
+            state.show_backtest,
+            "backtest",
+            Message::ToggleBacktest(!state.show_backtest),
+        ),
+    ];
+
+    for (shown, name, message) in toggles {
+        let verb = if shown { "Hide" } else { "Show" };
+        push(format!("{} {}", verb, name), None, message);
+    }
+
+    if state.chart().is_some() {
+        for (name, format) in [("PNG", ExportFormat::Png), ("SVG", ExportFormat::Svg)] {
+            push(
+                format!("Export chart as {}", name),
+                None,
+                Message::ExportChart(format),
+            );
+        }
+
+        for (scope, range) in [
+            ("visible", CandleRange::Visible),
+            ("all", CandleRange::Full),
+        ] {
+            for (name, format) in [
+                ("CSV", DataFormat::Csv),
+                ("JSON lines", DataFormat::JsonLines),
+                ("Parquet", DataFormat::Parquet),
+            ] {
+                push(
+                    format!("Export {} candles as {}", scope, name),
+                    None,
+                    Message::ExportCandles(format, range),
+                );
+            }
+        }
+    }
+
+    if state.chart().is_some() {
+        push("Run backtest".to_string(), None, Message::RunBacktest);
+    }
+
+    for order in &state.paper.orders {
+        push(
+            format!("Cancel paper order: {}", order),
+            None,
+            Message::CancelPaperOrder(order.id),
+        );
+    }
+
+    for name in state.watchlist_names() {
+        if name != state.watchlist().name {
+            push(
+                format!("Watchlist: {}", name),
+                None,
+                Message::SwitchWatchlist(name),
+            );
+        }
+    }
+
+    for (name, _) in &state.palettes {
+        push(
+            format!("Theme: {}", name),
+            None,
+            Message::SelectTheme(name.clone()),
+        );
+    }
+
+    for symbol in &state.watchlist().symbols {
+        push(
+            format!("Show {}", symbol.symbol),
+            None,
+            Message::SelectSymbol(symbol.symbol.clone()),
+        );
+    }
+
+    // Thousands of instruments would bury everything else before anything is typed.
+    if !query.trim().is_empty() {
+        let listed = state.watchlist().symbol_names();
+
+        for instrument in state.instruments.iter().filter(|i| i.is_trading()) {
+            if !listed.contains(&instrument.symbol) {
+                push(
+                    format!("Add {}", instrument.symbol),
+                    None,
+                    Message::AddSymbol(instrument.symbol.clone()),
+                );
+            }
+        }
+    }
+
+    commands
+}
+
+/// The best `COMMAND_LIMIT` commands for `query`, ties keep their listing order.
+pub fn matching_commands(state: &State, query: &str) -> Vec<Command> {
+    let mut ranked = commands(state, query)
+        .into_iter()
+        .filter_map(|command| text_match_score(&command.label, query).map(|score| (score, command)))
+        .collect::<Vec<_>>();
+
+    ranked.sort_by_key(|(score, _)| *score);
+
+    ranked
+        .into_iter()
+        .take(COMMAND_LIMIT)
+        .map(|(_, command)| command)
+        .collect()
+}
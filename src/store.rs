@@ -0,0 +1,430 @@
+use crate::candle::{Candle, get_candles, get_candles_since, merge_candles, timeframe_millis};
+use crate::config;
+use rust_decimal::Decimal;
+use std::collections::VecDeque;
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const GAP_PAGE_SIZE: u32 = 1000;
+const MAX_GAP_PAGES: usize = 10;
+
+/// Enough of the end of a file to hold its last line.
+const TAIL_BYTES: u64 = 512;
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn to_line(candle: &Candle) -> String {
+    format!(
+        "{},{},{},{},{},{},{}\n",
+        candle.open_time,
+        candle.close_time,
+        candle.open,
+        candle.high,
+        candle.low,
+        candle.close,
+        candle.volume
+    )
+}
+
+fn from_line(line: &str) -> Option<Candle> {
+    let mut fields = line.trim().split(',');
+
+    Some(Candle {
+        open_time: fields.next()?.parse().ok()?,
+        close_time: fields.next()?.parse().ok()?,
+        open: fields.next()?.parse::<Decimal>().ok()?,
+        high: fields.next()?.parse::<Decimal>().ok()?,
+        low: fields.next()?.parse::<Decimal>().ok()?,
+        close: fields.next()?.parse::<Decimal>().ok()?,
+        volume: fields.next()?.parse::<Decimal>().ok()?,
+    })
+}
+
+/// Open time of the last stored candle, read from the end of the file only.
+fn last_open_time(path: &Path) -> Option<u64> {
+    let mut file = File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(TAIL_BYTES)))
+        .ok()?;
+
+    let mut tail = String::new();
+    file.read_to_string(&mut tail).ok()?;
+    tail.lines().rev().find_map(from_line).map(|c| c.open_time)
+}
+
+/// What an append wrote, and the files it evicted to stay under the limit.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Appended {
+    pub candles: usize,
+    pub evicted: Vec<PathBuf>,
+}
+
+/// Append-only file per symbol and timeframe holding closed candles only, so the
+/// still open bar never has to be rewritten. Files unused the longest are evicted
+/// once the directory passes `max_bytes`. Every method touches the disk, the apps call
+/// them from tasks.
+#[derive(Debug, Clone)]
+pub struct CandleStore {
+    pub dir: PathBuf,
+    pub max_bytes: u64,
+    /// Shared by clones, so appends from concurrent tasks take turns.
+    lock: Arc<Mutex<()>>,
+}
+
+impl CandleStore {
+    pub fn new(dir: PathBuf, max_bytes: u64) -> Self {
+        Self {
+            dir,
+            max_bytes,
+            lock: Arc::default(),
+        }
+    }
+
+    pub fn from_config() -> Self {
+        let cache = &config::get().cache;
+
+        Self::new(cache.candles_dir.clone(), cache.disk_mb * 1024 * 1024)
+    }
+
+    fn path(&self, symbol: &str, timeframe: &str) -> PathBuf {
+        self.dir.join(format!("{}_{}.csv", symbol, timeframe))
+    }
+
+    pub fn read(&self, symbol: &str, timeframe: &str) -> Vec<Candle> {
+        fs::read_to_string(self.path(symbol, timeframe))
+            .map(|content| content.lines().filter_map(from_line).collect())
+            .unwrap_or_default()
+    }
+
+    /// Everything stored for the series with `recent` merged in, like the bars in memory
+    /// that are newer than the file or still forming.
+    pub fn history(&self, symbol: &str, timeframe: &str, recent: &[Candle]) -> Vec<Candle> {
+        let mut candles = VecDeque::from(self.read(symbol, timeframe));
+
+        match merge_candles(&mut candles, recent, timeframe, usize::MAX) {
+            Ok(_) => candles.into(),
+            Err(_) => recent.to_vec(),
+        }
+    }
+
+    /// Writes closed candles newer than the last stored one. Candles that do not continue
+    /// the file are left out, a hole on disk would never be fetched again.
+    pub fn append(
+        &self,
+        symbol: &str,
+        timeframe: &str,
+        candles: &[Candle],
+    ) -> Result<Appended, String> {
+        let _guard = self.lock.lock().unwrap_or_else(|err| err.into_inner());
+        let path = self.path(symbol, timeframe);
+        let last_stored = last_open_time(&path);
+
+        let now = now_millis();
+        let lines = candles
+            .iter()
+            .filter(|c| last_stored.is_none_or(|last| c.open_time > last) && c.close_time < now)
+            .collect::<Vec<_>>();
+
+        let Some(first) = lines.first() else {
+            return Ok(Appended::default());
+        };
+        if let (Some(last), Some(interval)) = (last_stored, timeframe_millis(timeframe))
+            && first.open_time > last + interval
+        {
+            return Ok(Appended::default());
+        }
+
+        fs::create_dir_all(&self.dir)
+            .and_then(|_| {
+                let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+                let content = lines.iter().map(|c| to_line(c)).collect::<String>();
+                file.write_all(content.as_bytes())
+            })
+            .map_err(|err| format!("Failed to store candles in {}: {}", path.display(), err))?;
+
+        Ok(Appended {
+            candles: lines.len(),
+            evicted: self.enforce_limit(&path)?,
+        })
+    }
+
+    /// Forgets a series, for history that can no longer be joined to the latest candles.
+    fn clear(&self, symbol: &str, timeframe: &str) {
+        let _guard = self.lock.lock().unwrap_or_else(|err| err.into_inner());
+        let _ = fs::remove_file(self.path(symbol, timeframe));
+    }
+
+    /// Drops whole files least recently written first, then the oldest lines of `keep` until
+    /// the directory fits. Returns the files dropped, `keep` too when no line of it fits.
+    fn enforce_limit(&self, keep: &PathBuf) -> Result<Vec<PathBuf>, String> {
+        let mut evicted = Vec::new();
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Ok(evicted);
+        };
+
+        let mut files = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                let modified = metadata.modified().ok()?;
+                metadata
+                    .is_file()
+                    .then(|| (entry.path(), metadata.len(), modified))
+            })
+            .collect::<Vec<_>>();
+
+        let mut total: u64 = files.iter().map(|(_, size, _)| size).sum();
+        files.sort_by_key(|(_, _, modified)| *modified);
+
+        for (path, size, _) in &files {
+            if total <= self.max_bytes {
+                return Ok(evicted);
+            }
+
+            if path == keep {
+                continue;
+            }
+
+            if fs::remove_file(path).is_ok() {
+                total -= size;
+                evicted.push(path.clone());
+            }
+        }
+
+        if total > self.max_bytes
+            && let Ok(content) = fs::read_to_string(keep)
+        {
+            let budget = self
+                .max_bytes
+                .saturating_sub(total.saturating_sub(content.len() as u64));
+            let mut size = 0;
+            let mut kept = content
+                .lines()
+                .rev()
+                .take_while(|line| {
+                    size += line.len() as u64 + 1;
+                    size <= budget
+                })
+                .collect::<Vec<_>>();
+
+            if kept.is_empty() {
+                fs::remove_file(keep)
+                    .map_err(|err| format!("Failed to remove {}: {}", keep.display(), err))?;
+                evicted.push(keep.clone());
+            } else {
+                kept.reverse();
+                fs::write(keep, kept.join("\n") + "\n")
+                    .map_err(|err| format!("Failed to shrink {}: {}", keep.display(), err))?;
+            }
+        }
+
+        Ok(evicted)
+    }
+}
+
+/// Runs disk work on the blocking pool, so it never holds up the executor.
+async fn blocking<T: Send + 'static>(
+    work: impl FnOnce() -> T + Send + 'static,
+) -> Result<T, String> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|err| format!("Candle store task failed: {}", err))
+}
+
+/// Appends fetched candles off the UI thread, see `CandleStore::append`.
+pub async fn store_candles(
+    store: CandleStore,
+    symbol: String,
+    timeframe: String,
+    candles: Vec<Candle>,
+) -> Result<Appended, String> {
+    blocking(move || store.append(&symbol, &timeframe, &candles)).await?
+}
+
+/// Candles already on disk, served before anything is requested from Binance.
+pub async fn get_stored_candles(
+    store: CandleStore,
+    symbol: String,
+    timeframe: String,
+) -> Vec<Candle> {
+    let candles = blocking(move || store.read(&symbol, &timeframe))
+        .await
+        .unwrap_or_default();
+    candles[candles.len().saturating_sub(config::get().history.candles)..].to_vec()
+}
+
+/// The whole stored history of a series, see `CandleStore::history`.
+pub async fn get_stored_history(
+    store: CandleStore,
+    symbol: String,
+    timeframe: String,
+    recent: Vec<Candle>,
+) -> Vec<Candle> {
+    blocking(move || store.history(&symbol, &timeframe, &recent))
+        .await
+        .unwrap_or_default()
+}
+
+/// Stored history plus only the bars since the last stored one. A gap too long to page
+/// through falls back to the latest candles and drops the stored ones.
+pub async fn get_candles_with_store(
+    store: CandleStore,
+    symbol: String,
+    timeframe: String,
+    decimals: u32,
+) -> Result<Vec<Candle>, String> {
+    let mut candles = {
+        let (store, symbol, timeframe) = (store.clone(), symbol.clone(), timeframe.clone());
+        blocking(move || store.read(&symbol, &timeframe)).await?
+    };
+
+    let Some(last) = candles.last() else {
+        return get_candles(&symbol, &timeframe, decimals).await;
+    };
+
+    let mut start_time = last.open_time + 1;
+
+    for _ in 0..MAX_GAP_PAGES {
+        let page =
+            get_candles_since(&symbol, &timeframe, decimals, start_time, GAP_PAGE_SIZE).await?;
+        let full = page.len() == GAP_PAGE_SIZE as usize;
+
+        if let Some(last) = page.last() {
+            start_time = last.open_time + 1;
+        }
+        candles.extend(page);
+
+        if !full {
+            return Ok(
+                candles[candles.len().saturating_sub(config::get().history.candles)..].to_vec(),
+            );
+        }
+    }
+
+    // Too far behind to page through, the stored bars would sit before a hole for good.
+    {
+        let (symbol, timeframe) = (symbol.clone(), timeframe.clone());
+        blocking(move || store.clear(&symbol, &timeframe)).await?;
+    }
+    get_candles(&symbol, &timeframe, decimals).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::candles;
+    use std::time::Duration;
+
+    const MINUTE: u64 = 60_000;
+
+    /// A store in a directory of its own, so concurrent test runs never share files.
+    fn store(name: &str, max_bytes: u64) -> CandleStore {
+        let dir =
+            std::env::temp_dir().join(format!("juicy-lemon-store-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        CandleStore::new(dir, max_bytes)
+    }
+
+    #[test]
+    fn lines_read_back_as_written() {
+        let candle = candles(7..8, MINUTE).remove(0);
+
+        assert_eq!(from_line(&to_line(&candle)), Some(candle));
+        assert_eq!(from_line("1,2,3"), None);
+        assert_eq!(from_line("a,b,c,d,e,f,g"), None);
+    }
+
+    #[test]
+    fn history_adds_the_recent_bars_to_the_stored_ones() {
+        let store = store("history", u64::MAX);
+        store
+            .append("BTCUSDT", "1m", &candles(0..5, MINUTE))
+            .unwrap();
+
+        let history = store.history("BTCUSDT", "1m", &candles(3..8, MINUTE));
+        assert_eq!(history, candles(0..8, MINUTE));
+
+        let history = store.history("ETHUSDT", "1m", &candles(3..8, MINUTE));
+        assert_eq!(history, candles(3..8, MINUTE));
+        fs::remove_dir_all(&store.dir).unwrap();
+    }
+
+    #[test]
+    fn appends_only_what_continues_the_file() {
+        let store = store("append", u64::MAX);
+
+        let append = |range| {
+            store
+                .append("BTCUSDT", "1m", &candles(range, MINUTE))
+                .unwrap()
+                .candles
+        };
+
+        assert_eq!(append(0..3), 3);
+        assert_eq!(append(1..5), 2);
+
+        // Bars after a hole are not written, the missing ones can still be.
+        assert_eq!(append(8..10), 0);
+        assert_eq!(append(5..10), 5);
+
+        assert_eq!(store.read("BTCUSDT", "1m"), candles(0..10, MINUTE));
+        fs::remove_dir_all(&store.dir).unwrap();
+    }
+
+    #[test]
+    fn evicts_the_least_recently_written_files() {
+        let file = candles(0..10, MINUTE)
+            .iter()
+            .map(to_line)
+            .collect::<String>();
+        let size = file.len() as u64;
+        let store = store("evict", size * 5 / 2);
+        let now = SystemTime::now();
+
+        for (i, symbol) in ["OLD", "NEWER"].iter().enumerate() {
+            store.append(symbol, "1m", &candles(0..10, MINUTE)).unwrap();
+            File::options()
+                .append(true)
+                .open(store.path(symbol, "1m"))
+                .unwrap()
+                .set_modified(now - Duration::from_secs(100 - i as u64))
+                .unwrap();
+        }
+
+        // Over the limit: the oldest file goes first, the one written is always kept.
+        let appended = store
+            .append("LATEST", "1m", &candles(0..10, MINUTE))
+            .unwrap();
+        assert_eq!(appended.evicted, vec![store.path("OLD", "1m")]);
+
+        assert!(store.read("OLD", "1m").is_empty());
+        assert_eq!(store.read("NEWER", "1m").len(), 10);
+        assert_eq!(store.read("LATEST", "1m").len(), 10);
+
+        // A file too big on its own keeps the newest lines that fit.
+        let big = CandleStore::new(store.dir.clone(), size - 1);
+        big.append("LATEST", "1m", &candles(10..12, MINUTE))
+            .unwrap();
+
+        assert!(store.read("NEWER", "1m").is_empty());
+        assert_eq!(store.read("LATEST", "1m"), candles(3..12, MINUTE));
+        assert!(fs::metadata(store.path("LATEST", "1m")).unwrap().len() < size);
+
+        // Not even one line fits, so nothing is written back.
+        let tiny = CandleStore::new(store.dir.clone(), 1);
+        let appended = tiny
+            .append("LATEST", "1m", &candles(12..13, MINUTE))
+            .unwrap();
+        assert_eq!(appended.evicted, vec![store.path("LATEST", "1m")]);
+        assert!(!store.path("LATEST", "1m").exists());
+        fs::remove_dir_all(&store.dir).unwrap();
+    }
+}
@@ -1,6 +1,7 @@
-use iced::window::close;
+use crate::config;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use std::fs;
 
 #[derive(Deserialize, Debug)]
 pub struct Filter {
@@ -11,12 +12,20 @@ pub struct Filter {
     pub tick_size: Option<String>,
 }
 
+pub const TRADING_STATUS: &str = "TRADING";
+
 #[derive(Deserialize, Debug)]
 pub struct Instrument {
     status: String,
-    #[serde(rename = "baseAssetPrecision")]
-    base_asset_precision: i32,
     pub symbol: String,
+    #[serde(rename = "baseAsset")]
+    pub base_asset: String,
+    #[serde(rename = "quoteAsset")]
+    pub quote_asset: String,
+    #[serde(default)]
+    pub permissions: Vec<String>,
+    #[serde(rename = "permissionSets", default)]
+    pub permission_sets: Vec<Vec<String>>,
     pub filters: Vec<Filter>,
 }
 
@@ -37,50 +46,88 @@ pub struct Symbol {
     pub price: Option<Decimal>,
     pub decimals: u32,
     pub timeframe: String,
+    #[serde(default)]
+    pub base_asset: String,
+    #[serde(default)]
+    pub quote_asset: String,
+    #[serde(default)]
+    pub status: String,
+    #[serde(default)]
+    pub permissions: Vec<String>,
 }
 
 impl Symbol {
-    pub fn new(symbol: String, price: Option<Decimal>, decimals: u32, timeframe: String) -> Self {
-        Self {
-            symbol,
-            price,
-            decimals,
-            timeframe,
+    pub fn is_trading(&self) -> bool {
+        self.status == TRADING_STATUS
+    }
+
+    /// Caches written before the metadata was added only carry the symbol and decimals.
+    pub fn has_metadata(&self) -> bool {
+        !self.base_asset.is_empty() && !self.quote_asset.is_empty() && !self.status.is_empty()
+    }
+}
+
+/// Instruments saved by an earlier run, `None` when missing or written before the metadata was added.
+pub fn read_cached_symbols() -> Option<Vec<Symbol>> {
+    let symbols: Vec<Symbol> = fs::read_to_string(&config::get().cache.symbols_file)
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())?;
+
+    (!symbols.is_empty() && symbols.iter().all(|s| s.has_metadata())).then_some(symbols)
+}
+
+pub fn write_cached_symbols(symbols: &[Symbol]) {
+    match serde_json::to_string_pretty(symbols) {
+        Ok(json) => {
+            if let Err(e) = fs::write(&config::get().cache.symbols_file, json) {
+                eprintln!("Failed to write instruments to file: {}", e);
+            }
         }
+        Err(_) => eprintln!("Failed to serialize instruments to JSON"),
     }
 }
 
 pub async fn get_symbols() -> Result<Vec<Symbol>, String> {
-    match reqwest::get("https://api.binance.com/api/v3/exchangeInfo").await {
+    match reqwest::get(format!("{}/api/v3/exchangeInfo", config::get().api.rest())).await {
         Ok(response) => {
-            let json = response.json::<Response>().await.unwrap();
+            let json = response
+                .json::<Response>()
+                .await
+                .map_err(|err| format!("Cannot read instruments: {}", err))?;
             let symbols = json
                 .symbols
                 .into_iter()
                 .filter_map(|i| {
-                    if i.status == "TRADING" {
-                        let price_filter = i.filters.iter().find(|f| f.filter_type == "PRICE_FILTER").unwrap();
-                        let tick_size = price_filter.tick_size.clone().unwrap();
-                        let normalized = tick_size.parse::<f32>().unwrap();
-                        let number_str = normalized.to_string();
-                        let parts = number_str.split('.');
-                        let decimals = parts.last().unwrap().len() as u32;
-
-                        Some(Symbol {
-                            symbol: i.symbol.clone(),
-                            decimals,
-                            price: None,
-                            timeframe: "1m".to_string(),
-                        })
-                    } else {
-                        None
+                    let price_filter = i.filters.iter().find(|f| f.filter_type == "PRICE_FILTER")?;
+                    let tick_size = price_filter.tick_size.clone()?;
+                    let normalized = tick_size.parse::<f32>().ok()?;
+                    let number_str = normalized.to_string();
+                    let mut parts = number_str.split('.');
+                    let decimals = parts.next_back()?.len() as u32;
+
+                    let mut permissions = i.permissions;
+                    for permission in i.permission_sets.into_iter().flatten() {
+                        if !permissions.contains(&permission) {
+                            permissions.push(permission);
+                        }
                     }
+
+                    Some(Symbol {
+                        symbol: i.symbol,
+                        price: None,
+                        decimals,
+                        timeframe: config::get().defaults.timeframe.clone(),
+                        base_asset: i.base_asset,
+                        quote_asset: i.quote_asset,
+                        status: i.status,
+                        permissions,
+                    })
                 })
                 .collect();
             Ok(symbols)
         }
         Err(err) => {
-            println!("Error: {}", err);
+            eprintln!("Error: {}", err);
             Err(String::from("Cannot fetch instruments"))
         }
     }
@@ -88,7 +135,8 @@ pub async fn get_symbols() -> Result<Vec<Symbol>, String> {
 
 pub async fn fetch_symbol_prices(symbols: Vec<String>) -> Result<Vec<SymbolWithPrice>, String> {
     let url = format!(
-        "https://www.binance.com/api/v3/ticker/price?symbols=[{}]",
+        "{}/api/v3/ticker/price?symbols=[{}]",
+        config::get().api.price(),
         symbols
             .iter()
             .map(|s| format!("\"{}\"", s))
@@ -99,23 +147,21 @@ pub async fn fetch_symbol_prices(symbols: Vec<String>) -> Result<Vec<SymbolWithP
     match reqwest::get(&url).await {
         Ok(response) => match response.text().await {
             Ok(body) => {
-                println!("Response body: {}", body);
-
                 match serde_json::from_str::<Vec<SymbolWithPrice>>(&body) {
                     Ok(json) => Ok(json),
                     Err(err) => {
-                        println!("Error parsing JSON: {}", err);
+                        eprintln!("Error parsing JSON: {}", err);
                         Err(String::from("Failed to parse JSON"))
                     }
                 }
             }
             Err(err) => {
-                println!("Error reading response body: {}", err);
+                eprintln!("Error reading response body: {}", err);
                 Err(String::from("Failed to read response body"))
             }
         },
         Err(err) => {
-            println!("Error: {}", err);
+            eprintln!("Error: {}", err);
             Err(String::from("Cannot fetch price"))
         }
     }
@@ -0,0 +1,227 @@
+use serde::de::{self, Deserializer};
+use serde::ser::Serializer;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use toml::{Table, Value};
+
+/// An sRGB color, written as `#rrggbb` in palette files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rgb(pub u8, pub u8, pub u8);
+
+impl Rgb {
+    pub fn parse(hex: &str) -> Result<Self, String> {
+        let digits = hex
+            .strip_prefix('#')
+            .filter(|d| d.len() == 6 && d.chars().all(|c| c.is_ascii_hexdigit()))
+            .ok_or_else(|| format!("expected a #rrggbb color, got {:?}", hex))?;
+
+        let channel = |i: usize| u8::from_str_radix(&digits[i..i + 2], 16).unwrap_or_default();
+        Ok(Rgb(channel(0), channel(2), channel(4)))
+    }
+
+    pub fn hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.0, self.1, self.2)
+    }
+}
+
+impl Serialize for Rgb {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.hex())
+    }
+}
+
+impl<'de> Deserialize<'de> for Rgb {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex = String::deserialize(deserializer)?;
+        Rgb::parse(&hex).map_err(de::Error::custom)
+    }
+}
+
+/// Every color the app draws with, so no widget or chart layer picks its own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Palette {
+    pub background: Rgb,
+    /// Inputs and dropdown menus.
+    pub surface: Rgb,
+    /// Buttons and the highlighted menu entry.
+    pub raised: Rgb,
+    pub text: Rgb,
+    /// Placeholders and secondary labels.
+    pub muted: Rgb,
+    pub grid: Rgb,
+    /// Chart axis lines and tick marks.
+    pub axis: Rgb,
+    pub border: Rgb,
+    /// The displayed symbol's row.
+    pub selection: Rgb,
+    /// Accent for widgets and the volume profile.
+    pub primary: Rgb,
+    /// Key levels like the session VPOC.
+    pub highlight: Rgb,
+    pub bull: Rgb,
+    pub bear: Rgb,
+}
+
+pub const DARK: Palette = Palette {
+    background: Rgb(3, 7, 18),
+    surface: Rgb(3, 7, 18),
+    raised: Rgb(50, 50, 50),
+    text: Rgb(249, 250, 251),
+    muted: Rgb(153, 153, 153),
+    grid: Rgb(51, 51, 51),
+    axis: Rgb(249, 250, 251),
+    border: Rgb(31, 41, 55),
+    selection: Rgb(109, 40, 217),
+    primary: Rgb(77, 153, 230),
+    highlight: Rgb(242, 191, 51),
+    bull: Rgb(66, 149, 137),
+    bear: Rgb(252, 79, 111),
+};
+
+pub const LIGHT: Palette = Palette {
+    background: Rgb(255, 255, 255),
+    surface: Rgb(243, 244, 246),
+    raised: Rgb(229, 231, 235),
+    text: Rgb(17, 24, 39),
+    muted: Rgb(107, 114, 128),
+    grid: Rgb(229, 231, 235),
+    axis: Rgb(55, 65, 81),
+    border: Rgb(209, 213, 219),
+    selection: Rgb(196, 181, 253),
+    primary: Rgb(37, 99, 235),
+    highlight: Rgb(217, 119, 6),
+    bull: Rgb(22, 128, 110),
+    bear: Rgb(220, 38, 38),
+};
+
+pub const HIGH_CONTRAST: Palette = Palette {
+    background: Rgb(0, 0, 0),
+    surface: Rgb(0, 0, 0),
+    raised: Rgb(64, 64, 64),
+    text: Rgb(255, 255, 255),
+    muted: Rgb(200, 200, 200),
+    grid: Rgb(90, 90, 90),
+    axis: Rgb(255, 255, 255),
+    border: Rgb(255, 255, 255),
+    selection: Rgb(0, 70, 200),
+    primary: Rgb(0, 191, 255),
+    highlight: Rgb(255, 255, 0),
+    bull: Rgb(0, 230, 118),
+    bear: Rgb(255, 64, 64),
+};
+
+pub const BUILTIN_PALETTES: [(&str, Palette); 3] = [
+    ("dark", DARK),
+    ("light", LIGHT),
+    ("high-contrast", HIGH_CONTRAST),
+];
+
+pub fn builtin(name: &str) -> Option<Palette> {
+    BUILTIN_PALETTES
+        .iter()
+        .find(|(builtin, _)| *builtin == name)
+        .map(|(_, palette)| *palette)
+}
+
+impl Palette {
+    /// A palette file sets any of the colors, the rest come from `base`, a built-in
+    /// palette name, or from the dark palette when there is none.
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let mut overrides = content.parse::<Table>().map_err(|err| err.to_string())?;
+
+        let base = match overrides.remove("base") {
+            Some(Value::String(name)) => builtin(&name).ok_or_else(|| {
+                format!(
+                    "unknown base palette {:?}, expected one of {}",
+                    name,
+                    BUILTIN_PALETTES.map(|(name, _)| name).join(", ")
+                )
+            })?,
+            Some(other) => return Err(format!("base must be a palette name, got {}", other)),
+            None => DARK,
+        };
+
+        let mut table = Table::try_from(base).map_err(|err| err.to_string())?;
+        table.extend(overrides);
+
+        table
+            .try_into()
+            .map_err(|err: toml::de::Error| err.message().to_string())
+    }
+}
+
+/// The built-in palettes followed by every `<name>.toml` in `dir`, a file named like a
+/// built-in one replaces it. A missing directory just means no custom palettes.
+pub fn load_palettes(dir: &Path) -> Result<Vec<(String, Palette)>, String> {
+    let mut palettes = BUILTIN_PALETTES
+        .iter()
+        .map(|(name, palette)| (name.to_string(), *palette))
+        .collect::<Vec<_>>();
+
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Ok(palettes);
+    };
+
+    let mut paths = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .collect::<Vec<_>>();
+    paths.sort();
+
+    for path in paths {
+        let Some(name) = path.file_stem().map(|s| s.to_string_lossy().to_string()) else {
+            continue;
+        };
+
+        let content = fs::read_to_string(&path)
+            .map_err(|err| format!("Cannot read {}: {}", path.display(), err))?;
+        let palette =
+            Palette::parse(&content).map_err(|err| format!("{}: {}", path.display(), err))?;
+
+        match palettes.iter_mut().find(|(existing, _)| *existing == name) {
+            Some(existing) => existing.1 = palette,
+            None => palettes.push((name, palette)),
+        }
+    }
+
+    Ok(palettes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn palette_files_override_their_base() {
+        let palette = Palette::parse("base = \"light\"\nbull = \"#00AA00\"").unwrap();
+
+        assert_eq!(palette.bull, Rgb(0, 170, 0));
+        assert_eq!(palette.bear, LIGHT.bear);
+        assert_eq!(Palette::parse("").unwrap(), DARK);
+
+        let shipped = Palette::parse(include_str!("../themes/solarized-light.toml")).unwrap();
+        assert_eq!(shipped.bull, Rgb(0x2a, 0xa1, 0x98));
+    }
+
+    #[test]
+    fn palette_errors_name_the_problem() {
+        let err = Palette::parse("bull = \"green\"").unwrap_err();
+        assert!(
+            err.contains("expected a #rrggbb color, got \"green\""),
+            "{}",
+            err
+        );
+
+        let err = Palette::parse("bul = \"#00aa00\"").unwrap_err();
+        assert!(err.starts_with("unknown field `bul`"), "{}", err);
+
+        let err = Palette::parse("base = \"sepia\"").unwrap_err();
+        assert!(
+            err.contains("expected one of dark, light, high-contrast"),
+            "{}",
+            err
+        );
+    }
+}
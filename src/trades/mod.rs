@@ -0,0 +1,87 @@
+use crate::config;
+use rust_decimal::Decimal;
+use serde::Deserialize;
+use std::collections::VecDeque;
+
+pub const TRADE_FEED_SIZE: usize = 100;
+
+/// `<symbol>@aggTrade` event or `/api/v3/aggTrades` entry, both share the short keys.
+#[derive(Deserialize, Debug, Clone)]
+pub struct AggTrade {
+    #[serde(rename = "a")]
+    pub id: u64,
+    #[serde(rename = "p")]
+    pub price: Decimal,
+    #[serde(rename = "q")]
+    pub quantity: Decimal,
+    #[serde(rename = "T")]
+    pub time: u64,
+    #[serde(rename = "m")]
+    pub buyer_maker: bool,
+}
+
+impl AggTrade {
+    pub fn notional(&self) -> Decimal {
+        self.price * self.quantity
+    }
+}
+
+/// Ring buffer of the latest trades, older ones fall off the front.
+#[derive(Debug, Clone)]
+pub struct TradeFeed {
+    pub symbol: String,
+    trades: VecDeque<AggTrade>,
+}
+
+impl TradeFeed {
+    pub fn new(symbol: String) -> Self {
+        Self {
+            symbol,
+            trades: VecDeque::with_capacity(TRADE_FEED_SIZE),
+        }
+    }
+
+    pub fn push(&mut self, trade: AggTrade) {
+        if self.trades.back().is_some_and(|last| last.id >= trade.id) {
+            return;
+        }
+
+        if self.trades.len() == TRADE_FEED_SIZE {
+            self.trades.pop_front();
+        }
+
+        self.trades.push_back(trade);
+    }
+
+    /// Fills the room left in the buffer with trades older than the first streamed one.
+    pub fn backfill(&mut self, trades: Vec<AggTrade>) {
+        let oldest = self.trades.front().map(|t| t.id).unwrap_or(u64::MAX);
+
+        for trade in trades.into_iter().rev().filter(|t| t.id < oldest) {
+            if self.trades.len() == TRADE_FEED_SIZE {
+                break;
+            }
+
+            self.trades.push_front(trade);
+        }
+    }
+
+    pub fn latest(&self) -> impl Iterator<Item = &AggTrade> {
+        self.trades.iter().rev()
+    }
+}
+
+pub async fn get_agg_trades(symbol: String) -> Result<Vec<AggTrade>, String> {
+    let url = format!(
+        "{}/api/v3/aggTrades?symbol={symbol}&limit={TRADE_FEED_SIZE}",
+        config::get().api.rest()
+    );
+
+    match reqwest::get(&url).await {
+        Ok(response) => response
+            .json::<Vec<AggTrade>>()
+            .await
+            .map_err(|err| format!("Failed to parse trades: {}", err)),
+        Err(err) => Err(format!("Failed to fetch trades: {}", err)),
+    }
+}
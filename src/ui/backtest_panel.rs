@@ -0,0 +1,136 @@
+use crate::ui::{action_button, input_style};
+use crate::{Message, State};
+use chrono::{Local, TimeZone};
+use iced::alignment::Vertical;
+use iced::widget::{Column, Row, canvas, text, text_input};
+use iced::{Color, Element, Fill, Padding, widget};
+use juicy_lemon::colors::color;
+use juicy_lemon::graph::portfolio::ValueChart;
+use juicy_lemon::theme::Palette;
+use rust_decimal::Decimal;
+
+const COLUMNS: [&str; 6] = [
+    "Entry",
+    "Entry price",
+    "Exit",
+    "Exit price",
+    "P&L",
+    "Return",
+];
+
+/// Most recent trades listed, all of them stay marked on the chart.
+const LISTED_TRADES: usize = 20;
+
+fn pnl_color(palette: &Palette, pnl: Decimal) -> Color {
+    if pnl.is_sign_negative() {
+        color(palette.bear)
+    } else {
+        color(palette.bull)
+    }
+}
+
+fn cell<'a>(content: String, cell_color: Option<Color>) -> Element<'a, Message> {
+    text(content)
+        .size(12)
+        .color_maybe(cell_color)
+        .width(Fill)
+        .into()
+}
+
+fn time(millis: u64) -> String {
+    Local
+        .timestamp_millis_opt(millis as i64)
+        .single()
+        .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_default()
+}
+
+/// The strategy form, then the latest report on the displayed candles: its figures,
+/// equity curve and trades.
+pub fn render_backtest_panel(state: &State) -> Column<'_, Message> {
+    let palette = state.palette();
+
+    let form = widget::row![
+        text("Backtest").size(14),
+        text_input("sma:10,30 or rsi:14,30,70", &state.backtest_strategy_input)
+            .on_input(Message::BacktestStrategyInput)
+            .on_submit(Message::RunBacktest)
+            .style(move |_, _| input_style(&palette))
+            .size(12)
+            .width(200),
+        action_button(palette, "Run", Message::RunBacktest),
+    ]
+    .spacing(10)
+    .align_y(Vertical::Center);
+
+    let panel = widget::column![form]
+        .spacing(6)
+        .width(Fill)
+        .padding(Padding {
+            top: 0.0,
+            right: 10.0,
+            bottom: 10.0,
+            left: 10.0,
+        });
+
+    let Some((timeframe, report)) = &state.backtest else {
+        return panel.push(
+            text("Runs the strategy over the candles on the chart, trading at each next open")
+                .size(12),
+        );
+    };
+
+    let mut panel = panel.push(
+        text(format!(
+            "{} {}: {}",
+            report.symbol,
+            timeframe,
+            report.summary().trim_end().replace('\n', ", ")
+        ))
+        .size(12),
+    );
+
+    panel = panel.push(
+        canvas(ValueChart {
+            points: (&report.equity, &[]),
+            valuation: state
+                .displayed_symbol
+                .as_ref()
+                .filter(|s| s.symbol == report.symbol)
+                .map(|s| s.quote_asset.as_str())
+                .unwrap_or_default(),
+            palette,
+        })
+        .width(Fill)
+        .height(120),
+    );
+
+    if report.trades.is_empty() {
+        return panel.push(text("No trades").size(12));
+    }
+
+    let mut table = Column::new()
+        .spacing(2)
+        .push(COLUMNS.iter().fold(Row::new(), |row, title| {
+            row.push(cell(title.to_string(), Some(color(palette.muted))))
+        }));
+
+    for trade in report.trades.iter().rev().take(LISTED_TRADES) {
+        table = table.push(widget::row![
+            cell(time(trade.entry_time), None),
+            cell(trade.entry_price.round_dp(8).normalize().to_string(), None),
+            cell(time(trade.exit_time), None),
+            cell(trade.exit_price.round_dp(8).normalize().to_string(), None),
+            cell(
+                trade.pnl.round_dp(2).to_string(),
+                Some(pnl_color(&palette, trade.pnl))
+            ),
+            cell(
+                format!("{}%", trade.return_pct.round_dp(2)),
+                Some(pnl_color(&palette, trade.pnl))
+            ),
+        ]);
+    }
+
+    panel.push(table)
+}
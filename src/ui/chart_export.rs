@@ -0,0 +1,71 @@
+use crate::ui::{action_button, input_style, menu_style};
+use crate::{Message, State};
+use iced::widget;
+use iced::widget::{Row, combo_box};
+use juicy_lemon::config;
+use juicy_lemon::dataset::DataFormat;
+use juicy_lemon::graph::export::ExportFormat;
+
+/// Which candles a data export writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CandleRange {
+    /// The ones on screen after zooming.
+    Visible,
+    /// Everything cached for the series.
+    Full,
+}
+
+/// Image sizes offered besides the configured `export.width` and `export.height`.
+const SIZES: [(u32, u32); 4] = [(1280, 720), (1920, 1080), (2560, 1440), (3840, 2160)];
+
+pub fn size_label((width, height): (u32, u32)) -> String {
+    format!("{}x{}", width, height)
+}
+
+pub fn parse_size(label: &str) -> Option<(u32, u32)> {
+    let (width, height) = label.split_once('x')?;
+    Some((width.parse().ok()?, height.parse().ok()?))
+}
+
+/// The configured size first, then the presets it is not one of.
+pub fn size_options() -> Vec<String> {
+    let export = &config::get().export;
+    let configured = (export.width, export.height);
+
+    std::iter::once(configured)
+        .chain(SIZES.into_iter().filter(|size| *size != configured))
+        .map(size_label)
+        .collect()
+}
+
+/// Saves the chart as shown, or its visible candles, into `export.dir`.
+pub fn render_chart_export(state: &State) -> Row<'_, Message> {
+    let palette = state.palette();
+    let data = |label, format| {
+        action_button(
+            palette,
+            label,
+            Message::ExportCandles(format, CandleRange::Visible),
+        )
+    };
+
+    let size = combo_box(
+        &state.export_size_state,
+        "Size",
+        Some(&state.export_size_label),
+        Message::SelectExportSize,
+    )
+    .width(110)
+    .input_style(move |_, _| input_style(&palette))
+    .menu_style(move |_| menu_style(&palette));
+
+    widget::row![
+        size,
+        action_button(palette, "PNG", Message::ExportChart(ExportFormat::Png)),
+        action_button(palette, "SVG", Message::ExportChart(ExportFormat::Svg)),
+        data("CSV", DataFormat::Csv),
+        data("JSONL", DataFormat::JsonLines),
+        data("Parquet", DataFormat::Parquet),
+    ]
+    .spacing(4)
+}
@@ -0,0 +1,88 @@
+use crate::shortcuts::CommandPalette;
+use crate::ui::{border, input_style};
+use crate::{Message, State};
+use iced::widget::{Column, button, column, container, mouse_area, opaque, row, text, text_input};
+use iced::{Background, Border, Center, Color, Element, Fill, Padding};
+use juicy_lemon::colors::color;
+
+pub fn input_id() -> text_input::Id {
+    text_input::Id::new("command-palette")
+}
+
+/// A list of commands over a dimmed backdrop, clicking the backdrop closes it.
+pub fn render_command_palette<'a>(
+    state: &State,
+    command_palette: &'a CommandPalette,
+) -> Element<'a, Message> {
+    let palette = state.palette();
+
+    let input = text_input("Type a command or symbol...", &command_palette.input)
+        .id(input_id())
+        .on_input(Message::CommandInput)
+        .on_submit(Message::RunCommand(command_palette.selected))
+        .size(14)
+        .padding(8)
+        .style(move |_, _| input_style(&palette));
+
+    let mut entries = Column::new().spacing(2);
+
+    for (i, command) in command_palette.matches.iter().enumerate() {
+        let selected = i == command_palette.selected;
+
+        entries = entries.push(
+            button(row![
+                text(&command.label).size(13).width(Fill),
+                text(command.shortcut.clone().unwrap_or_default())
+                    .size(12)
+                    .color(color(palette.muted)),
+            ])
+            .width(Fill)
+            .padding([4, 8])
+            .style(move |_, status| button::Style {
+                background: (selected || status == button::Status::Hovered)
+                    .then_some(Background::Color(color(palette.raised))),
+                text_color: color(palette.text),
+                border: Border {
+                    radius: 4.0.into(),
+                    ..Border::default()
+                },
+                shadow: Default::default(),
+            })
+            .on_press(Message::RunCommand(i)),
+        );
+    }
+
+    if command_palette.matches.is_empty() {
+        entries = entries.push(text("No matches").size(12).color(color(palette.muted)));
+    }
+
+    let panel = container(column![input, entries].spacing(6))
+        .width(420)
+        .padding(8)
+        .style(move |_| container::Style {
+            background: Some(Background::Color(color(palette.surface))),
+            border: border(&palette),
+            ..container::Style::default()
+        });
+
+    opaque(
+        mouse_area(
+            container(opaque(panel))
+                .width(Fill)
+                .height(Fill)
+                .align_x(Center)
+                .padding(Padding {
+                    top: 80.0,
+                    ..Padding::ZERO
+                })
+                .style(|_| container::Style {
+                    background: Some(Background::Color(Color {
+                        a: 0.6,
+                        ..Color::BLACK
+                    })),
+                    ..container::Style::default()
+                }),
+        )
+        .on_press(Message::CloseCommandPalette),
+    )
+}
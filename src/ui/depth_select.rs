@@ -0,0 +1,16 @@
+use crate::{Message, State};
+use crate::ui::{input_style, menu_style};
+use iced::widget::{ComboBox, combo_box};
+
+pub fn render_depth_grouping_select(state: &State) -> ComboBox<'_, String, Message> {
+    let palette = state.palette();
+
+    combo_box(
+        &state.depth_grouping_state,
+        "Grouping",
+        Some(&state.depth_grouping_label),
+        Message::SelectDepthGrouping,
+    )
+    .input_style(move |_, _| input_style(&palette))
+    .menu_style(move |_| menu_style(&palette))
+}
@@ -1,2 +1,101 @@
+pub mod backtest_panel;
+pub mod chart_export;
+pub mod command_palette;
+pub mod depth_select;
 pub mod instrument_select;
+pub mod paper_panel;
+pub mod portfolio_panel;
+pub mod quote_select;
+pub mod theme_select;
 pub mod timeframe_select;
+pub mod watchlist_select;
+
+use crate::Message;
+use iced::advanced::widget::operation::Focusable;
+use iced::advanced::widget::{self, Operation, operate};
+use iced::overlay::menu;
+use iced::widget::{Button, button, container, text, text_input};
+use iced::{Background, Border, Rectangle, Task};
+use juicy_lemon::colors::color;
+use juicy_lemon::theme::Palette;
+
+pub fn border(palette: &Palette) -> Border {
+    Border {
+        color: color(palette.border),
+        width: 1.0,
+        radius: 6.0.into(),
+    }
+}
+
+pub fn action_button(palette: Palette, label: &str, message: Message) -> Button<'_, Message> {
+    button(text(label).size(12))
+        .style(move |_, _| button::Style {
+            background: Some(Background::Color(color(palette.raised))),
+            text_color: color(palette.text),
+            border: border(&palette),
+            shadow: Default::default(),
+        })
+        .padding([2, 6])
+        .on_press(message)
+}
+
+/// Shared by every text input and combo box, focused or not.
+pub fn input_style(palette: &Palette) -> text_input::Style {
+    text_input::Style {
+        background: Background::Color(color(palette.surface)),
+        border: border(palette),
+        icon: Default::default(),
+        placeholder: color(palette.muted),
+        value: color(palette.text),
+        selection: color(palette.selection),
+    }
+}
+
+pub fn menu_style(palette: &Palette) -> menu::Style {
+    menu::Style {
+        background: Background::Color(color(palette.surface)),
+        border: border(palette),
+        text_color: color(palette.text),
+        selected_text_color: color(palette.text),
+        selected_background: Background::Color(color(palette.raised)),
+    }
+}
+
+/// Focuses the first input inside the container `id`, which reaches inputs that take no
+/// id of their own like the one in a combo box.
+pub fn focus_within<T: Send + 'static>(id: container::Id) -> Task<T> {
+    struct FocusWithin {
+        target: widget::Id,
+        inside: bool,
+        focused: bool,
+    }
+
+    impl<T> Operation<T> for FocusWithin {
+        fn container(
+            &mut self,
+            id: Option<&widget::Id>,
+            _bounds: Rectangle,
+            operate_on_children: &mut dyn FnMut(&mut dyn Operation<T>),
+        ) {
+            let entered = !self.inside && id == Some(&self.target);
+            self.inside |= entered;
+            operate_on_children(self);
+            self.inside &= !entered;
+        }
+
+        fn focusable(&mut self, state: &mut dyn Focusable, _id: Option<&widget::Id>) {
+            if self.inside && !self.focused {
+                state.focus();
+                self.focused = true;
+            } else {
+                state.unfocus();
+            }
+        }
+    }
+
+    operate(FocusWithin {
+        target: id.into(),
+        inside: false,
+        focused: false,
+    })
+}
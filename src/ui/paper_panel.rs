@@ -0,0 +1,236 @@
+use crate::ui::{action_button, border, input_style};
+use crate::{Message, State};
+use iced::alignment::Vertical;
+use iced::widget::{Button, Column, button, text, text_input};
+use iced::{Background, Fill, Padding, widget};
+use juicy_lemon::colors::color;
+use juicy_lemon::paper::{OrderKind, OrderSide};
+use juicy_lemon::theme::Palette;
+use rust_decimal::Decimal;
+
+const RECENT_FILLS: usize = 5;
+
+/// The order types of the form, the prices they need come from its inputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaperOrderType {
+    Market,
+    Limit,
+    Stop,
+    StopLimit,
+}
+
+impl PaperOrderType {
+    pub const ALL: [PaperOrderType; 4] = [
+        PaperOrderType::Market,
+        PaperOrderType::Limit,
+        PaperOrderType::Stop,
+        PaperOrderType::StopLimit,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PaperOrderType::Market => "Market",
+            PaperOrderType::Limit => "Limit",
+            PaperOrderType::Stop => "Stop",
+            PaperOrderType::StopLimit => "Stop limit",
+        }
+    }
+
+    /// `price` is the limit of limit orders and the stop of stop orders.
+    pub fn kind(&self, price: &str, stop: &str) -> Result<OrderKind, String> {
+        let parse = |label: &str, input: &str| {
+            input
+                .trim()
+                .parse::<Decimal>()
+                .map_err(|_| format!("{} must be a number, got {:?}", label, input))
+        };
+
+        Ok(match self {
+            PaperOrderType::Market => OrderKind::Market,
+            PaperOrderType::Limit => OrderKind::Limit(parse("Price", price)?),
+            PaperOrderType::Stop => OrderKind::Stop(parse("Stop", price)?),
+            PaperOrderType::StopLimit => OrderKind::StopLimit {
+                stop: parse("Stop", stop)?,
+                limit: parse("Limit", price)?,
+            },
+        })
+    }
+}
+
+fn choice_button(
+    palette: Palette,
+    label: &str,
+    selected: bool,
+    message: Message,
+) -> Button<'_, Message> {
+    let background = if selected {
+        palette.selection
+    } else {
+        palette.raised
+    };
+
+    button(text(label).size(12))
+        .style(move |_, _| button::Style {
+            background: Some(Background::Color(color(background))),
+            text_color: color(palette.text),
+            border: border(&palette),
+            shadow: Default::default(),
+        })
+        .padding([2, 6])
+        .on_press(message)
+}
+
+fn section(title: &str) -> iced::widget::Text<'_> {
+    text(title).size(13)
+}
+
+/// The order form of the displayed symbol, then open orders, positions, balances and the
+/// latest fills of the whole paper account.
+pub fn render_paper_panel(state: &State) -> Column<'_, Message> {
+    let palette = state.palette();
+    let account = &state.paper;
+
+    let mut sides = widget::row![].spacing(4);
+    for (side, label) in [(OrderSide::Buy, "Buy"), (OrderSide::Sell, "Sell")] {
+        sides = sides.push(choice_button(
+            palette,
+            label,
+            state.paper_side == side,
+            Message::PaperSide(side),
+        ));
+    }
+
+    let mut types = widget::row![].spacing(4);
+    for order_type in PaperOrderType::ALL {
+        types = types.push(choice_button(
+            palette,
+            order_type.label(),
+            state.paper_order_type == order_type,
+            Message::PaperOrderType(order_type),
+        ));
+    }
+
+    let input = |placeholder, value, on_input: fn(String) -> Message| {
+        text_input(placeholder, value)
+            .on_input(on_input)
+            .on_submit(Message::PlacePaperOrder)
+            .style(move |_, _| input_style(&palette))
+            .size(12)
+            .width(100)
+    };
+
+    let mut form = widget::row![input(
+        "Quantity",
+        &state.paper_quantity_input,
+        Message::PaperQuantityInput
+    )]
+    .spacing(4)
+    .align_y(Vertical::Center);
+
+    form = match state.paper_order_type {
+        PaperOrderType::Market => form,
+        PaperOrderType::Limit => form.push(input(
+            "Price",
+            &state.paper_price_input,
+            Message::PaperPriceInput,
+        )),
+        PaperOrderType::Stop => form.push(input(
+            "Stop",
+            &state.paper_price_input,
+            Message::PaperPriceInput,
+        )),
+        PaperOrderType::StopLimit => form
+            .push(input(
+                "Stop",
+                &state.paper_stop_input,
+                Message::PaperStopInput,
+            ))
+            .push(input(
+                "Limit",
+                &state.paper_price_input,
+                Message::PaperPriceInput,
+            )),
+    };
+    form = form.push(action_button(palette, "Place", Message::PlacePaperOrder));
+
+    let mut panel = widget::column![
+        widget::row![text("Paper trading").size(14), sides, types]
+            .spacing(10)
+            .align_y(Vertical::Center),
+        form,
+    ]
+    .spacing(6)
+    .width(Fill)
+    .padding(Padding {
+        top: 0.0,
+        right: 10.0,
+        bottom: 10.0,
+        left: 10.0,
+    });
+
+    let balances = account
+        .balances
+        .iter()
+        .filter(|(_, balance)| !balance.is_zero())
+        .map(|(asset, balance)| format!("{} {}", balance.normalize(), asset))
+        .collect::<Vec<_>>();
+    panel = panel.push(text(format!("Balances: {}", balances.join(", "))).size(12));
+
+    if !account.orders.is_empty() {
+        panel = panel.push(section("Open orders"));
+    }
+    for order in &account.orders {
+        panel = panel.push(
+            widget::row![
+                text(order.to_string()).size(12).width(Fill),
+                action_button(palette, "Cancel", Message::CancelPaperOrder(order.id)),
+            ]
+            .spacing(5)
+            .align_y(Vertical::Center),
+        );
+    }
+
+    if !account.positions.is_empty() {
+        panel = panel.push(section("Positions"));
+    }
+    for position in &account.positions {
+        let unrealized = state
+            .last_price(&position.symbol)
+            .map(|price| {
+                position
+                    .unrealized(price)
+                    .round_dp(8)
+                    .normalize()
+                    .to_string()
+            })
+            .unwrap_or_else(|| "-".to_string());
+
+        panel = panel.push(
+            text(format!(
+                "{} {} @ {}, unrealized {}, realized {}",
+                position.symbol,
+                position.quantity.normalize(),
+                position.average_price.round_dp(8).normalize(),
+                unrealized,
+                position.realized.round_dp(8).normalize()
+            ))
+            .size(12),
+        );
+    }
+
+    if !account.fills.is_empty() {
+        panel = panel.push(section("Fills"));
+    }
+    for fill in account.fills.iter().rev().take(RECENT_FILLS) {
+        panel = panel.push(
+            text(format!(
+                "{}, fee {}",
+                fill,
+                fill.fee.round_dp(8).normalize()
+            ))
+            .size(12),
+        );
+    }
+
+    panel
+}
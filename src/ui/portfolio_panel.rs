@@ -0,0 +1,202 @@
+use crate::ui::{action_button, input_style};
+use crate::{Message, State};
+use iced::alignment::Vertical;
+use iced::widget::{Column, Row, canvas, text, text_input};
+use iced::{Color, Element, Fill, Padding, widget};
+use juicy_lemon::colors::color;
+use juicy_lemon::config;
+use juicy_lemon::graph::portfolio::ValueChart;
+use juicy_lemon::theme::Palette;
+use rust_decimal::Decimal;
+
+const COLUMNS: [&str; 8] = [
+    "Asset",
+    "Quantity",
+    "Avg cost",
+    "Price",
+    "Value",
+    "Alloc",
+    "Unrealized",
+    "Realized",
+];
+
+fn amount(value: Decimal) -> String {
+    value.round_dp(2).to_string()
+}
+
+fn pnl_color(palette: &Palette, pnl: Decimal) -> Color {
+    if pnl.is_sign_negative() {
+        color(palette.bear)
+    } else {
+        color(palette.bull)
+    }
+}
+
+fn cell<'a>(content: String, cell_color: Option<Color>) -> Element<'a, Message> {
+    text(content)
+        .size(12)
+        .color_maybe(cell_color)
+        .width(Fill)
+        .into()
+}
+
+/// Holdings valued at the latest prices with their P&L and share of the total, the forms
+/// to enter or import them, and the total value over time.
+pub fn render_portfolio_panel(state: &State) -> Column<'_, Message> {
+    let palette = state.palette();
+    let valuation_asset = &config::get().portfolio.valuation;
+    let valuation = state.portfolio.value(&state.rates(), valuation_asset);
+
+    let input = |placeholder, value, on_input: fn(String) -> Message, on_submit| {
+        text_input(placeholder, value)
+            .on_input(on_input)
+            .on_submit(on_submit)
+            .style(move |_, _| input_style(&palette))
+            .size(12)
+    };
+
+    let header = widget::row![
+        text(format!("Portfolio in {}", valuation_asset)).size(14),
+        text(format!("Value {}", amount(valuation.total))).size(12),
+        text(format!("Unrealized {}", amount(valuation.unrealized)))
+            .size(12)
+            .color(pnl_color(&palette, valuation.unrealized)),
+        text(format!("Realized {}", amount(valuation.realized)))
+            .size(12)
+            .color(pnl_color(&palette, valuation.realized)),
+    ]
+    .spacing(10)
+    .align_y(Vertical::Center);
+
+    let holding_form = widget::row![
+        input(
+            "Asset",
+            &state.portfolio_asset_input,
+            Message::PortfolioAssetInput,
+            Message::SetHolding
+        )
+        .width(80),
+        input(
+            "Quantity",
+            &state.portfolio_quantity_input,
+            Message::PortfolioQuantityInput,
+            Message::SetHolding
+        )
+        .width(100),
+        input(
+            "Average cost",
+            &state.portfolio_cost_input,
+            Message::PortfolioCostInput,
+            Message::SetHolding
+        )
+        .width(100),
+        action_button(palette, "Set", Message::SetHolding),
+        input(
+            "trades.csv (asset,side,quantity,price,fee)",
+            &state.portfolio_path_input,
+            Message::PortfolioPathInput,
+            Message::ImportTrades
+        ),
+        action_button(palette, "Import trades", Message::ImportTrades),
+    ]
+    .spacing(4)
+    .align_y(Vertical::Center);
+
+    let mut panel = widget::column![header, holding_form]
+        .spacing(6)
+        .width(Fill)
+        .padding(Padding {
+            top: 0.0,
+            right: 10.0,
+            bottom: 10.0,
+            left: 10.0,
+        });
+
+    if valuation.assets.is_empty() {
+        return panel.push(text("No holdings yet").size(12));
+    }
+
+    let mut table = Column::new().spacing(2).push(
+        COLUMNS
+            .iter()
+            .fold(Row::new(), |row, title| {
+                row.push(cell(title.to_string(), Some(color(palette.muted))))
+            })
+            .push(text("").width(60)),
+    );
+
+    for asset in &valuation.assets {
+        let holding = &asset.holding;
+        let missing = || "-".to_string();
+
+        table = table.push(
+            widget::row![
+                cell(holding.asset.clone(), None),
+                cell(holding.quantity.normalize().to_string(), None),
+                cell(
+                    holding.average_cost.round_dp(8).normalize().to_string(),
+                    None
+                ),
+                cell(
+                    asset
+                        .price
+                        .map(|p| p.round_dp(8).normalize().to_string())
+                        .unwrap_or_else(missing),
+                    None
+                ),
+                cell(asset.value.map(amount).unwrap_or_else(missing), None),
+                cell(
+                    asset
+                        .allocation
+                        .map(|a| format!("{}%", a.round_dp(1)))
+                        .unwrap_or_else(missing),
+                    None
+                ),
+                cell(
+                    asset.unrealized.map(amount).unwrap_or_else(missing),
+                    asset.unrealized.map(|pnl| pnl_color(&palette, pnl))
+                ),
+                cell(
+                    amount(holding.realized),
+                    Some(pnl_color(&palette, holding.realized))
+                ),
+                action_button(
+                    palette,
+                    "Remove",
+                    Message::RemoveHolding(holding.asset.clone())
+                )
+                .width(60),
+            ]
+            .align_y(Vertical::Center),
+        );
+    }
+
+    panel = panel.push(table);
+
+    let unpriced = valuation
+        .assets
+        .iter()
+        .filter(|a| a.price.is_none())
+        .map(|a| a.holding.asset.as_str())
+        .collect::<Vec<_>>();
+    if !unpriced.is_empty() && !state.instruments.is_empty() {
+        panel = panel.push(
+            text(format!(
+                "No price in {} yet for {}",
+                valuation_asset,
+                unpriced.join(", ")
+            ))
+            .size(12),
+        );
+    }
+
+    panel.push(
+        canvas(ValueChart {
+            points: state.portfolio_history.points(),
+            valuation: valuation_asset,
+            palette,
+        })
+        .width(Fill)
+        .height(120),
+    )
+}
@@ -0,0 +1,16 @@
+use crate::ui::{input_style, menu_style};
+use crate::{Message, State};
+use iced::widget::{ComboBox, combo_box};
+
+pub fn render_theme_select(state: &State) -> ComboBox<'_, String, Message> {
+    let palette = state.palette();
+
+    combo_box(
+        &state.theme_select_state,
+        "Theme",
+        Some(state.palette_name()),
+        Message::SelectTheme,
+    )
+    .input_style(move |_, _| input_style(&palette))
+    .menu_style(move |_| menu_style(&palette))
+}
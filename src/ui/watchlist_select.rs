@@ -0,0 +1,50 @@
+use crate::ui::{action_button, input_style, menu_style};
+use crate::{Message, State};
+use iced::widget;
+use iced::widget::{ComboBox, Row, combo_box, text_input};
+
+pub fn render_watchlist_select(state: &State) -> ComboBox<'_, String, Message> {
+    let palette = state.palette();
+
+    combo_box(
+        &state.watchlist_select_state,
+        "Select a watchlist...",
+        Some(&state.watchlist().name),
+        Message::SwitchWatchlist,
+    )
+    .input_style(move |_, _| input_style(&palette))
+    .menu_style(move |_| menu_style(&palette))
+}
+
+pub fn render_watchlist_actions(state: &State) -> Row<'_, Message> {
+    let palette = state.palette();
+    let name_input = text_input("List name...", &state.watchlist_name_input)
+        .on_input(Message::WatchlistNameInput)
+        .on_submit(Message::CreateWatchlist)
+        .size(12)
+        .style(move |_, _| input_style(&palette));
+
+    widget::row![
+        name_input,
+        action_button(palette, "New", Message::CreateWatchlist),
+        action_button(palette, "Rename", Message::RenameWatchlist),
+        action_button(palette, "Copy", Message::DuplicateWatchlist),
+        action_button(palette, "Delete", Message::DeleteWatchlist),
+    ]
+    .spacing(4)
+}
+
+pub fn render_watchlist_transfer(state: &State) -> Row<'_, Message> {
+    let palette = state.palette();
+    let path_input = text_input("watchlist.json / .csv / .txt", &state.watchlist_path_input)
+        .on_input(Message::WatchlistPathInput)
+        .size(12)
+        .style(move |_, _| input_style(&palette));
+
+    widget::row![
+        path_input,
+        action_button(palette, "Import", Message::ImportWatchlist),
+        action_button(palette, "Export", Message::ExportWatchlist),
+    ]
+    .spacing(4)
+}
@@ -1,45 +1,319 @@
-use crate::graph::candle::get_candles;
-use crate::symbols::{Symbol, fetch_symbol_prices, get_symbols};
-use crate::utils::{get_current_select_state, get_default_select_state};
+use crate::shortcuts::{CommandPalette, MIN_VISIBLE_CANDLES, action_message, matching_commands};
+use crate::ui::chart_export::{CandleRange, parse_size};
+use crate::ui::command_palette;
+use crate::ui::focus_within;
+use crate::ui::instrument_select::search_id;
 use crate::{Message, State};
+use juicy_lemon::backtest::{Backtester, parse_strategy};
+use juicy_lemon::candle::{CandleGap, get_candles, get_candles_since};
+use juicy_lemon::config;
+use juicy_lemon::dataset::CandleTable;
+use juicy_lemon::graph::export::{ChartSnapshot, write_chart};
+use juicy_lemon::graph::sparkline::get_sparklines;
+use juicy_lemon::orderbook::source::get_depth_snapshot;
+use juicy_lemon::orderbook::{
+    DEPTH_GROUPINGS, OrderBookSync, grouping_label, grouping_labels, tick_size,
+};
+use juicy_lemon::paper::PaperEvent;
+use juicy_lemon::profile::{MAX_KLINES, get_volume_profiles};
+use juicy_lemon::store::{
+    get_candles_with_store, get_stored_candles, get_stored_history, store_candles,
+};
+use juicy_lemon::symbols::{
+    Symbol, fetch_symbol_prices, get_symbols, read_cached_symbols, write_cached_symbols,
+};
+use juicy_lemon::trades::{TradeFeed, get_agg_trades};
+use juicy_lemon::utils::{get_current_select_state, get_default_select_state};
+use juicy_lemon::watchlist::io::{WatchlistFormat, export, parse, validate};
+use juicy_lemon::watchlist::{Watchlist, unique_name};
 use iced::Task;
-use iced::widget::combo_box;
-use std::collections::{HashMap, VecDeque};
+use iced::widget::{combo_box, text_input};
+use std::collections::VecDeque;
 use std::fs;
-use std::fs::File;
-use std::io::Write;
+use std::path::PathBuf;
+use std::sync::PoisonError;
+use std::time::Instant;
+use chrono::{Local, Utc};
 use rust_decimal::Decimal;
+use rust_decimal::prelude::*;
+
+fn fetch_prices(symbols: Vec<String>) -> Task<Message> {
+    Task::perform(
+        async move {
+            match fetch_symbol_prices(symbols).await {
+                Ok(prices) => Message::PricesUpdated(prices),
+                Err(err) => Message::FetchError(err.to_string()),
+            }
+        },
+        |msg| msg,
+    )
+}
+
+fn fetch_sparklines(symbols: &[Symbol]) -> Task<Message> {
+    if symbols.is_empty() {
+        return Task::none();
+    }
+
+    let symbols = symbols
+        .iter()
+        .map(|s| (s.symbol.clone(), s.decimals))
+        .collect::<Vec<_>>();
+
+    Task::perform(get_sparklines(symbols), Message::SparklinesFetched)
+}
+
+fn missing_sparklines(state: &State) -> Vec<Symbol> {
+    state
+        .watchlist()
+        .symbols
+        .iter()
+        .filter(|s| !state.sparklines.contains_key(&s.symbol))
+        .cloned()
+        .collect()
+}
+
+/// Requests the bars a merge found missing, the answer goes through the same merge.
+fn fetch_candle_gap(
+    state: &State,
+    symbol: String,
+    timeframe: String,
+    gap: CandleGap,
+) -> Task<Message> {
+    let Some(decimals) = state
+        .instruments
+        .iter()
+        .find(|s| s.symbol == symbol)
+        .map(|s| s.decimals)
+    else {
+        return Task::none();
+    };
+
+    Task::perform(
+        async move {
+            match get_candles_since(&symbol, &timeframe, decimals, gap.from, gap.count).await {
+                Ok(candles) => Message::CandlesFetched(candles, symbol, timeframe),
+                Err(err) => Message::FetchError(err),
+            }
+        },
+        |msg| msg,
+    )
+}
+
+fn now_millis() -> u64 {
+    Utc::now().timestamp_millis() as u64
+}
+
+/// Runs file reads and writes on the blocking pool instead of the executor.
+async fn blocking<T: Send + 'static>(
+    work: impl FnOnce() -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    tokio::task::spawn_blocking(work)
+        .await
+        .map_err(|err| err.to_string())?
+}
+
+/// Writes the holdings after every change off the UI thread, a failure only shows in the
+/// status line. A save that finds a newer revision already written leaves the file alone.
+fn save_portfolio(state: &mut State) -> Task<Message> {
+    state.portfolio_revision += 1;
+    let revision = state.portfolio_revision;
+    let portfolio = state.portfolio.clone();
+    let saved = state.portfolio_saved.clone();
+
+    Task::perform(
+        blocking(move || {
+            let mut saved = saved.lock().unwrap_or_else(PoisonError::into_inner);
+            if *saved > revision {
+                return Ok(());
+            }
+
+            portfolio.save(&config::get().portfolio.file)?;
+            *saved = revision;
+            Ok(())
+        }),
+        Message::PortfolioSaved,
+    )
+}
+
+/// Fetches the pairs that price the holdings, right away instead of at the next refresh.
+fn fetch_portfolio_prices(state: &State) -> Task<Message> {
+    let pairs = state.portfolio_pairs();
+
+    if pairs.is_empty() {
+        Task::none()
+    } else {
+        fetch_prices(pairs)
+    }
+}
+
+/// Fills and rejections of paper orders go to the status line.
+fn report_paper_events(state: &mut State, events: Vec<PaperEvent>) {
+    if !events.is_empty() {
+        state.error_message = events
+            .iter()
+            .map(|event| event.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+    }
+}
+
+/// Keeps the cache within budget without dropping the series on screen.
+fn evict_candles(state: &mut State) {
+    let visible = state
+        .visible_series()
+        .map(|(symbol, timeframe)| (symbol.to_string(), timeframe.to_string()));
+
+    let evicted = state
+        .candles
+        .evict(visible.as_ref().map(|(s, t)| (s.as_str(), t.as_str())));
+
+    for (symbol, timeframe) in evicted {
+        println!("Evicted {} {} from the candle cache", symbol, timeframe);
+    }
+}
+
+/// Shows what the candle store already has, then replaces it once the missing bars arrive.
+fn load_candles(state: &State, symbol: String, timeframe: String, decimals: u32) -> Task<Message> {
+    let stored = Task::perform(
+        get_stored_candles(
+            state.candle_store.clone(),
+            symbol.clone(),
+            timeframe.clone(),
+        ),
+        {
+            let symbol = symbol.clone();
+            let timeframe = timeframe.clone();
+            move |candles| Message::CandlesLoaded(candles, symbol.clone(), timeframe.clone())
+        },
+    );
+
+    let fetched = Task::perform(
+        get_candles_with_store(
+            state.candle_store.clone(),
+            symbol.clone(),
+            timeframe.clone(),
+            decimals,
+        ),
+        move |result| match result {
+            Ok(candles) => Message::CandlesLoaded(candles, symbol.clone(), timeframe.clone()),
+            Err(err) => Message::FetchError(err),
+        },
+    );
+
+    stored.chain(fetched)
+}
+
+fn fetch_depth_snapshot(state: &State) -> Task<Message> {
+    let Some(sync) = state.order_book.as_ref() else {
+        return Task::none();
+    };
+
+    let source = state.depth_source.clone();
+    let symbol = sync.symbol.clone();
+    let request = sync.snapshot_requests.saturating_sub(1);
+
+    Task::perform(
+        async move {
+            let snapshot = get_depth_snapshot(source, symbol.clone(), request).await;
+            Message::DepthSnapshotFetched(symbol, snapshot)
+        },
+        |msg| msg,
+    )
+}
+
+/// Profiles cover the candles currently loaded for the displayed symbol and timeframe.
+fn fetch_volume_profiles(state: &mut State) -> Task<Message> {
+    if !state.show_volume_profile || state.volume_profile_loading {
+        return Task::none();
+    }
+
+    let (Some(symbol), Some(timeframe)) = (&state.displayed_symbol, &state.selected_timeframe)
+    else {
+        return Task::none();
+    };
+
+    let Some(start_time) = state
+        .candles
+        .get(&symbol.symbol, timeframe)
+        .and_then(|candles| candles.front())
+        .map(|candle| candle.open_time)
+    else {
+        return Task::none();
+    };
+
+    state.volume_profile_loading = true;
+
+    Task::perform(
+        get_volume_profiles(
+            symbol.symbol.clone(),
+            timeframe.clone(),
+            symbol.decimals,
+            start_time,
+        ),
+        Message::VolumeProfileFetched,
+    )
+}
+
+/// Makes the list at `index` visible and only fetches prices for its symbols.
+fn activate_watchlist(state: &mut State, index: usize) -> Task<Message> {
+    state.active_watchlist = index;
+    state.selected_timeframe = Some(state.watchlist().timeframe.clone());
+    state.displayed_symbol = None;
+
+    let mut tasks = vec![Task::perform(async {}, |_| Message::UpdateSelectOptions)];
+
+    if let Some(first) = state.watchlist().symbols.first() {
+        let symbol = first.symbol.clone();
+        tasks.push(Task::perform(async {}, move |_| {
+            Message::SelectSymbol(symbol.clone())
+        }));
+        tasks.push(fetch_prices(state.watchlist().symbol_names()));
+        tasks.push(fetch_sparklines(&missing_sparklines(state)));
+    }
+
+    Task::batch(tasks)
+}
+
+/// `<symbol>-<timeframe>-<time>.<extension>` in `export.dir`, `None` without a chart.
+fn export_path(state: &State, extension: &str) -> Option<PathBuf> {
+    let (symbol, timeframe) = state.visible_series()?;
+
+    Some(config::get().export.dir.join(format!(
+        "{}-{}-{}.{}",
+        symbol,
+        timeframe,
+        Local::now().format("%Y%m%d-%H%M%S"),
+        extension
+    )))
+}
+
+fn create_export_dir() -> Result<(), String> {
+    let dir = &config::get().export.dir;
+    fs::create_dir_all(dir).map_err(|err| format!("Cannot create {}: {}", dir.display(), err))
+}
 
 pub fn update(state: &mut State, message: Message) -> Task<Message> {
     match message {
         Message::ChangeTimeframe(timeframe) => {
             state.selected_timeframe = Some(timeframe.clone());
+            state.watchlist_mut().timeframe = timeframe.clone();
 
             if let Some(displayed_symbol) = state.displayed_symbol.as_mut() {
                 displayed_symbol.timeframe = timeframe.clone();
 
                 let symbol = displayed_symbol.symbol.clone();
-                let tf = displayed_symbol.timeframe.clone();
                 let decimals = displayed_symbol.decimals;
 
-                Task::perform(
-                    async move {
-                        match get_candles(&symbol, &tf, decimals).await {
-                            Ok(candles) => Message::CandlesFetched(candles, symbol),
-                            Err(err) => Message::FetchError(err),
-                        }
-                    },
-                    |msg| msg,
-                )
+                load_candles(state, symbol, timeframe, decimals)
             } else {
                 Task::none()
             }
         }
         Message::SelectSymbol(symbol) => {
-            if let Some(displayed_symbol) = &state.displayed_symbol {
-                if displayed_symbol.symbol == symbol {
-                    return Task::none();
-                }
+            if let Some(displayed_symbol) = &state.displayed_symbol
+                && displayed_symbol.symbol == symbol
+            {
+                return Task::none();
             }
 
             let timeframe = match state.selected_timeframe.as_ref() {
@@ -48,59 +322,123 @@ pub fn update(state: &mut State, message: Message) -> Task<Message> {
             };
 
             let instrument = state
-                .watchlist
+                .watchlist()
+                .symbols
                 .iter()
                 .find(|s| s.symbol == symbol)
                 .unwrap();
 
             let mut new_instrument = instrument.clone();
             new_instrument.timeframe = timeframe.clone();
-            state.displayed_symbol = Some(instrument.clone());
+            state.displayed_symbol = Some(new_instrument);
 
-            let decimals = state.displayed_symbol.as_ref().map(|s| s.decimals).unwrap_or(8);
+            let decimals = state
+                .displayed_symbol
+                .as_ref()
+                .map(|s| s.decimals)
+                .unwrap_or(8);
 
-            Task::perform(
-                async move {
-                    match get_candles(&symbol, &timeframe, decimals).await {
-                        Ok(candles) => Message::CandlesFetched(candles, symbol),
-                        Err(err) => Message::FetchError(err),
-                    }
-                },
-                |msg| msg,
-            )
+            load_candles(state, symbol, timeframe, decimals)
+        }
+        Message::CandlesFetched(candles, symbol, timeframe) => {
+            let max_len = config::get().history.candles;
+            let gap_task = match state.candles.merge(&symbol, &timeframe, &candles, max_len) {
+                Ok(Some(gap)) => fetch_candle_gap(state, symbol.clone(), timeframe.clone(), gap),
+                Ok(None) => Task::none(),
+                Err(err) => {
+                    println!("Dropped candles for {} {}: {:?}", symbol, timeframe, err);
+                    Task::none()
+                }
+            };
+
+            let store_task = Task::perform(
+                store_candles(
+                    state.candle_store.clone(),
+                    symbol.clone(),
+                    timeframe.clone(),
+                    candles.clone(),
+                ),
+                Message::CandlesStored,
+            );
+            evict_candles(state);
+
+            let events = candles
+                .iter()
+                .flat_map(|candle| state.paper.on_candle(&symbol, candle))
+                .collect();
+            report_paper_events(state, events);
+
+            let profile_task = if state.displayed_volume_profiles().is_none() {
+                fetch_volume_profiles(state)
+            } else {
+                Task::none()
+            };
+
+            Task::batch(vec![
+                Task::perform(async {}, |_| Message::UpdateSelectOptions),
+                profile_task,
+                gap_task,
+                store_task,
+            ])
+        }
+        Message::CandlesLoaded(candles, symbol, timeframe) => {
+            if candles.is_empty() {
+                return Task::none();
+            }
+
+            let store_task = Task::perform(
+                store_candles(
+                    state.candle_store.clone(),
+                    symbol.clone(),
+                    timeframe.clone(),
+                    candles.clone(),
+                ),
+                Message::CandlesStored,
+            );
+            state
+                .candles
+                .insert(&symbol, &timeframe, VecDeque::from(candles));
+
+            evict_candles(state);
+
+            let profile_task = if state.displayed_volume_profiles().is_none() {
+                fetch_volume_profiles(state)
+            } else {
+                Task::none()
+            };
+
+            Task::batch(vec![
+                Task::perform(async {}, |_| Message::UpdateSelectOptions),
+                profile_task,
+                store_task,
+            ])
         }
-        Message::CandlesFetched(candles, symbol) => {
-            state.graph.clear();
-
-            if let Some(timeframe) = state.selected_timeframe.clone() {
-                let symbol_entry = state
-                    .candles
-                    .entry(symbol.clone())
-                    .or_insert_with(HashMap::new);
-                let old_candles = symbol_entry
-                    .entry(timeframe.clone())
-                    .or_insert_with(VecDeque::new);
-
-                if let (Some(last_old), Some(last_new)) = (old_candles.back(), candles.last()) {
-                    if last_old.open_time == last_new.open_time {
-                        old_candles.pop_back();
-                        old_candles.push_back(last_new.clone());
-                    } else {
-                        old_candles.push_back(last_new.clone());
-                        old_candles.pop_front();
+        Message::CandlesStored(result) => {
+            match result {
+                Ok(appended) => {
+                    for path in appended.evicted {
+                        println!("Evicted {} from the candle store", path.display());
                     }
-                } else {
-                    *old_candles = VecDeque::from(candles.clone());
                 }
+                Err(err) => state.error_message = err,
             }
 
-            Task::perform(async {}, |_| Message::UpdateSelectOptions)
+            Task::none()
         }
         Message::UpdateSelectOptions => {
             let mut options: Vec<String> = if state.input_text.is_empty() {
-                get_default_select_state(&state.instruments, &state.watchlist)
+                get_default_select_state(
+                    &state.instruments,
+                    &state.watchlist().symbols,
+                    state.quote_filter(),
+                )
             } else {
-                get_current_select_state(&state.instruments, &state.input_text, &state.watchlist)
+                get_current_select_state(
+                    &state.instruments,
+                    &state.input_text,
+                    &state.watchlist().symbols,
+                    state.quote_filter(),
+                )
             };
 
             if options.is_empty() {
@@ -120,29 +458,34 @@ pub fn update(state: &mut State, message: Message) -> Task<Message> {
             Task::perform(async {}, |_| Message::UpdateSelectOptions)
         }
         Message::SymbolRemove(symbol) => {
-            state.watchlist.retain(|w| w.symbol != symbol);
+            let next = state
+                .watchlist_mut()
+                .remove(&symbol)
+                .map(|s| s.symbol.clone());
 
-            if state.watchlist.is_empty() {
-                if let (Some(symbol), Some(timeframe)) =
-                    (&state.displayed_symbol, &state.selected_timeframe)
-                {
-                    if let Some(symbol_map) = state.candles.get_mut(symbol.symbol.as_str()) {
-                        for timeframe in state.timeframe_select_state.options().iter() {
-                            symbol_map.remove(timeframe);
-                        }
-                    }
+            if !state
+                .watchlists
+                .iter()
+                .any(|w| w.symbols.iter().any(|s| s.symbol == symbol))
+            {
+                state.sparklines.remove(&symbol);
+                state.price_ticks.remove(&symbol);
+                state.candles.remove_symbol(&symbol);
+            }
+
+            if state.watchlist().symbols.is_empty() {
+                if let Some(symbol) = &state.displayed_symbol {
+                    state.candles.remove_symbol(&symbol.symbol);
                 }
 
-                state.graph.clear();
                 return Task::perform(async {}, |_| Message::UpdateSelectOptions);
             }
 
             if let Some(displayed_symbol) = &state.displayed_symbol {
-                if Some(symbol) == Some(displayed_symbol.symbol.clone()) {
-                    let new_symbol = state.watchlist.first().unwrap().symbol.clone();
-                    return Task::perform(async {}, move |_| {
-                        Message::SelectSymbol(new_symbol.clone())
-                    });
+                if displayed_symbol.symbol == symbol
+                    && let Some(next) = next
+                {
+                    return Task::perform(async {}, move |_| Message::SelectSymbol(next.clone()));
                 }
             } else {
                 return Task::none();
@@ -157,31 +500,56 @@ pub fn update(state: &mut State, message: Message) -> Task<Message> {
             Task::none()
         }
         Message::PricesUpdated(prices) => {
-            for (i, item) in state.watchlist.iter_mut().enumerate() {
-                let instrument_response = prices[i].clone();
-                    let mut price = instrument_response.price.parse::<Decimal>().ok().unwrap();
-                    price.rescale(item.decimals);
+            let now = Instant::now();
+
+            for item in &prices {
+                if let Ok(price) = item.price.parse::<Decimal>() {
+                    state.portfolio_prices.insert(item.symbol.clone(), price);
+                }
+            }
+
+            let ticks = state.watchlist_mut().apply_prices(&prices, now);
 
-                    println!("Price: {}", price);
-                    println!("Decimals: {}", item.decimals);
-                
-                    item.price = Some(price);
+            state.now = now;
+            state.price_ticks.extend(ticks);
+
+            // Orders may be on symbols of other watchlists or of none.
+            let time = now_millis();
+            let events = prices
+                .iter()
+                .filter_map(|item| Some((&item.symbol, item.price.parse::<Decimal>().ok()?)))
+                .flat_map(|(symbol, price)| state.paper.on_price(symbol, price, time))
+                .collect();
+            report_paper_events(state, events);
+
+            // A total missing some holdings would show as a drop in the value chart.
+            let valuation = state
+                .portfolio
+                .value(&state.rates(), &config::get().portfolio.valuation);
+            if !valuation.assets.is_empty() && valuation.assets.iter().all(|a| a.price.is_some()) {
+                state.portfolio_history.push(time, valuation.total);
             }
 
             Task::none()
         }
         Message::RefetchData => {
-            if state.watchlist.is_empty() {
+            let mut symbols = state.watchlist().symbol_names();
+            let orders = state.paper.orders.iter().map(|order| order.symbol.clone());
+            for pair in state.portfolio_pairs().into_iter().chain(orders) {
+                if !symbols.contains(&pair) {
+                    symbols.push(pair);
+                }
+            }
+
+            if symbols.is_empty() {
                 return Task::none();
             }
 
             println!("Refetching price");
 
-            let symbols: Vec<String> = state.watchlist.iter().map(|s| s.symbol.clone()).collect();
-
             let symbol = match state.displayed_symbol.as_ref() {
                 Some(s) => s.clone(),
-                None => return Task::none(),
+                None => return fetch_prices(symbols),
             };
 
             Task::batch(vec![
@@ -192,25 +560,16 @@ pub fn update(state: &mut State, message: Message) -> Task<Message> {
 
                         async move {
                             match get_candles(&symbol_str, &timeframe_str, symbol.decimals).await {
-                                Ok(candles) => Message::CandlesFetched(candles, symbol_str),
+                                Ok(candles) => {
+                                    Message::CandlesFetched(candles, symbol_str, timeframe_str)
+                                }
                                 Err(err) => Message::FetchError(err),
                             }
                         }
                     },
                     |msg| msg,
                 ),
-                Task::perform(
-                    {
-                        let symbols = symbols.clone();
-                        async move {
-                            match fetch_symbol_prices(symbols).await {
-                                Ok(prices) => Message::PricesUpdated(prices),
-                                Err(err) => Message::FetchError(err.to_string()),
-                            }
-                        }
-                    },
-                    |msg| msg,
-                ),
+                fetch_prices(symbols),
             ])
         }
         Message::AddSymbol(symbol) => {
@@ -222,59 +581,39 @@ pub fn update(state: &mut State, message: Message) -> Task<Message> {
                 return Task::perform(async {}, |_| Message::UpdateSelectOptions);
             }
 
-            let instrument = state
-                .instruments
-                .iter()
-                .find(|s| s.symbol == symbol)
-                .unwrap();
-
-            let timeframe = match state.selected_timeframe.as_ref() {
-                Some(timeframe) => timeframe.clone(),
-                None => "1h".to_string(),
+            let active = state.active_watchlist;
+            let new_instrument = match state.watchlists[active].add(&state.instruments, &symbol) {
+                Ok(instrument) => instrument.clone(),
+                Err(err) => {
+                    state.error_message = err;
+                    return Task::none();
+                }
             };
-
-            let mut new_instrument = instrument.clone();
-            new_instrument.timeframe = timeframe.clone();
-
-            state.watchlist.push(new_instrument.clone());
-
-            state.watchlist.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+            let timeframe = new_instrument.timeframe.clone();
 
             state.input_text = "".to_string();
             state.error_message = "".to_string();
 
+            let decimals = new_instrument.decimals;
+            let sparkline_task = if state.sparklines.contains_key(&symbol) {
+                Task::none()
+            } else {
+                fetch_sparklines(std::slice::from_ref(&new_instrument))
+            };
             state.displayed_symbol = Some(new_instrument);
 
-            Task::perform(
-                {
-                    let symbol = symbol.clone();
-                    let timeframe = timeframe.clone();
-                    let decimals = instrument.decimals.clone();
+            let candles_task = load_candles(state, symbol.clone(), timeframe, decimals);
 
-                    async move {
-                        match get_candles(&symbol, &timeframe, decimals).await {
-                            Ok(candles) => Message::CandlesFetched(candles, symbol),
-                            Err(err) => Message::FetchError(err),
-                        }
-                    }
-                },
-                |msg| msg,
-            )
+            Task::batch(vec![candles_task, sparkline_task])
         }
         Message::InitApp => {
             state.loading = true;
 
             Task::perform(
                 async move {
-                    let cached: Option<Vec<Symbol>> = fs::read_to_string("symbols.json")
-                        .ok()
-                        .and_then(|data| serde_json::from_str(&data).ok());
-
-                    if let Some(symbols) = cached {
-                        if !symbols.is_empty() {
-                            println!("Loaded symbols from file");
-                            return Message::SymbolsFetched(symbols);
-                        }
+                    if let Some(symbols) = read_cached_symbols() {
+                        println!("Loaded symbols from file");
+                        return Message::SymbolsFetched(symbols);
                     }
 
                     match get_symbols().await {
@@ -289,19 +628,743 @@ pub fn update(state: &mut State, message: Message) -> Task<Message> {
             state.instruments = instruments.clone();
             state.loading = false;
 
-            if let Ok(json) = serde_json::to_string_pretty(&instruments) {
-                if let Ok(mut file) = File::create("symbols.json") {
-                    if let Err(e) = file.write_all(json.as_bytes()) {
-                        eprintln!("Failed to write instruments to file: {}", e);
-                    }
-                } else {
-                    eprintln!("Failed to create file for instruments");
+            write_cached_symbols(&instruments);
+
+            let defaults = &config::get().defaults.symbols;
+            if state.watchlist().symbols.is_empty() && !defaults.is_empty() {
+                let (symbols, issues) = validate(defaults, &state.instruments);
+                state.error_message = issues
+                    .iter()
+                    .map(|issue| issue.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                state.watchlist_mut().symbols = symbols;
+
+                return activate_watchlist(state, state.active_watchlist);
+            }
+
+            Task::perform(async {}, |_| Message::UpdateSelectOptions)
+        }
+        Message::SwitchWatchlist(name) => {
+            let Some(index) = state.watchlists.iter().position(|w| w.name == name) else {
+                return Task::none();
+            };
+
+            if index == state.active_watchlist {
+                return Task::none();
+            }
+
+            activate_watchlist(state, index)
+        }
+        Message::WatchlistNameInput(input) => {
+            state.watchlist_name_input = input;
+            Task::none()
+        }
+        Message::CreateWatchlist => {
+            let name = unique_name(&state.watchlists, &state.watchlist_name_input);
+            let timeframe = state.watchlist().timeframe.clone();
+
+            state.watchlists.push(Watchlist::new(name.clone(), timeframe));
+            state.watchlist_name_input = "".to_string();
+            state.watchlist_select_state = combo_box::State::new(state.watchlist_names());
+
+            Task::perform(async {}, move |_| Message::SwitchWatchlist(name.clone()))
+        }
+        Message::RenameWatchlist => {
+            let input = state.watchlist_name_input.trim().to_string();
+
+            if input.is_empty() || input == state.watchlist().name {
+                return Task::none();
+            }
+
+            state.watchlist_mut().name = unique_name(&state.watchlists, &input);
+            state.watchlist_name_input = "".to_string();
+            state.watchlist_select_state = combo_box::State::new(state.watchlist_names());
+
+            Task::none()
+        }
+        Message::DuplicateWatchlist => {
+            let mut copy = state.watchlist().clone();
+            copy.name = unique_name(&state.watchlists, &format!("{} copy", copy.name));
+            let name = copy.name.clone();
+
+            state.watchlists.push(copy);
+            state.watchlist_select_state = combo_box::State::new(state.watchlist_names());
+
+            Task::perform(async {}, move |_| Message::SwitchWatchlist(name.clone()))
+        }
+        Message::DeleteWatchlist => {
+            if state.watchlists.len() <= 1 {
+                state.error_message = "Cannot delete the last watchlist".to_string();
+                return Task::none();
+            }
+
+            state.watchlists.remove(state.active_watchlist);
+            state.watchlist_select_state = combo_box::State::new(state.watchlist_names());
+
+            let next = state.active_watchlist.min(state.watchlists.len() - 1);
+            activate_watchlist(state, next)
+        }
+        Message::RefetchSparklines => fetch_sparklines(&state.watchlist().symbols),
+        Message::SparklinesFetched(sparklines) => {
+            state.sparklines.extend(sparklines);
+            Task::none()
+        }
+        Message::SelectQuoteFilter(quote) => {
+            state.quote_filter_label = quote;
+            Task::perform(async {}, |_| Message::UpdateSelectOptions)
+        }
+        Message::DepthUpdated(update) => {
+            let Some(decimals) = state
+                .displayed_symbol
+                .as_ref()
+                .filter(|s| s.symbol == update.symbol)
+                .map(|s| s.decimals)
+            else {
+                return Task::none();
+            };
+
+            if state
+                .order_book
+                .as_ref()
+                .is_none_or(|sync| sync.symbol != update.symbol)
+            {
+                state.order_book = Some(OrderBookSync::new(update.symbol.clone()));
+                state.depth_grouping_label = grouping_label(decimals, state.depth_grouping);
+                state.depth_grouping_state = combo_box::State::new(grouping_labels(decimals));
+            }
+
+            let symbol = update.symbol.clone();
+            let applied = state.order_book.as_mut().map(|sync| sync.on_update(update));
+            let needs_snapshot = match applied {
+                Some(Ok(needs_snapshot)) => needs_snapshot,
+                Some(Err(gap)) => {
+                    state.error_message = format!(
+                        "Depth gap for {}: expected {}, got {}, resyncing",
+                        symbol, gap.expected, gap.received
+                    );
+                    true
                 }
+                None => false,
+            };
+
+            if needs_snapshot {
+                fetch_depth_snapshot(state)
             } else {
-                eprintln!("Failed to serialize instruments to JSON");
+                Task::none()
             }
+        }
+        Message::DepthSnapshotFetched(symbol, result) => {
+            let Some(sync) = state
+                .order_book
+                .as_mut()
+                .filter(|sync| sync.symbol == symbol)
+            else {
+                return Task::none();
+            };
 
-            Task::perform(async {}, |_| Message::UpdateSelectOptions)
+            match result {
+                Ok(snapshot) if sync.on_snapshot(&snapshot) => fetch_depth_snapshot(state),
+                Ok(_) => Task::none(),
+                Err(err) => {
+                    println!("Depth snapshot error for {}: {}", symbol, err);
+                    sync.on_snapshot_failed();
+                    Task::none()
+                }
+            }
+        }
+        Message::DepthDisconnected(symbol) => {
+            if state
+                .order_book
+                .as_ref()
+                .is_some_and(|sync| sync.symbol == symbol)
+            {
+                state.order_book = None;
+            }
+
+            Task::none()
+        }
+        Message::SelectDepthGrouping(label) => {
+            let decimals = state.displayed_symbol.as_ref().map(|s| s.decimals).unwrap_or(8);
+
+            if let Some(multiplier) = label
+                .parse::<Decimal>()
+                .ok()
+                .and_then(|increment| (increment / tick_size(decimals)).to_u32())
+                .filter(|multiplier| DEPTH_GROUPINGS.contains(multiplier))
+            {
+                state.depth_grouping = multiplier;
+                state.depth_grouping_label = label;
+            }
+
+            Task::none()
+        }
+        Message::ToggleDepthChart(show) => {
+            state.show_depth_chart = show;
+            Task::none()
+        }
+        Message::TradeReceived(symbol, trade) => {
+            if state
+                .displayed_symbol
+                .as_ref()
+                .is_none_or(|s| s.symbol != symbol)
+            {
+                return Task::none();
+            }
+
+            let events = state.paper.on_price(&symbol, trade.price, trade.time);
+            report_paper_events(state, events);
+
+            let mut task = Task::none();
+
+            if state
+                .trade_feed
+                .as_ref()
+                .is_none_or(|feed| feed.symbol != symbol)
+            {
+                state.trade_feed = Some(TradeFeed::new(symbol.clone()));
+                task = Task::perform(
+                    async move {
+                        let trades = get_agg_trades(symbol.clone()).await;
+                        Message::TradesFetched(symbol, trades)
+                    },
+                    |msg| msg,
+                );
+            }
+
+            if let Some(feed) = state.trade_feed.as_mut() {
+                feed.push(trade);
+            }
+
+            task
+        }
+        Message::TradesFetched(symbol, result) => {
+            match (state.trade_feed.as_mut(), result) {
+                (Some(feed), Ok(trades)) if feed.symbol == symbol => feed.backfill(trades),
+                (_, Err(err)) => println!("Trades error for {}: {}", symbol, err),
+                _ => {}
+            }
+
+            Task::none()
+        }
+        Message::LargeTradeInput(input) => {
+            if let Ok(notional) = input.trim().parse::<Decimal>()
+                && notional >= Decimal::ZERO
+            {
+                state.large_trade_notional = notional;
+            }
+
+            state.large_trade_input = input;
+            Task::none()
+        }
+        Message::ToggleCacheDebug(show) => {
+            state.show_cache_debug = show;
+            Task::none()
+        }
+        Message::SelectTheme(name) => {
+            if let Some(index) = state.palettes.iter().position(|(n, _)| *n == name) {
+                state.active_palette = index;
+            }
+
+            Task::none()
+        }
+        Message::KeyPressed(chord) => {
+            // The palette keeps its own keys fixed, a remapped shortcut cannot trap it open.
+            if let Some(palette) = state.command_palette.as_mut() {
+                match chord.key.as_str() {
+                    "escape" => state.command_palette = None,
+                    "up" => palette.selected = palette.selected.saturating_sub(1),
+                    "down" => {
+                        palette.selected =
+                            (palette.selected + 1).min(palette.matches.len().saturating_sub(1))
+                    }
+                    _ => {}
+                }
+
+                return Task::none();
+            }
+
+            match state
+                .keymap
+                .action(&chord)
+                .and_then(|action| action_message(state, action))
+            {
+                Some(message) => update(state, message),
+                None => Task::none(),
+            }
+        }
+        Message::FocusSearch => focus_within(search_id()),
+        Message::ZoomIn => {
+            state.visible_candles = (state.visible_candles * 2 / 3)
+                .max(MIN_VISIBLE_CANDLES)
+                .min(state.visible_candles);
+            Task::none()
+        }
+        Message::ZoomOut => {
+            state.visible_candles =
+                (state.visible_candles * 3 / 2 + 1).min(config::get().history.candles);
+            Task::none()
+        }
+        Message::OpenCommandPalette => {
+            state.command_palette = Some(CommandPalette {
+                matches: matching_commands(state, ""),
+                ..CommandPalette::default()
+            });
+
+            text_input::focus(command_palette::input_id())
+        }
+        Message::CloseCommandPalette => {
+            state.command_palette = None;
+            Task::none()
+        }
+        Message::CommandInput(input) => {
+            let matches = matching_commands(state, &input);
+
+            if let Some(palette) = state.command_palette.as_mut() {
+                palette.matches = matches;
+                palette.selected = 0;
+                palette.input = input;
+            }
+
+            Task::none()
+        }
+        Message::RunCommand(index) => {
+            let command = state
+                .command_palette
+                .take()
+                .and_then(|palette| palette.matches.into_iter().nth(index));
+
+            match command {
+                Some(command) => update(state, command.message),
+                None => Task::none(),
+            }
+        }
+        Message::ToggleVolumeProfile(show) => {
+            state.show_volume_profile = show;
+            fetch_volume_profiles(state)
+        }
+        Message::RefetchVolumeProfile => fetch_volume_profiles(state),
+        Message::VolumeProfileFetched(result) => {
+            state.volume_profile_loading = false;
+
+            match result {
+                Ok(profiles) => {
+                    if profiles.truncated {
+                        state.error_message = format!(
+                            "The volume profile only covers the first {} candles of its range",
+                            MAX_KLINES
+                        );
+                    }
+                    state.volume_profiles = Some(profiles);
+                }
+                Err(err) => println!("Volume profile error: {}", err),
+            }
+
+            Task::none()
+        }
+        Message::AnimationTick(now) => {
+            state.now = now;
+            Task::none()
+        }
+        Message::ToggleTickArrows(show) => {
+            state.show_tick_arrows = show;
+            Task::none()
+        }
+        Message::WatchlistPathInput(input) => {
+            state.watchlist_path_input = input;
+            Task::none()
+        }
+        Message::ImportWatchlist => {
+            let path = state.watchlist_path_input.trim().to_string();
+
+            let Some(format) = WatchlistFormat::from_path(&path) else {
+                state.error_message = "Use a .json, .csv or .txt watchlist file".to_string();
+                return Task::none();
+            };
+
+            Task::perform(
+                blocking(move || {
+                    fs::read_to_string(&path)
+                        .map_err(|err| format!("Cannot read {}: {}", path, err))
+                        .and_then(|content| parse(format, &content))
+                }),
+                Message::WatchlistImported,
+            )
+        }
+        Message::WatchlistImported(result) => {
+            let file = match result {
+                Ok(file) => file,
+                Err(err) => {
+                    state.error_message = err;
+                    return Task::none();
+                }
+            };
+
+            let (mut symbols, issues) = validate(&file.symbols, &state.instruments);
+            let timeframe = file
+                .timeframe
+                .filter(|tf| state.timeframe_select_state.options().contains(tf))
+                .unwrap_or_else(|| state.watchlist().timeframe.clone());
+
+            for symbol in symbols.iter_mut() {
+                symbol.timeframe = timeframe.clone();
+            }
+            symbols.sort_by(|a, b| a.symbol.cmp(&b.symbol));
+
+            let name = unique_name(
+                &state.watchlists,
+                file.name.as_deref().unwrap_or("Imported"),
+            );
+            let mut watchlist = Watchlist::new(name.clone(), timeframe);
+            watchlist.symbols = symbols;
+
+            state.error_message =
+                format!("Imported {} symbols into {}", watchlist.symbols.len(), name);
+            if !issues.is_empty() {
+                let skipped = issues.iter().map(|i| i.to_string()).collect::<Vec<_>>();
+                state.error_message =
+                    format!("{}, skipped: {}", state.error_message, skipped.join(", "));
+            }
+
+            state.watchlists.push(watchlist);
+            state.watchlist_path_input = "".to_string();
+            state.watchlist_select_state = combo_box::State::new(state.watchlist_names());
+
+            activate_watchlist(state, state.watchlists.len() - 1)
+        }
+        Message::ExportWatchlist => {
+            let path = state.watchlist_path_input.trim().to_string();
+
+            let Some(format) = WatchlistFormat::from_path(&path) else {
+                state.error_message = "Use a .json, .csv or .txt watchlist file".to_string();
+                return Task::none();
+            };
+
+            let name = state.watchlist().name.clone();
+            let content = match export(format, state.watchlist()) {
+                Ok(content) => content,
+                Err(err) => {
+                    state.error_message = format!("Failed to export watchlist: {}", err);
+                    return Task::none();
+                }
+            };
+
+            Task::perform(
+                blocking(move || {
+                    fs::write(&path, content)
+                        .map(|()| path)
+                        .map_err(|err| err.to_string())
+                }),
+                move |result| Message::WatchlistExported(name.clone(), result),
+            )
+        }
+        Message::WatchlistExported(name, result) => {
+            state.error_message = match result {
+                Ok(path) => format!("Exported {} to {}", name, path),
+                Err(err) => format!("Failed to export watchlist: {}", err),
+            };
+
+            Task::none()
+        }
+        Message::SelectExportSize(label) => {
+            if let Some(size) = parse_size(&label) {
+                state.export_size = size;
+                state.export_size_label = label;
+            }
+
+            Task::none()
+        }
+        Message::ExportChart(format) => {
+            let (Some(chart), Some(path)) = (state.chart(), export_path(state, format.extension()))
+            else {
+                state.error_message = "Open a chart to export it".to_string();
+                return Task::none();
+            };
+
+            let snapshot = ChartSnapshot::new(&chart);
+            let (width, height) = state.export_size;
+            let scale = config::get().export.scale;
+
+            Task::perform(
+                async move {
+                    create_export_dir()?;
+                    write_chart(snapshot, path, width, height, scale).await
+                },
+                Message::ChartExported,
+            )
+        }
+        Message::ChartExported(result) => {
+            state.error_message = match result {
+                Ok(path) => format!("Exported chart to {}", path.display()),
+                Err(err) => format!("Failed to export chart: {}", err),
+            };
+
+            Task::none()
+        }
+        Message::ExportCandles(format, range) => {
+            let (Some(chart), Some(path)) = (state.chart(), export_path(state, format.extension()))
+            else {
+                state.error_message = "Open a chart to export its candles".to_string();
+                return Task::none();
+            };
+
+            let rows = match range {
+                CandleRange::Visible => chart.visible_range(),
+                CandleRange::Full => 0..chart.candles.len(),
+            };
+            let table = CandleTable::new(
+                chart.candles.range(rows.clone()),
+                config::get().export.timestamps,
+            );
+
+            // The shown backtest's series go next to its candles, computed over the whole
+            // series so the first rows exported have values too.
+            let indicators = match state.displayed_backtest() {
+                Some(report) => parse_strategy(&report.strategy).map(|strategy| {
+                    let candles = chart.candles.iter().cloned().collect::<Vec<_>>();
+                    strategy.indicators(&candles)
+                }),
+                None => Ok(Vec::new()),
+            };
+
+            let result = indicators
+                .and_then(|indicators| {
+                    indicators.into_iter().try_fold(table, |table, mut indicator| {
+                        indicator.values = indicator.values[rows.clone()].to_vec();
+                        table.with_indicator(indicator)
+                    })
+                })
+                .and_then(|table| {
+                    create_export_dir()?;
+                    table.encode(format)
+                })
+                .and_then(|content| {
+                    fs::write(&path, content)
+                        .map_err(|err| format!("Cannot write {}: {}", path.display(), err))
+                });
+
+            state.error_message = match result {
+                Ok(()) => format!("Exported {} candles to {}", rows.len(), path.display()),
+                Err(err) => format!("Failed to export candles: {}", err),
+            };
+
+            Task::none()
+        }
+        Message::TogglePaperTrading(show) => {
+            state.show_paper_trading = show;
+            Task::none()
+        }
+        Message::PaperSide(side) => {
+            state.paper_side = side;
+            Task::none()
+        }
+        Message::PaperOrderType(order_type) => {
+            state.paper_order_type = order_type;
+            Task::none()
+        }
+        Message::PaperQuantityInput(input) => {
+            state.paper_quantity_input = input;
+            Task::none()
+        }
+        Message::PaperPriceInput(input) => {
+            state.paper_price_input = input;
+            Task::none()
+        }
+        Message::PaperStopInput(input) => {
+            state.paper_stop_input = input;
+            Task::none()
+        }
+        Message::PlacePaperOrder => {
+            let Some(symbol) = state.displayed_symbol.clone() else {
+                return Task::none();
+            };
+
+            let input = &state.paper_quantity_input;
+            let Ok(quantity) = input.trim().parse::<Decimal>() else {
+                state.error_message = format!("Quantity must be a number, got {:?}", input);
+                return Task::none();
+            };
+
+            let last_price = state.last_price(&symbol.symbol);
+            let side = state.paper_side;
+            let result = state
+                .paper_order_type
+                .kind(&state.paper_price_input, &state.paper_stop_input)
+                .and_then(|kind| {
+                    state
+                        .paper
+                        .place(&symbol, side, kind, quantity, last_price, now_millis())
+                });
+
+            match result {
+                Ok(events) if events.is_empty() => {
+                    if let Some(order) = state.paper.orders.last() {
+                        state.error_message = format!("Placed {}", order);
+                    }
+                }
+                Ok(events) => report_paper_events(state, events),
+                Err(err) => state.error_message = format!("Paper order refused: {}", err),
+            }
+
+            Task::none()
+        }
+        Message::CancelPaperOrder(id) => {
+            if let Some(order) = state.paper.cancel(id) {
+                state.error_message = format!("Cancelled {}", order);
+            }
+
+            Task::none()
+        }
+        Message::TogglePortfolio(show) => {
+            state.show_portfolio = show;
+            Task::none()
+        }
+        Message::PortfolioAssetInput(input) => {
+            state.portfolio_asset_input = input;
+            Task::none()
+        }
+        Message::PortfolioQuantityInput(input) => {
+            state.portfolio_quantity_input = input;
+            Task::none()
+        }
+        Message::PortfolioCostInput(input) => {
+            state.portfolio_cost_input = input;
+            Task::none()
+        }
+        Message::SetHolding => {
+            let parse = |label: &str, input: &str| {
+                input
+                    .trim()
+                    .parse::<Decimal>()
+                    .map_err(|_| format!("{} must be a number, got {:?}", label, input))
+            };
+
+            let result = parse("Quantity", &state.portfolio_quantity_input).and_then(|quantity| {
+                let cost = parse("Average cost", &state.portfolio_cost_input)?;
+                state
+                    .portfolio
+                    .set(&state.portfolio_asset_input, quantity, cost)
+            });
+
+            if let Err(err) = result {
+                state.error_message = err;
+                return Task::none();
+            }
+
+            state.portfolio_asset_input.clear();
+            state.portfolio_quantity_input.clear();
+            state.portfolio_cost_input.clear();
+            state.portfolio_history.clear();
+
+            Task::batch(vec![save_portfolio(state), fetch_portfolio_prices(state)])
+        }
+        Message::RemoveHolding(asset) => {
+            if state.portfolio.remove(&asset).is_none() {
+                return Task::none();
+            }
+
+            state.portfolio_history.clear();
+            save_portfolio(state)
+        }
+        Message::PortfolioPathInput(input) => {
+            state.portfolio_path_input = input;
+            Task::none()
+        }
+        Message::ImportTrades => {
+            let path = state.portfolio_path_input.trim().to_string();
+
+            Task::perform(
+                blocking(move || {
+                    fs::read_to_string(&path)
+                        .map_err(|err| format!("Cannot read {}: {}", path, err))
+                }),
+                Message::TradeHistoryRead,
+            )
+        }
+        Message::TradeHistoryRead(result) => {
+            match result.and_then(|csv| state.portfolio.import_trades(&csv)) {
+                Ok(count) => {
+                    state.error_message = format!("Imported {} trades", count);
+                    state.portfolio_history.clear();
+
+                    Task::batch(vec![save_portfolio(state), fetch_portfolio_prices(state)])
+                }
+                Err(err) => {
+                    state.error_message = format!("Failed to import trades: {}", err);
+                    Task::none()
+                }
+            }
+        }
+        Message::PortfolioSaved(result) => {
+            if let Err(err) = result {
+                state.error_message = format!("Failed to save portfolio: {}", err);
+            }
+
+            Task::none()
+        }
+        Message::ToggleBacktest(show) => {
+            state.show_backtest = show;
+            Task::none()
+        }
+        Message::BacktestStrategyInput(input) => {
+            state.backtest_strategy_input = input;
+            Task::none()
+        }
+        Message::RunBacktest => {
+            let Some((symbol, timeframe)) = state.visible_series() else {
+                return Task::none();
+            };
+            let Some(candles) = state.candles.get(symbol, timeframe) else {
+                return Task::none();
+            };
+
+            let (symbol, timeframe) = (symbol.to_string(), timeframe.to_string());
+            let recent = candles.iter().cloned().collect::<Vec<_>>();
+            let store = state.candle_store.clone();
+            let spec = state.backtest_strategy_input.clone();
+            let settings = &config::get().backtest;
+            let backtester = Backtester {
+                initial_cash: settings.initial_cash,
+                fee_rate: settings.fee_rate,
+                slippage: settings.slippage,
+            };
+            state.error_message = format!("Running the backtest on {}", symbol);
+
+            Task::perform(
+                {
+                    let timeframe = timeframe.clone();
+
+                    // Over the whole stored history, the window only holds the latest bars.
+                    async move {
+                        let mut candles =
+                            get_stored_history(store, symbol.clone(), timeframe, recent).await;
+
+                        // The forming candle's close still moves, signals only see closed ones.
+                        candles.pop();
+
+                        parse_strategy(&spec).and_then(|mut strategy| {
+                            backtester.run(&symbol, &candles, &mut *strategy)
+                        })
+                    }
+                },
+                move |result| Message::BacktestFinished(timeframe.clone(), result),
+            )
+        }
+        Message::BacktestFinished(timeframe, result) => {
+            match result {
+                Ok(report) => {
+                    state.error_message = format!(
+                        "Backtested {} over {} candles",
+                        report.symbol,
+                        report.equity.len()
+                    );
+                    state.backtest = Some((timeframe, report));
+                    state.backtest_run += 1;
+                }
+                Err(err) => state.error_message = format!("Backtest failed: {}", err),
+            }
+
+            Task::none()
         }
     }
 }
@@ -1,39 +1,118 @@
-use crate::symbols::{Symbol};
+use crate::symbols::Symbol;
 use std::collections::HashSet;
 use rust_decimal::Decimal;
 use rust_decimal::prelude::*;
 
+pub const QUOTE_FILTERS: [&str; 7] = ["All", "USDT", "FDUSD", "USDC", "BTC", "ETH", "EUR"];
+
+fn is_selectable(
+    instrument: &Symbol,
+    watchlist_symbols: &HashSet<&str>,
+    quote_filter: Option<&str>,
+) -> bool {
+    instrument.is_trading()
+        && !watchlist_symbols.contains(instrument.symbol.as_str())
+        && quote_filter.is_none_or(|quote| instrument.quote_asset == quote)
+}
+
+/// Characters of `query` must appear in `target` in order, fewer gaps rank higher.
+fn subsequence_gaps(target: &str, query: &str) -> Option<usize> {
+    let mut gaps = 0;
+    let mut target_chars = target.chars();
+
+    for query_char in query.chars() {
+        loop {
+            match target_chars.next() {
+                Some(c) if c == query_char => break,
+                Some(_) => gaps += 1,
+                None => return None,
+            }
+        }
+    }
+
+    Some(gaps)
+}
+
+/// Lower is better: exact and prefix hits first, then base asset hits, then looser matches.
+pub fn match_score(instrument: &Symbol, query: &str) -> Option<usize> {
+    let query = query.trim().to_uppercase();
+
+    if let Some((base, quote)) = query.split_once('/') {
+        let base_matches = instrument.base_asset.starts_with(base.trim());
+        let quote_matches = instrument.quote_asset.starts_with(quote.trim());
+
+        return (base_matches && quote_matches).then_some(
+            instrument.base_asset.len() - base.trim().len() + instrument.quote_asset.len()
+                - quote.trim().len(),
+        );
+    }
+
+    let symbol = instrument.symbol.as_str();
+
+    if symbol == query {
+        Some(0)
+    } else if instrument.base_asset == query {
+        Some(1)
+    } else if symbol.starts_with(&query) {
+        Some(2)
+    } else if instrument.base_asset.starts_with(&query) {
+        Some(3)
+    } else if let Some(position) = symbol.find(&query) {
+        Some(10 + position)
+    } else {
+        subsequence_gaps(symbol, &query).map(|gaps| 100 + gaps)
+    }
+}
+
+/// Case-insensitive `match_score` for plain labels like command palette entries. Lower
+/// is better: prefix, then word start, then substring, then subsequence hits.
+pub fn text_match_score(text: &str, query: &str) -> Option<usize> {
+    let text = text.to_lowercase();
+    let query = query.trim().to_lowercase();
+
+    if text.starts_with(&query) {
+        Some(0)
+    } else if let Some(position) = text.find(&format!(" {}", query)) {
+        Some(1 + position)
+    } else if let Some(position) = text.find(&query) {
+        Some(100 + position)
+    } else {
+        subsequence_gaps(&text, &query).map(|gaps| 1000 + gaps)
+    }
+}
+
 pub fn get_current_select_state(
-    instruments: &Vec<Symbol>,
+    instruments: &[Symbol],
     input: &str,
-    watchlist: &Vec<Symbol>,
+    watchlist: &[Symbol],
+    quote_filter: Option<&str>,
 ) -> Vec<String> {
-    let lowercase_input = input.to_lowercase();
-
-    let watchlist_symbols: HashSet<String> =
-        watchlist.iter().map(|item| item.symbol.clone()).collect();
+    let watchlist_symbols: HashSet<&str> =
+        watchlist.iter().map(|item| item.symbol.as_str()).collect();
 
-    let mut sorted_instruments = instruments
+    let mut ranked_instruments = instruments
         .iter()
-        .filter_map(|i| {
-            if i.symbol.to_lowercase().contains(&lowercase_input)
-                && !watchlist_symbols.contains(&i.symbol)
-            {
-                Some(i.symbol.clone())
-            } else {
-                None
-            }
-        })
+        .filter(|i| is_selectable(i, &watchlist_symbols, quote_filter))
+        .filter_map(|i| match_score(i, input).map(|score| (score, i)))
         .collect::<Vec<_>>();
 
-    sorted_instruments.sort();
+    ranked_instruments.sort_by(|(a_score, a), (b_score, b)| {
+        a_score
+            .cmp(b_score)
+            .then(a.symbol.len().cmp(&b.symbol.len()))
+            .then(a.symbol.cmp(&b.symbol))
+    });
 
-    sorted_instruments
+    ranked_instruments
+        .into_iter()
+        .map(|(_, i)| i.symbol.clone())
+        .collect()
 }
 
 pub fn get_default_select_state(
     instruments: &[Symbol],
     watchlist: &[Symbol],
+    quote_filter: Option<&str>,
 ) -> Vec<String> {
     let mut sorted_instruments: Vec<&Symbol> = instruments.iter().collect();
     sorted_instruments.sort_by_key(|i| i.symbol.clone());
@@ -43,12 +122,43 @@ pub fn get_default_select_state(
 
     sorted_instruments
         .into_iter()
-        .filter(|i| !watchlist_symbols.contains(i.symbol.as_str()))
+        .filter(|i| is_selectable(i, &watchlist_symbols, quote_filter))
         .take(10)
         .map(|i| i.symbol.clone())
         .collect()
 }
 
+pub fn price_to_y(price: Decimal, min_price: Decimal, max_price: Decimal, height: f32) -> Decimal {
+    let normalized = (price - min_price) / (max_price - min_price);
+    let height_decimal = Decimal::from_f32(height).unwrap();
+    height_decimal - normalized * height_decimal
+}
+
+/// The `f64` counterpart of `price_to_y`, within a pixel of it for any price on screen.
+#[derive(Debug, Clone, Copy)]
+pub struct PriceScale {
+    min: f64,
+    range: f64,
+    height: f64,
+}
+
+impl PriceScale {
+    pub fn new(display_min: Decimal, display_max: Decimal, screen_height: f32) -> Self {
+        let min = display_min.to_f64().unwrap_or_default();
+        let max = display_max.to_f64().unwrap_or_default();
+
+        Self {
+            min,
+            range: max - min,
+            height: screen_height as f64,
+        }
+    }
+
+    pub fn y(&self, price: f64) -> f32 {
+        (self.height - (price - self.min) / self.range * self.height) as f32
+    }
+}
+
 pub fn calculate_tick_count(min: Decimal, max: Decimal) -> (usize, Decimal) {
     let range = max - min;
     let nice_step = nice_step_from_range(range);
@@ -108,3 +218,66 @@ pub fn estimate_y_axis_width(
 
     max_label_len as f32 * avg_char_width + padding
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CASES: usize = 10_000;
+
+    fn decimal(rng: &mut fastrand::Rng, min: i64, max: i64, scale: u32) -> Decimal {
+        Decimal::new(rng.i64(min..max), scale)
+    }
+
+    #[test]
+    fn labels_rank_prefixes_before_word_starts_and_gaps() {
+        let labels = ["Add BTCUSDT", "Timeframe 1h", "Theme: light", "Zoom in"];
+        let ranked = |query: &str| {
+            let mut hits = labels
+                .iter()
+                .filter_map(|label| text_match_score(label, query).map(|score| (score, *label)))
+                .collect::<Vec<_>>();
+            hits.sort();
+            hits.into_iter().map(|(_, label)| label).collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            ranked("t"),
+            vec!["Theme: light", "Timeframe 1h", "Add BTCUSDT"]
+        );
+        assert_eq!(ranked("BTC"), vec!["Add BTCUSDT"]);
+        assert_eq!(ranked("tf1h"), vec!["Timeframe 1h"]);
+        assert_eq!(ranked("zoom out"), Vec::<&str>::new());
+        assert_eq!(ranked("").len(), labels.len());
+    }
+
+    #[test]
+    fn f64_scale_matches_decimal_within_a_pixel() {
+        let mut rng = fastrand::Rng::with_seed(39);
+
+        for _ in 0..CASES {
+            let scale = rng.u32(0..9);
+            let display_min = decimal(&mut rng, 1, 10_000_000_000, scale);
+            let display_max = display_min + decimal(&mut rng, 1, 1_000_000_000, scale);
+            let screen_height = rng.f32() * 4000.0 + 10.0;
+            let price = display_min + (display_max - display_min) * decimal(&mut rng, 0, 1001, 3);
+
+            let exact = price_to_y(price, display_min, display_max, screen_height)
+                .to_f32()
+                .unwrap();
+            let fast =
+                PriceScale::new(display_min, display_max, screen_height).y(price.to_f64().unwrap());
+
+            assert!(
+                (exact - fast).abs() <= 1.0,
+                "{} in {}..{} at {}px: decimal {} vs f64 {}",
+                price,
+                display_min,
+                display_max,
+                screen_height,
+                exact,
+                fast
+            );
+        }
+    }
+}
@@ -1,9 +1,32 @@
-use crate::ui::instrument_select::render_select;
+use crate::ui::depth_select::render_depth_grouping_select;
+use crate::ui::command_palette::render_command_palette;
+use crate::ui::backtest_panel::render_backtest_panel;
+use crate::ui::chart_export::render_chart_export;
+use crate::ui::instrument_select::{render_select, search_id};
+use crate::ui::paper_panel::render_paper_panel;
+use crate::ui::portfolio_panel::render_portfolio_panel;
+use crate::ui::quote_select::render_quote_select;
+use crate::ui::theme_select::render_theme_select;
 use crate::ui::timeframe_select::render_timeframe_select;
+use crate::ui::watchlist_select::{
+    render_watchlist_actions, render_watchlist_select, render_watchlist_transfer,
+};
 use crate::{Message, State};
 use iced::widget::image::{self, Image};
-use iced::widget::{Column, Row, Rule, button, canvas, container, text};
-use iced::{Background, Element, Fill, Length, Padding, Theme, widget, Shrink};
+use iced::alignment::Vertical;
+use iced::widget::{
+    Column, Row, Rule, Space, button, canvas, container, scrollable, stack, text, text_input,
+};
+use iced::{Background, Color, Element, Fill, Length, Padding, Theme, widget, Shrink};
+use chrono::{Local, TimeZone};
+use juicy_lemon::colors::{color, tick_color, trade_color};
+use juicy_lemon::graph::depth::DepthChart;
+use juicy_lemon::graph::sparkline::Sparkline;
+use juicy_lemon::orderbook::{DEPTH_ROWS, DepthLevel, Side};
+use juicy_lemon::theme::Palette;
+use juicy_lemon::trades::AggTrade;
+use rust_decimal::Decimal;
+use rust_decimal::prelude::*;
 
 fn vertical_rule() -> Column<'static, Message> {
     Column::new()
@@ -19,7 +42,290 @@ fn horizontal_rule() -> Row<'static, Message> {
         .push(Rule::horizontal(1))
 }
 
-pub fn view(state: &State) -> Element<Message> {
+fn price_cell<'a>(state: &State, symbol: &str, formatted_price: String) -> Element<'a, Message> {
+    let tick = state.price_ticks.get(symbol);
+    let palette = state.palette();
+
+    let mut price_row = Row::new().spacing(4).push(text(formatted_price));
+
+    if state.show_tick_arrows
+        && let Some(tick) = tick
+    {
+        price_row = price_row.push(text(tick.arrow()).size(10).color(tick_color(&palette, tick)));
+    }
+
+    let flash = tick
+        .map(|tick| Color {
+            a: tick.flash_intensity(state.now) * 0.5,
+            ..tick_color(&palette, tick)
+        })
+        .unwrap_or(Color::TRANSPARENT);
+
+    let cell = container(price_row)
+        .style(move |_| container::Style {
+            background: Some(Background::Color(flash)),
+            ..container::Style::default()
+        })
+        .width(Fill);
+
+    match tick {
+        Some(tick) => widget::tooltip(
+            cell,
+            container(text(format!("Previous: {}", tick.previous)).size(12))
+                .padding(4)
+                .style(container::rounded_box),
+            widget::tooltip::Position::Bottom,
+        )
+        .into(),
+        None => cell.into(),
+    }
+}
+
+fn depth_row<'a>(
+    palette: &Palette,
+    level: &DepthLevel,
+    max_cumulative: Decimal,
+    side: Side,
+) -> Element<'a, Message> {
+    let side_color = match side {
+        Side::Bid => color(palette.bull),
+        Side::Ask => color(palette.bear),
+    };
+
+    let filled = if max_cumulative > Decimal::ZERO {
+        (level.cumulative / max_cumulative * dec!(1000)).to_u16().unwrap_or(0)
+    } else {
+        0
+    };
+
+    let bar = widget::row![
+        Space::with_width(Length::FillPortion(1000 - filled)),
+        container(Space::new(Fill, Fill))
+            .style(move |_| container::Style {
+                background: Some(Background::Color(Color { a: 0.2, ..side_color })),
+                ..container::Style::default()
+            })
+            .width(Length::FillPortion(filled.max(1))),
+    ]
+    .height(18);
+
+    let values = widget::row![
+        text(level.price.to_string()).size(12).color(side_color).width(Fill),
+        text(level.size.normalize().to_string()).size(12).width(Fill),
+        text(level.cumulative.normalize().to_string()).size(12).width(Fill),
+    ]
+    .spacing(5)
+    .padding(Padding {
+        top: 1.0,
+        right: 5.0,
+        bottom: 1.0,
+        left: 5.0,
+    });
+
+    stack![bar, values].into()
+}
+
+fn cache_debug_panel(state: &State) -> Column<'_, Message> {
+    let mut panel = widget::column![
+        text(format!(
+            "Candle cache {:.1} / {} KB",
+            state.candles.total_bytes() as f32 / 1024.0,
+            state.candles.max_bytes / 1024
+        ))
+        .size(12)
+    ]
+    .spacing(2)
+    .padding(Padding {
+        top: 0.0,
+        right: 10.0,
+        bottom: 10.0,
+        left: 10.0,
+    });
+
+    for series in state.candles.sizes(state.visible_series()) {
+        let label = format!(
+            "{}{} {} - {} bars, {:.1} KB",
+            if series.visible { "* " } else { "" },
+            series.symbol,
+            series.timeframe,
+            series.candles,
+            series.bytes as f32 / 1024.0
+        );
+
+        panel = panel.push(text(label).size(11));
+    }
+
+    panel
+}
+
+fn depth_chart(state: &State) -> Option<Element<'_, Message>> {
+    if !state.show_depth_chart {
+        return None;
+    }
+
+    let book = state.displayed_order_book()?.book.as_ref()?;
+    let decimals = state.displayed_symbol.as_ref()?.decimals;
+
+    Some(
+        container(
+            canvas(DepthChart {
+                book,
+                decimals,
+                palette: state.palette(),
+            })
+            .width(Fill)
+            .height(220),
+        )
+            .padding(Padding {
+                top: 0.0,
+                left: 10.0,
+                bottom: 10.0,
+                right: 10.0,
+            })
+            .into(),
+    )
+}
+
+fn trade_row<'a>(
+    palette: &Palette,
+    trade: &AggTrade,
+    large_notional: Decimal,
+) -> Element<'a, Message> {
+    let time = Local
+        .timestamp_millis_opt(trade.time as i64)
+        .single()
+        .map(|t| t.format("%H:%M:%S").to_string())
+        .unwrap_or_default();
+
+    let side_color = trade_color(palette, trade);
+    let highlight = if trade.notional() >= large_notional {
+        Color { a: 0.3, ..side_color }
+    } else {
+        Color::TRANSPARENT
+    };
+
+    container(
+        widget::row![
+            text(trade.price.to_string()).size(12).color(side_color).width(Fill),
+            text(trade.quantity.normalize().to_string()).size(12).width(Fill),
+            text(time).size(12).width(Fill),
+        ]
+        .spacing(5),
+    )
+    .style(move |_| container::Style {
+        background: Some(Background::Color(highlight)),
+        ..container::Style::default()
+    })
+    .padding(Padding {
+        top: 1.0,
+        right: 5.0,
+        bottom: 1.0,
+        left: 5.0,
+    })
+    .into()
+}
+
+fn trades_panel(state: &State) -> Column<'_, Message> {
+    let header = widget::row![
+        text("Trades").size(14).width(Fill),
+        text_input("Large", &state.large_trade_input)
+            .on_input(Message::LargeTradeInput)
+            .size(12)
+            .width(80),
+    ]
+    .spacing(5)
+    .align_y(Vertical::Center)
+    .padding(Padding {
+        top: 0.0,
+        right: 5.0,
+        bottom: 5.0,
+        left: 5.0,
+    });
+
+    let rows = state
+        .displayed_trade_feed()
+        .map(|feed| {
+            feed.latest()
+                .map(|trade| trade_row(&state.palette(), trade, state.large_trade_notional))
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or_default();
+
+    widget::column![header, scrollable(Column::with_children(rows)).height(Fill)]
+        .width(Length::Fixed(240.0))
+        .height(Fill)
+}
+
+fn order_book_panel(state: &State) -> Column<'_, Message> {
+    let mut panel = widget::column![
+        widget::row![
+            text("Order book").size(14).width(Fill),
+            render_depth_grouping_select(state).size(12.0).width(80),
+        ]
+        .spacing(5)
+        .padding(Padding {
+            top: 0.0,
+            right: 5.0,
+            bottom: 5.0,
+            left: 5.0,
+        })
+    ]
+    .width(Length::Fixed(240.0))
+    .padding(Padding {
+        top: 10.0,
+        right: 0.0,
+        bottom: 10.0,
+        left: 0.0,
+    });
+
+    let Some(book) = state.displayed_order_book().and_then(|sync| sync.book.as_ref()) else {
+        return panel.push(
+            container(text("Syncing order book...").size(12)).padding(Padding {
+                top: 0.0,
+                right: 5.0,
+                bottom: 0.0,
+                left: 5.0,
+            }),
+        );
+    };
+
+    let palette = state.palette();
+    let increment = state.depth_increment();
+    let asks = book.grouped(Side::Ask, increment, DEPTH_ROWS);
+    let bids = book.grouped(Side::Bid, increment, DEPTH_ROWS);
+
+    let max_cumulative = asks
+        .last()
+        .map(|l| l.cumulative)
+        .unwrap_or_default()
+        .max(bids.last().map(|l| l.cumulative).unwrap_or_default());
+
+    for level in asks.iter().rev() {
+        panel = panel.push(depth_row(&palette, level, max_cumulative, Side::Ask));
+    }
+
+    let spread = match (book.best_bid(), book.best_ask()) {
+        (Some(bid), Some(ask)) => format!("Spread {}", (ask - bid).normalize()),
+        _ => "Spread -".to_string(),
+    };
+
+    panel = panel.push(
+        container(text(spread).size(12)).padding(Padding {
+            top: 4.0,
+            right: 5.0,
+            bottom: 4.0,
+            left: 5.0,
+        }),
+    );
+
+    for level in &bids {
+        panel = panel.push(depth_row(&palette, level, max_cumulative, Side::Bid));
+    }
+
+    panel
+}
+
+pub fn view(state: &State) -> Element<'_, Message> {
     if state.loading {
         return text("Loading...").size(20).into();
     }
@@ -29,7 +335,30 @@ pub fn view(state: &State) -> Element<Message> {
     }
 
     let mut symbols_column = widget::column![
-        widget::row![render_select(&state).size(14.0)].padding(Padding {
+        widget::row![render_watchlist_select(state).size(14.0)].padding(Padding {
+            top: 0.0,
+            right: 10.0,
+            bottom: 5.0,
+            left: 10.0,
+        }),
+        render_watchlist_actions(state).padding(Padding {
+            top: 0.0,
+            right: 10.0,
+            bottom: 5.0,
+            left: 10.0,
+        }),
+        render_watchlist_transfer(state).padding(Padding {
+            top: 0.0,
+            right: 10.0,
+            bottom: 10.0,
+            left: 10.0,
+        }),
+        widget::row![
+            container(render_select(state).size(14.0)).id(search_id()),
+            render_quote_select(state).size(14.0).width(80),
+        ]
+        .spacing(5)
+        .padding(Padding {
             top: 0.0,
             right: 10.0,
             bottom: 10.0,
@@ -44,24 +373,99 @@ pub fn view(state: &State) -> Element<Message> {
         left: 0.0,
     });
 
+    symbols_column = symbols_column.push(
+        widget::row![
+            text("Theme").size(12),
+            render_theme_select(state).size(12.0),
+        ]
+        .spacing(5)
+        .align_y(Vertical::Center)
+        .padding(Padding {
+            top: 0.0,
+            right: 10.0,
+            bottom: 10.0,
+            left: 10.0,
+        }),
+    );
+
+    symbols_column = symbols_column.push(
+        widget::container(
+            widget::checkbox("Tick arrows", state.show_tick_arrows)
+                .on_toggle(Message::ToggleTickArrows)
+                .size(14)
+                .text_size(12),
+        )
+        .padding(Padding {
+            top: 0.0,
+            right: 10.0,
+            bottom: 10.0,
+            left: 10.0,
+        }),
+    );
+
+    symbols_column = symbols_column.push(
+        widget::container(
+            widget::checkbox("Portfolio", state.show_portfolio)
+                .on_toggle(Message::TogglePortfolio)
+                .size(14)
+                .text_size(12),
+        )
+        .padding(Padding {
+            top: 0.0,
+            right: 10.0,
+            bottom: 10.0,
+            left: 10.0,
+        }),
+    );
+
+    symbols_column = symbols_column.push(
+        widget::container(
+            widget::checkbox("Cache debug", state.show_cache_debug)
+                .on_toggle(Message::ToggleCacheDebug)
+                .size(14)
+                .text_size(12),
+        )
+        .padding(Padding {
+            top: 0.0,
+            right: 10.0,
+            bottom: 10.0,
+            left: 10.0,
+        }),
+    );
+
+    if state.show_cache_debug {
+        symbols_column = symbols_column.push(cache_debug_panel(state));
+    }
+
+    if !state.error_message.is_empty() {
+        symbols_column = symbols_column.push(
+            widget::container(text(state.error_message.clone()).size(12))
+                .padding(Padding {
+                    top: 0.0,
+                    right: 10.0,
+                    bottom: 10.0,
+                    left: 10.0,
+                }),
+        );
+    }
+
     let displayed_symbol = match state.displayed_symbol.as_ref() {
         Some(s) => s.symbol.clone(),
         None => "".to_string(),
     };
 
-    for item in &state.watchlist {
-        let formatted_price = if item.price == None {
-            "Loading...".to_string()
-        } else {
-            item.price.as_ref().unwrap().to_string()
+    for item in &state.watchlist().symbols {
+        let formatted_price = match &item.price {
+            Some(price) => price.to_string(),
+            None => "Loading...".to_string(),
         };
 
         let icon_handle = image::Handle::from_path("icons/trash.png");
 
         let background_color = if Some(displayed_symbol.clone()) == Some(item.symbol.clone()) {
-            Background::Color([0.427, 0.157, 0.851].into())
+            Background::Color(color(state.palette().selection))
         } else {
-            Background::Color([0.012, 0.027, 0.071].into())
+            Background::Color(color(state.palette().background))
         };
 
         symbols_column = symbols_column
@@ -78,7 +482,17 @@ pub fn view(state: &State) -> Element<Message> {
                             .height(24)
                             .padding(0)
                             .on_press(Message::SelectSymbol(item.symbol.clone())),
-                        widget::text(formatted_price).width(Fill),
+                        price_cell(state, &item.symbol, formatted_price),
+                        canvas(Sparkline {
+                            closes: state
+                                .sparklines
+                                .get(&item.symbol)
+                                .map(|closes| closes.as_slice())
+                                .unwrap_or_default(),
+                            palette: state.palette(),
+                        })
+                        .width(48)
+                        .height(20),
                         iced::widget::button(Image::new(icon_handle).height(20))
                             .style(move |theme: &Theme, _| button::Style {
                                 background: background_color.into(),
@@ -121,7 +535,33 @@ pub fn view(state: &State) -> Element<Message> {
     ];
 
     if !displayed_symbol.is_empty() {
-        top_row = top_row.push(render_timeframe_select(&state).size(14.0).width(150.0));
+        top_row = top_row
+            .push(
+                widget::checkbox("Volume profile", state.show_volume_profile)
+                    .on_toggle(Message::ToggleVolumeProfile)
+                    .size(14)
+                    .text_size(12),
+            )
+            .push(
+                widget::checkbox("Depth chart", state.show_depth_chart)
+                    .on_toggle(Message::ToggleDepthChart)
+                    .size(14)
+                    .text_size(12),
+            )
+            .push(
+                widget::checkbox("Paper trading", state.show_paper_trading)
+                    .on_toggle(Message::TogglePaperTrading)
+                    .size(14)
+                    .text_size(12),
+            )
+            .push(
+                widget::checkbox("Backtest", state.show_backtest)
+                    .on_toggle(Message::ToggleBacktest)
+                    .size(14)
+                    .text_size(12),
+            )
+            .push(render_timeframe_select(state).size(14.0).width(150.0))
+            .push(render_chart_export(state));
     }
 
     let layout = widget::row![
@@ -139,10 +579,45 @@ pub fn view(state: &State) -> Element<Message> {
                 right: 10.0,
             })
         ]
+        .push_maybe(depth_chart(state))
+        .push_maybe(
+            (state.show_paper_trading && state.displayed_symbol.is_some())
+                .then(|| render_paper_panel(state)),
+        )
+        .push_maybe(
+            (state.show_backtest && state.displayed_symbol.is_some())
+                .then(|| render_backtest_panel(state)),
+        )
+        .push_maybe(state.show_portfolio.then(|| render_portfolio_panel(state)))
         .spacing(10),
         vertical_rule(),
-        symbols_column,
     ];
 
-    Column::new().spacing(20).push(layout).into()
+    let layout = if state.displayed_symbol.is_some() {
+        layout
+            .push(widget::column![
+                order_book_panel(state),
+                horizontal_rule(),
+                trades_panel(state).padding(Padding {
+                    top: 10.0,
+                    right: 0.0,
+                    bottom: 10.0,
+                    left: 0.0,
+                }),
+            ])
+            .push(vertical_rule())
+    } else {
+        layout
+    };
+
+    let layout = layout.push(symbols_column);
+
+    let content = Column::new().spacing(20).push(layout);
+
+    match &state.command_palette {
+        Some(command_palette) => {
+            stack![content, render_command_palette(state, command_palette)].into()
+        }
+        None => content.into(),
+    }
 }
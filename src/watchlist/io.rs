@@ -0,0 +1,260 @@
+use crate::symbols::Symbol;
+use crate::watchlist::Watchlist;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+const TRADINGVIEW_EXCHANGE: &str = "BINANCE";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchlistFormat {
+    Json,
+    Csv,
+    TradingView,
+}
+
+impl WatchlistFormat {
+    pub fn from_path(path: &str) -> Option<Self> {
+        let extension = Path::new(path).extension()?.to_str()?.to_lowercase();
+
+        match extension.as_str() {
+            "json" => Some(WatchlistFormat::Json),
+            "csv" => Some(WatchlistFormat::Csv),
+            "txt" => Some(WatchlistFormat::TradingView),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchlistFile {
+    pub name: Option<String>,
+    pub timeframe: Option<String>,
+    pub symbols: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImportIssue {
+    Unknown(String),
+    NotTrading(String),
+    WrongExchange(String),
+    Duplicate(String),
+}
+
+impl fmt::Display for ImportIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImportIssue::Unknown(symbol) => write!(f, "{} is unknown", symbol),
+            ImportIssue::NotTrading(symbol) => write!(f, "{} is not trading", symbol),
+            ImportIssue::WrongExchange(symbol) => write!(f, "{} is not a Binance symbol", symbol),
+            ImportIssue::Duplicate(symbol) => write!(f, "{} is listed twice", symbol),
+        }
+    }
+}
+
+pub fn parse(format: WatchlistFormat, content: &str) -> Result<WatchlistFile, String> {
+    match format {
+        WatchlistFormat::Json => serde_json::from_str::<WatchlistFile>(content)
+            .map_err(|err| format!("Invalid watchlist JSON: {}", err)),
+        WatchlistFormat::Csv => Ok(parse_csv(content)),
+        WatchlistFormat::TradingView => Ok(parse_tradingview(content)),
+    }
+}
+
+fn parse_csv(content: &str) -> WatchlistFile {
+    let symbols = content
+        .lines()
+        .filter_map(|line| line.split(',').next())
+        .map(|cell| cell.trim().trim_matches('"').to_string())
+        .filter(|cell| !cell.is_empty() && !cell.eq_ignore_ascii_case("symbol"))
+        .collect();
+
+    WatchlistFile {
+        name: None,
+        timeframe: None,
+        symbols,
+    }
+}
+
+fn parse_tradingview(content: &str) -> WatchlistFile {
+    let mut name = None;
+    let mut symbols = Vec::new();
+
+    for entry in content.split([',', '\n']).map(str::trim) {
+        if entry.is_empty() {
+            continue;
+        }
+
+        // TradingView prefixes section headers with ###, the first one names the list.
+        if let Some(section) = entry.strip_prefix("###") {
+            name.get_or_insert_with(|| section.trim().to_string());
+            continue;
+        }
+
+        symbols.push(entry.to_string());
+    }
+
+    WatchlistFile {
+        name,
+        timeframe: None,
+        symbols,
+    }
+}
+
+/// Matches every imported entry against the known instruments, keeping the valid ones.
+pub fn validate(entries: &[String], instruments: &[Symbol]) -> (Vec<Symbol>, Vec<ImportIssue>) {
+    let mut symbols: Vec<Symbol> = Vec::new();
+    let mut issues = Vec::new();
+
+    for entry in entries {
+        let symbol = match entry.split_once(':') {
+            Some((exchange, symbol)) if exchange.eq_ignore_ascii_case(TRADINGVIEW_EXCHANGE) => {
+                symbol.to_uppercase()
+            }
+            Some(_) => {
+                issues.push(ImportIssue::WrongExchange(entry.clone()));
+                continue;
+            }
+            None => entry.to_uppercase(),
+        };
+
+        if symbols.iter().any(|s| s.symbol == symbol) {
+            issues.push(ImportIssue::Duplicate(symbol));
+            continue;
+        }
+
+        match instruments.iter().find(|i| i.symbol == symbol) {
+            Some(instrument) if instrument.is_trading() => symbols.push(instrument.clone()),
+            Some(_) => issues.push(ImportIssue::NotTrading(symbol)),
+            None => issues.push(ImportIssue::Unknown(symbol)),
+        }
+    }
+
+    (symbols, issues)
+}
+
+pub fn export(format: WatchlistFormat, watchlist: &Watchlist) -> Result<String, String> {
+    match format {
+        WatchlistFormat::Json => serde_json::to_string_pretty(&WatchlistFile {
+            name: Some(watchlist.name.clone()),
+            timeframe: Some(watchlist.timeframe.clone()),
+            symbols: watchlist.symbol_names(),
+        })
+        .map_err(|err| format!("Failed to serialize watchlist: {}", err)),
+        WatchlistFormat::Csv => {
+            let mut csv = String::from("symbol\n");
+            for symbol in &watchlist.symbols {
+                csv.push_str(&symbol.symbol);
+                csv.push('\n');
+            }
+            Ok(csv)
+        }
+        WatchlistFormat::TradingView => {
+            let symbols = watchlist
+                .symbols
+                .iter()
+                .map(|s| format!("{}:{}", TRADINGVIEW_EXCHANGE, s.symbol))
+                .collect::<Vec<_>>()
+                .join(",");
+            Ok(format!("###{},{}", watchlist.name, symbols))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::pair;
+
+    fn watchlist() -> Watchlist {
+        let mut watchlist = Watchlist::new("Majors".to_string(), "4h".to_string());
+        watchlist.symbols = vec![pair("BTC", "USDT", 2), pair("ETH", "USDT", 2)];
+        watchlist
+    }
+
+    #[test]
+    fn every_format_reads_back_what_it_wrote() {
+        let watchlist = watchlist();
+
+        for (format, name, timeframe) in [
+            (WatchlistFormat::Json, Some("Majors"), Some("4h")),
+            (WatchlistFormat::Csv, None, None),
+            (WatchlistFormat::TradingView, Some("Majors"), None),
+        ] {
+            let file = parse(format, &export(format, &watchlist).unwrap()).unwrap();
+            let (symbols, issues) = validate(&file.symbols, &watchlist.symbols);
+
+            assert_eq!(file.name.as_deref(), name, "{:?}", format);
+            assert_eq!(file.timeframe.as_deref(), timeframe, "{:?}", format);
+            assert_eq!(
+                symbols
+                    .iter()
+                    .map(|s| s.symbol.as_str())
+                    .collect::<Vec<_>>(),
+                ["BTCUSDT", "ETHUSDT"],
+                "{:?}",
+                format
+            );
+            assert!(issues.is_empty(), "{:?}: {:?}", format, issues);
+        }
+    }
+
+    #[test]
+    fn reads_files_written_elsewhere() {
+        let csv = parse(
+            WatchlistFormat::Csv,
+            "Symbol,Note\n\"btcusdt\",long\n\n ETHUSDT ,\n",
+        )
+        .unwrap();
+        assert_eq!(csv.symbols, ["btcusdt", "ETHUSDT"]);
+
+        let tradingview = parse(
+            WatchlistFormat::TradingView,
+            "###Alts,BINANCE:SOLUSDT\n###Later,NASDAQ:AAPL,\n",
+        )
+        .unwrap();
+        assert_eq!(tradingview.name.as_deref(), Some("Alts"));
+        assert_eq!(tradingview.symbols, ["BINANCE:SOLUSDT", "NASDAQ:AAPL"]);
+
+        let err = parse(WatchlistFormat::Json, "{\"symbols\": \"BTCUSDT\"}").unwrap_err();
+        assert!(err.starts_with("Invalid watchlist JSON"), "{}", err);
+        assert!(parse(WatchlistFormat::Json, "").is_err());
+
+        assert_eq!(
+            WatchlistFormat::from_path("list.TXT"),
+            Some(WatchlistFormat::TradingView)
+        );
+        assert_eq!(WatchlistFormat::from_path("list.xlsx"), None);
+        assert_eq!(WatchlistFormat::from_path("list"), None);
+    }
+
+    #[test]
+    fn reports_every_entry_it_cannot_import() {
+        let mut halted = pair("LUNA", "USDT", 4);
+        halted.status = "BREAK".to_string();
+        let instruments = [pair("BTC", "USDT", 2), halted];
+        let entries = [
+            "binance:btcusdt",
+            "BTCUSDT",
+            "LUNAUSDT",
+            "NASDAQ:AAPL",
+            "XYZ",
+        ]
+        .map(String::from);
+
+        let (symbols, issues) = validate(&entries, &instruments);
+
+        assert_eq!(symbols.len(), 1);
+        assert_eq!(symbols[0].symbol, "BTCUSDT");
+        assert_eq!(
+            issues,
+            [
+                ImportIssue::Duplicate("BTCUSDT".to_string()),
+                ImportIssue::NotTrading("LUNAUSDT".to_string()),
+                ImportIssue::WrongExchange("NASDAQ:AAPL".to_string()),
+                ImportIssue::Unknown("XYZ".to_string()),
+            ]
+        );
+        assert_eq!(issues[1].to_string(), "LUNAUSDT is not trading");
+    }
+}
@@ -0,0 +1,204 @@
+pub mod io;
+pub mod tick;
+
+use crate::symbols::{Symbol, SymbolWithPrice};
+use rust_decimal::Decimal;
+use std::time::Instant;
+use tick::PriceTick;
+
+#[derive(Debug, Clone)]
+pub struct Watchlist {
+    pub name: String,
+    pub symbols: Vec<Symbol>,
+    pub timeframe: String,
+}
+
+impl Watchlist {
+    pub fn new(name: String, timeframe: String) -> Self {
+        Self {
+            name,
+            symbols: Vec::new(),
+            timeframe,
+        }
+    }
+
+    pub fn symbol_names(&self) -> Vec<String> {
+        self.symbols.iter().map(|s| s.symbol.clone()).collect()
+    }
+
+    /// Adds a known instrument that is trading and not listed yet, keeping the list sorted.
+    pub fn add(&mut self, instruments: &[Symbol], symbol: &str) -> Result<&Symbol, String> {
+        let Some(instrument) = instruments.iter().find(|s| s.symbol == symbol) else {
+            return Err(format!("{} is not a known instrument", symbol));
+        };
+
+        if !instrument.is_trading() {
+            return Err(format!("{} is not trading", symbol));
+        }
+
+        if self.symbols.iter().any(|s| s.symbol == symbol) {
+            return Err(format!("{} is already in the watchlist", symbol));
+        }
+
+        let index = self.symbols.partition_point(|s| s.symbol.as_str() < symbol);
+        self.symbols.insert(
+            index,
+            Symbol {
+                timeframe: self.timeframe.clone(),
+                ..instrument.clone()
+            },
+        );
+
+        Ok(&self.symbols[index])
+    }
+
+    /// Removes the symbol and returns the one now in its place, the new last one when it
+    /// was at the end.
+    pub fn remove(&mut self, symbol: &str) -> Option<&Symbol> {
+        let index = self.symbols.iter().position(|s| s.symbol == symbol)?;
+        self.symbols.remove(index);
+
+        self.symbols
+            .get(index.min(self.symbols.len().saturating_sub(1)))
+    }
+
+    /// Sets the fetched prices at each symbol's decimals, returns the ticks of those that moved.
+    pub fn apply_prices(
+        &mut self,
+        prices: &[SymbolWithPrice],
+        now: Instant,
+    ) -> Vec<(String, PriceTick)> {
+        let mut ticks = Vec::new();
+
+        for item in self.symbols.iter_mut() {
+            let Some(mut price) = prices
+                .iter()
+                .find(|p| p.symbol == item.symbol)
+                .and_then(|p| p.price.parse::<Decimal>().ok())
+            else {
+                continue;
+            };
+            price.rescale(item.decimals);
+
+            if let Some(previous) = item.price
+                && let Some(tick) = PriceTick::between(previous, price, now)
+            {
+                ticks.push((item.symbol.clone(), tick));
+            }
+
+            item.price = Some(price);
+        }
+
+        ticks
+    }
+}
+
+pub fn unique_name(watchlists: &[Watchlist], base: &str) -> String {
+    let base = base.trim();
+    let base = if base.is_empty() { "Watchlist" } else { base };
+
+    if !watchlists.iter().any(|w| w.name == base) {
+        return base.to_string();
+    }
+
+    (2..)
+        .map(|i| format!("{} {}", base, i))
+        .find(|name| !watchlists.iter().any(|w| &w.name == name))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::pair;
+    use crate::symbols::TRADING_STATUS;
+    use rust_decimal::dec;
+    use tick::TickDirection;
+
+    fn instrument(base: &str, status: &str) -> Symbol {
+        Symbol {
+            status: status.to_string(),
+            ..pair(base, "USDT", 2)
+        }
+    }
+
+    fn price(symbol: &str, price: &str) -> SymbolWithPrice {
+        SymbolWithPrice {
+            symbol: symbol.to_string(),
+            price: price.to_string(),
+        }
+    }
+
+    #[test]
+    fn adds_trading_instruments_once_in_order() {
+        let instruments = [
+            instrument("ETH", TRADING_STATUS),
+            instrument("BTC", TRADING_STATUS),
+            instrument("LUNA", "BREAK"),
+        ];
+        let mut watchlist = Watchlist::new("Main".to_string(), "15m".to_string());
+
+        assert_eq!(
+            watchlist.add(&instruments, "ETHUSDT").unwrap().timeframe,
+            "15m"
+        );
+        assert!(watchlist.add(&instruments, "BTCUSDT").is_ok());
+        assert_eq!(
+            watchlist.add(&instruments, "BTCUSDT").unwrap_err(),
+            "BTCUSDT is already in the watchlist"
+        );
+        assert_eq!(
+            watchlist.add(&instruments, "LUNAUSDT").unwrap_err(),
+            "LUNAUSDT is not trading"
+        );
+        assert_eq!(
+            watchlist.add(&instruments, "XYZUSDT").unwrap_err(),
+            "XYZUSDT is not a known instrument"
+        );
+        assert_eq!(watchlist.symbol_names(), ["BTCUSDT", "ETHUSDT"]);
+    }
+
+    #[test]
+    fn removing_moves_to_the_next_symbol() {
+        let mut watchlist = Watchlist::new("Main".to_string(), "1h".to_string());
+        for base in ["A", "B", "C"] {
+            watchlist.symbols.push(instrument(base, TRADING_STATUS));
+        }
+
+        assert_eq!(
+            watchlist.remove("BUSDT").map(|s| s.symbol.as_str()),
+            Some("CUSDT")
+        );
+        assert_eq!(
+            watchlist.remove("CUSDT").map(|s| s.symbol.as_str()),
+            Some("AUSDT")
+        );
+        assert!(watchlist.remove("CUSDT").is_none());
+        assert!(watchlist.remove("AUSDT").is_none());
+        assert!(watchlist.symbols.is_empty());
+    }
+
+    #[test]
+    fn applies_prices_and_reports_moves() {
+        let mut watchlist = Watchlist::new("Main".to_string(), "1h".to_string());
+        watchlist
+            .symbols
+            .push(instrument("BTC", TRADING_STATUS));
+        watchlist
+            .symbols
+            .push(instrument("ETH", TRADING_STATUS));
+        let now = Instant::now();
+
+        let ticks = watchlist.apply_prices(&[price("BTCUSDT", "100.5")], now);
+        assert!(ticks.is_empty());
+        assert_eq!(watchlist.symbols[0].price, Some(dec!(100.50)));
+        assert_eq!(watchlist.symbols[1].price, None);
+
+        let ticks =
+            watchlist.apply_prices(&[price("BTCUSDT", "99.123"), price("ETHUSDT", "nope")], now);
+        assert_eq!(ticks.len(), 1);
+        assert_eq!(ticks[0].0, "BTCUSDT");
+        assert_eq!(ticks[0].1.direction, TickDirection::Down);
+        assert_eq!(watchlist.symbols[0].price.unwrap().to_string(), "99.12");
+    }
+}
@@ -0,0 +1,59 @@
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+pub const FLASH_DURATION: Duration = Duration::from_millis(600);
+pub const FLASH_FRAME: Duration = Duration::from_millis(50);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TickDirection {
+    Up,
+    Down,
+}
+
+#[derive(Debug, Clone)]
+pub struct PriceTick {
+    pub previous: Decimal,
+    pub direction: TickDirection,
+    pub changed_at: Instant,
+}
+
+impl PriceTick {
+    /// Returns `None` when the price did not move, so the last tick stays in place.
+    pub fn between(previous: Decimal, current: Decimal, now: Instant) -> Option<Self> {
+        let direction = if current > previous {
+            TickDirection::Up
+        } else if current < previous {
+            TickDirection::Down
+        } else {
+            return None;
+        };
+
+        Some(Self {
+            previous,
+            direction,
+            changed_at: now,
+        })
+    }
+
+    pub fn flash_intensity(&self, now: Instant) -> f32 {
+        let elapsed = now.saturating_duration_since(self.changed_at);
+
+        if elapsed >= FLASH_DURATION {
+            return 0.0;
+        }
+
+        1.0 - elapsed.as_secs_f32() / FLASH_DURATION.as_secs_f32()
+    }
+
+    pub fn arrow(&self) -> &'static str {
+        match self.direction {
+            TickDirection::Up => "▲",
+            TickDirection::Down => "▼",
+        }
+    }
+}
+
+pub fn is_flashing(ticks: &HashMap<String, PriceTick>, now: Instant) -> bool {
+    ticks.values().any(|tick| tick.flash_intensity(now) > 0.0)
+}
@@ -0,0 +1,44 @@
+pub mod depth;
+pub mod trades;
+
+use crate::Message;
+use juicy_lemon::config;
+use iced::futures::channel::mpsc::Sender;
+use iced::futures::{SinkExt, StreamExt};
+use std::time::Duration;
+use tokio_tungstenite::{connect_async, tungstenite};
+
+pub const RECONNECT_DELAY: Duration = Duration::from_secs(3);
+
+/// Forwards every parsed message of a Binance market stream until the connection drops.
+pub async fn forward_stream(
+    stream: &str,
+    output: &mut Sender<Message>,
+    parse: impl Fn(&str) -> Option<Message>,
+) {
+    let url = format!("{}/ws/{}", config::get().api.stream(), stream);
+
+    let mut socket = match connect_async(url.as_str()).await {
+        Ok((socket, _)) => socket,
+        Err(err) => {
+            println!("Cannot connect {} stream: {}", stream, err);
+            return;
+        }
+    };
+
+    while let Some(message) = socket.next().await {
+        let text = match message {
+            Ok(tungstenite::Message::Text(text)) => text,
+            Ok(tungstenite::Message::Close(_)) => break,
+            Ok(_) => continue,
+            Err(err) => {
+                println!("{} stream error: {}", stream, err);
+                break;
+            }
+        };
+
+        if let Some(message) = parse(&text) {
+            let _ = output.send(message).await;
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use crate::Message;
+use crate::websocket::{RECONNECT_DELAY, forward_stream};
+use iced::futures::{SinkExt, Stream};
+use iced::stream;
+use juicy_lemon::orderbook::DepthUpdate;
+use juicy_lemon::orderbook::source::{DepthSource, read_lines};
+use std::time::Duration;
+
+const REPLAY_INTERVAL: Duration = Duration::from_millis(250);
+
+pub fn depth_updates(source: DepthSource, symbol: String) -> impl Stream<Item = Message> {
+    stream::channel(100, move |mut output| async move {
+        match source {
+            DepthSource::Binance => loop {
+                let stream = format!("{}@depth@100ms", symbol.to_lowercase());
+
+                forward_stream(&stream, &mut output, |text| {
+                    match serde_json::from_str::<DepthUpdate>(text) {
+                        Ok(update) => Some(Message::DepthUpdated(update)),
+                        Err(err) => {
+                            println!("Error parsing depth update: {}", err);
+                            None
+                        }
+                    }
+                })
+                .await;
+
+                let _ = output
+                    .send(Message::DepthDisconnected(symbol.clone()))
+                    .await;
+                tokio::time::sleep(RECONNECT_DELAY).await;
+            },
+            DepthSource::Replay(dir) => {
+                let lines = match read_lines(dir.join("updates.jsonl")) {
+                    Ok(lines) => lines,
+                    Err(err) => {
+                        println!("{}", err);
+                        Vec::new()
+                    }
+                };
+
+                for line in lines {
+                    tokio::time::sleep(REPLAY_INTERVAL).await;
+
+                    match serde_json::from_str::<DepthUpdate>(&line) {
+                        Ok(mut update) => {
+                            update.symbol = symbol.clone();
+                            let _ = output.send(Message::DepthUpdated(update)).await;
+                        }
+                        Err(err) => println!("Invalid replay update: {}", err),
+                    }
+                }
+
+                iced::futures::future::pending::<()>().await;
+            }
+        }
+    })
+}
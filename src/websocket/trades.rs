@@ -0,0 +1,26 @@
+use crate::Message;
+use crate::websocket::{RECONNECT_DELAY, forward_stream};
+use iced::futures::Stream;
+use iced::stream;
+use juicy_lemon::trades::AggTrade;
+
+pub fn trade_updates(symbol: String) -> impl Stream<Item = Message> {
+    stream::channel(100, move |mut output| async move {
+        loop {
+            let stream = format!("{}@aggTrade", symbol.to_lowercase());
+
+            forward_stream(&stream, &mut output, |text| {
+                match serde_json::from_str::<AggTrade>(text) {
+                    Ok(trade) => Some(Message::TradeReceived(symbol.clone(), trade)),
+                    Err(err) => {
+                        println!("Error parsing trade: {}", err);
+                        None
+                    }
+                }
+            })
+            .await;
+
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    })
+}
@@ -0,0 +1,54 @@
+use juicy_lemon::backtest::{Backtester, parse_strategy};
+use juicy_lemon::dataset::parse_candles;
+use rust_decimal::prelude::*;
+use std::fs;
+use std::path::PathBuf;
+
+/// Runs a strategy over the daily fixture twice without the network, the same candles
+/// must give the same report.
+#[test]
+fn backtest_over_csv_is_reproducible() {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("data/btcusd.csv");
+    let candles = parse_candles(&fs::read_to_string(path).unwrap()).unwrap();
+    let backtester = Backtester {
+        initial_cash: dec!(10000),
+        fee_rate: dec!(0.001),
+        slippage: dec!(0.0005),
+    };
+
+    let run = || {
+        backtester
+            .run(
+                "BTCUSD",
+                &candles,
+                &mut *parse_strategy("sma:5,20").unwrap(),
+            )
+            .unwrap()
+    };
+    let report = run();
+    let again = run();
+
+    assert_eq!(candles.len(), 150);
+    assert_eq!(report.interval, 86_400_000);
+    assert_eq!(report.equity, again.equity);
+    assert_eq!(report.trades, again.trades);
+    assert_eq!(report.summary(), again.summary());
+
+    assert_eq!(report.equity.len(), candles.len());
+    assert!(!report.trades.is_empty());
+    assert_eq!(report.account.fills.len(), report.trades.len() * 2);
+
+    // Every trade enters at an open after the signal's close, and the equity only
+    // changes by the trades' P&L.
+    for trade in &report.trades {
+        assert!(trade.entry_time < trade.exit_time);
+        assert!(candles.iter().any(|c| c.open_time == trade.entry_time));
+        assert!(trade.fees > Decimal::ZERO);
+    }
+    let pnl = report.trades.iter().map(|t| t.pnl).sum::<Decimal>();
+    assert_eq!(report.final_equity(), dec!(10000) + pnl);
+
+    assert!(report.max_drawdown() >= Decimal::ZERO);
+    assert!(report.win_rate().is_some());
+    assert!(report.sharpe().is_some_and(f64::is_finite));
+}
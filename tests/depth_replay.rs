@@ -0,0 +1,37 @@
+use juicy_lemon::orderbook::source::{DepthSource, get_depth_snapshot, read_lines};
+use juicy_lemon::orderbook::{DepthUpdate, OrderBookSync};
+use rust_decimal::dec;
+use std::path::PathBuf;
+
+/// Feeds the recorded fixture through the sync the way the app does, fetching a snapshot
+/// as soon as one is asked for.
+#[test]
+fn replay_fixture_resyncs_after_gap() {
+    let dir = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("data/depth");
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mut sync = OrderBookSync::new("BTCUSDT".to_string());
+
+    for line in read_lines(dir.join("updates.jsonl")).unwrap() {
+        let update = serde_json::from_str::<DepthUpdate>(&line).unwrap();
+        // The fixture has a gap on purpose, it needs a snapshot like any other request.
+        let mut needs_snapshot = sync.on_update(update).unwrap_or(true);
+
+        while needs_snapshot {
+            let snapshot = runtime
+                .block_on(get_depth_snapshot(
+                    DepthSource::Replay(dir.clone()),
+                    sync.symbol.clone(),
+                    sync.snapshot_requests - 1,
+                ))
+                .unwrap();
+            needs_snapshot = sync.on_snapshot(&snapshot);
+        }
+    }
+
+    let book = sync.book.as_ref().expect("book is synced");
+
+    assert_eq!(sync.snapshot_requests, 2);
+    assert_eq!(book.last_update_id, 1020);
+    assert!(book.best_bid() < book.best_ask());
+    assert!(book.mid_price().is_some_and(|mid| mid > dec!(0)));
+}